@@ -1,80 +1,54 @@
+use crate::history::{self, HostUsageEntry};
 use crate::settings::{self, AppSettings};
-use crate::ssh::config::{parse_ssh_config, ssh_config_path};
-use crate::ssh::types::SshHost;
-use glob::glob;
+use crate::ssh::backup::{
+    atomic_write, backup_file, content_version, list_backups, modified_millis, read_backup,
+};
+use crate::ssh::client::{check_content as check_with_ssh_client, resolve_with_client};
+use crate::ssh::config::{
+    config_roots, find_host_root, is_allowed_config_path, parse_ssh_config,
+    parse_ssh_host_patterns, roots_including, ssh_config_path,
+};
+use crate::ssh::cst::{move_host, ConfigDocument};
+use crate::ssh::diff::{merge3, unified_diff};
+use crate::ssh::edit::{create_host, duplicate_host, find_host_file, host_definition, update_host};
+use crate::ssh::fix::{apply_fixes, attach_content_fixes};
+use crate::ssh::group::{default_group_rules, validate_group_rules};
+use crate::ssh::include::{
+    include_base_dir, load_config_file, load_config_file_with, parse_config_content,
+};
+use crate::ssh::keywords::{installed_version, known_versions, Version};
+use crate::ssh::launch::SshLaunch;
+use crate::ssh::lint::{lint_configs, lint_rules, root_not_linted};
+use crate::ssh::profile::{launch_with_profile, profiles_for, validate_launch_profiles};
+use crate::ssh::resolve::{load_root_configs, resolve_host, with_following_configs};
+use crate::ssh::search::search_hosts;
+use crate::ssh::terminal::{
+    detect_installed_terminals, open_in_terminal, validate_terminal_template, CUSTOM_TERMINAL,
+};
+use crate::ssh::types::{
+    ChmodFix, ConfigBackup, ConfigFixResult, ConfigSnapshot, Diagnostic, GroupRule, HostMatch,
+    LaunchProfile, LintRuleInfo, OpenSshVersions, ResolvedHost, SaveConflict, SaveOutcome,
+    Severity, SshHost, SshHostDefinition, SshHostPattern, SuggestedFix, TerminalInfo,
+};
+use crate::ssh::validate::{format_diagnostic, has_errors, validate_content};
+use crate::ssh::workspace::{open_workspace as open_ssh_workspace, validate_workspaces};
 use serde::Serialize;
-use ssh2_config::{ParseRule, SshConfig};
 use std::collections::HashSet;
 use std::fs;
-use std::io::BufReader;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Clone)]
 pub struct SshConfigFile {
-    /// Display name (relative to ~/.ssh/)
+    /// Display name (relative to ~/.ssh/, otherwise the full path)
     pub name: String,
     /// Absolute path
     pub path: String,
-    /// Whether this is the main config file
+    /// Whether this is a top-level config file from the settings
     pub is_main: bool,
     /// Number of Host entries in this file
     pub host_count: usize,
 }
 
-/// Expand an Include pattern relative to ~/.ssh/
-fn expand_include_pattern(pattern: &str) -> Vec<PathBuf> {
-    let home = dirs::home_dir().unwrap_or_default();
-    let ssh_dir = home.join(".ssh");
-
-    let full_pattern = if pattern.starts_with('/') || pattern.starts_with('~') {
-        pattern.replace('~', &home.to_string_lossy())
-    } else {
-        ssh_dir.join(pattern).to_string_lossy().to_string()
-    };
-
-    let mut files = Vec::new();
-    if let Ok(paths) = glob(&full_pattern) {
-        for entry in paths.flatten() {
-            if entry.is_file() {
-                files.push(entry);
-            }
-        }
-    }
-    files.sort();
-    files
-}
-
-/// Parse Include directives from a config file and return all referenced files recursively
-fn collect_config_files(path: &Path, visited: &mut HashSet<PathBuf>) -> Vec<PathBuf> {
-    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-    if visited.contains(&canonical) || !path.exists() {
-        return Vec::new();
-    }
-    visited.insert(canonical);
-
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return Vec::new(),
-    };
-
-    let mut result = Vec::new();
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if trimmed.to_lowercase().starts_with("include") {
-            let parts: Vec<&str> = trimmed.splitn(2, char::is_whitespace).collect();
-            if parts.len() == 2 {
-                let pattern = parts[1].trim();
-                for inc_path in expand_include_pattern(pattern) {
-                    result.push(inc_path.clone());
-                    // Recurse into included files
-                    result.extend(collect_config_files(&inc_path, visited));
-                }
-            }
-        }
-    }
-    result
-}
-
 /// Count Host entries in a file
 fn count_hosts(content: &str) -> usize {
     content
@@ -86,46 +60,61 @@ fn count_hosts(content: &str) -> usize {
         .count()
 }
 
+/// Name a config file is shown under: relative to ~/.ssh/ when it is in
+/// there, otherwise its path with the home directory abbreviated to `~`
+fn display_name(path: &Path) -> String {
+    if let Ok(rel) = path.strip_prefix(include_base_dir()) {
+        return rel.to_string_lossy().to_string();
+    }
+    let home = dirs::home_dir().unwrap_or_default();
+    match path.strip_prefix(&home) {
+        Ok(rel) => format!("~/{}", rel.to_string_lossy()),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Every config root from the settings with the files it includes. Roots
+/// come first, in settings order; a file reachable twice is listed once.
 #[tauri::command]
 pub fn list_ssh_config_files() -> Result<Vec<SshConfigFile>, String> {
-    let main_path = ssh_config_path();
-    if !main_path.exists() {
-        return Err(format!("SSH config not found at: {}", main_path.display()));
+    let roots: Vec<PathBuf> = config_roots().into_iter().filter(|r| r.exists()).collect();
+    if roots.is_empty() {
+        return Err(format!(
+            "SSH config not found at: {}",
+            ssh_config_path().display()
+        ));
     }
 
-    let home = dirs::home_dir().unwrap_or_default();
-    let ssh_dir = home.join(".ssh");
-    let make_name = |p: &Path| -> String {
-        p.strip_prefix(&ssh_dir)
-            .map(|r| r.to_string_lossy().to_string())
-            .unwrap_or_else(|_| p.to_string_lossy().to_string())
-    };
-
     let mut files = Vec::new();
-
-    // Main config
-    let main_content =
-        fs::read_to_string(&main_path).map_err(|e| format!("Failed to read SSH config: {}", e))?;
-    files.push(SshConfigFile {
-        name: "config".to_string(),
-        path: main_path.to_string_lossy().to_string(),
-        is_main: true,
-        host_count: count_hosts(&main_content),
-    });
-
-    // Collect included files
-    let mut visited = HashSet::new();
-    let includes = collect_config_files(&main_path, &mut visited);
-    for inc_path in includes {
-        let content = fs::read_to_string(&inc_path).unwrap_or_default();
+    let mut seen = HashSet::new();
+    for root in &roots {
+        let content =
+            fs::read_to_string(root).map_err(|e| format!("Failed to read SSH config: {}", e))?;
+        seen.insert(root.clone());
         files.push(SshConfigFile {
-            name: make_name(&inc_path),
-            path: inc_path.to_string_lossy().to_string(),
-            is_main: false,
+            name: display_name(root),
+            path: root.to_string_lossy().to_string(),
+            is_main: true,
             host_count: count_hosts(&content),
         });
     }
 
+    // Collect included files
+    for root in &roots {
+        for inc_path in load_config_file(root)?.included_files() {
+            if !seen.insert(inc_path.clone()) {
+                continue;
+            }
+            let content = fs::read_to_string(&inc_path).unwrap_or_default();
+            files.push(SshConfigFile {
+                name: display_name(&inc_path),
+                path: inc_path.to_string_lossy().to_string(),
+                is_main: false,
+                host_count: count_hosts(&content),
+            });
+        }
+    }
+
     Ok(files)
 }
 
@@ -134,101 +123,156 @@ pub fn list_ssh_hosts() -> Result<Vec<SshHost>, String> {
     parse_ssh_config()
 }
 
+/// Wildcard / negated Host blocks, which apply to other hosts
+#[tauri::command]
+pub fn list_ssh_host_patterns() -> Result<Vec<SshHostPattern>, String> {
+    parse_ssh_host_patterns()
+}
+
 #[tauri::command]
 pub fn refresh_ssh_config() -> Result<Vec<SshHost>, String> {
     parse_ssh_config()
 }
 
+/// Effective settings for a host alias, as `ssh -G` would report them.
+/// With the ssh client check enabled they come from `ssh -G` itself when
+/// it can be run.
 #[tauri::command]
-pub fn open_ssh_terminal(host: String, terminal: Option<String>) -> Result<(), String> {
-    let terminal = terminal.unwrap_or_else(|| "terminal".to_string());
-
-    #[cfg(target_os = "macos")]
-    {
-        if terminal == "warp" {
-            // Warp doesn't support AppleScript `do script`, and using
-            // System Events keystroke requires Accessibility permissions.
-            // Use a self-deleting .command file instead.
-            let tmp_path = format!("/tmp/ssh-m-connect-{}.command", std::process::id());
-            let script_content = format!("#!/bin/bash\nexec ssh {}\n", host);
-            std::fs::write(&tmp_path, &script_content)
-                .map_err(|e| format!("Failed to create temp script: {}", e))?;
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o755))
-                    .map_err(|e| format!("Failed to set script permissions: {}", e))?;
-            }
-            std::process::Command::new("open")
-                .args(["-a", "Warp", &tmp_path])
-                .spawn()
-                .map_err(|e| format!("Failed to open Warp: {}", e))?;
-        } else {
-            let script = match terminal.as_str() {
-                "iterm" => format!(
-                    r#"
-                    tell application "iTerm"
-                        activate
-                        create window with default profile command "ssh {}"
-                    end tell
-                    "#,
-                    host
-                ),
-                _ => format!(
-                    r#"
-                    tell application "Terminal"
-                        activate
-                        do script "ssh {}"
-                    end tell
-                    "#,
-                    host
-                ),
-            };
-
-            std::process::Command::new("osascript")
-                .arg("-e")
-                .arg(&script)
-                .spawn()
-                .map_err(|e| format!("Failed to open terminal: {}", e))?;
-        }
+pub fn resolve_ssh_host(alias: String) -> Result<ResolvedHost, String> {
+    let resolved = resolve_host(&alias)?;
+    if !settings::load_settings().ssh_client_check {
+        return Ok(resolved);
     }
+    let root = find_host_root(&alias).unwrap_or_else(ssh_config_path);
+    let configs = load_root_configs(&root).unwrap_or_default();
+    Ok(resolve_with_client(&alias, &root, &configs, &resolved).unwrap_or(resolved))
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        let _ = &terminal; // suppress unused warning
-        let terminals = ["gnome-terminal", "konsole", "xterm", "x-terminal-emulator"];
-        let mut launched = false;
-
-        for term in &terminals {
-            if std::process::Command::new(term)
-                .args(["--", "ssh", &host])
-                .spawn()
-                .is_ok()
-            {
-                launched = true;
-                break;
-            }
-        }
-
-        if !launched {
-            return Err("No terminal emulator found".to_string());
-        }
+/// Open `host` in `terminal` (the default terminal if not given), with
+/// the launch profile called `profile` if given
+#[tauri::command]
+pub fn open_ssh_terminal(
+    app: tauri::AppHandle,
+    host: String,
+    terminal: Option<String>,
+    profile: Option<String>,
+) -> Result<(), String> {
+    // The host comes from the webview: only known aliases and validated
+    // user@host:port targets are launched
+    let launch = launch_with_profile(&host, profile.as_deref())?;
+    let terminal = terminal.unwrap_or_else(|| settings::load_settings().default_terminal);
+    open_in_terminal(&terminal, &launch)?;
+    // History only keeps config aliases, the hosts the lists can show
+    if launch.is_explicit() {
+        return Ok(());
     }
+    let recorded = history::record_connection(&host);
+    // The tray lists recent hosts
+    crate::tray::refresh_tray_menu(&app);
+    recorded
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        let _ = &terminal; // suppress unused warning
-        std::process::Command::new("cmd")
-            .args(["/c", "start", "ssh", &host])
-            .spawn()
-            .map_err(|e| format!("Failed to open terminal: {}", e))?;
+/// Open the saved workspace called `name` in `terminal` (the default
+/// terminal if not given)
+#[tauri::command]
+pub fn open_workspace(
+    app: tauri::AppHandle,
+    name: String,
+    terminal: Option<String>,
+) -> Result<(), String> {
+    let settings = settings::load_settings();
+    let workspace = settings
+        .workspaces
+        .iter()
+        .find(|w| w.name == name)
+        .ok_or_else(|| format!("No workspace \"{}\"", name))?;
+    let terminal = terminal.unwrap_or(settings.default_terminal.clone());
+    open_ssh_workspace(workspace, &terminal)?;
+    // Like open_ssh_terminal, history only keeps config aliases
+    let recorded = workspace
+        .hosts
+        .iter()
+        .filter(|host| SshLaunch::for_target(host).is_ok_and(|l| !l.is_explicit()))
+        .try_for_each(|host| history::record_connection(host));
+    crate::tray::refresh_tray_menu(&app);
+    recorded
+}
+
+/// Launch profiles offered for the host `alias`
+#[tauri::command]
+pub fn list_launch_profiles(alias: String) -> Result<Vec<LaunchProfile>, String> {
+    let profiles = settings::load_settings().launch_profiles;
+    let hosts = parse_ssh_config()?;
+    let group = hosts
+        .iter()
+        .find(|h| h.name == alias)
+        .map(|h| h.group.as_str());
+    Ok(profiles_for(&profiles, &alias, group)
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// Terminals installed on this machine, for the default terminal setting
+#[tauri::command]
+pub fn detect_terminals() -> Vec<TerminalInfo> {
+    detect_installed_terminals()
+}
+
+/// Hosts matching `query`, ranked by how well they match and how often
+/// and lately they were connected to; at most `limit` if given
+#[tauri::command]
+pub fn search_ssh_hosts(query: String, limit: Option<usize>) -> Result<Vec<HostMatch>, String> {
+    let hosts = parse_ssh_config()?;
+    let mut matches = search_hosts(&hosts, &history::load_history(), &query);
+    if let Some(limit) = limit {
+        matches.truncate(limit);
     }
+    Ok(matches)
+}
 
-    Ok(())
+/// Entries returned by the history commands unless a limit is given
+const DEFAULT_HISTORY_LIMIT: usize = 10;
+
+/// Hosts connected to most recently, at most `limit` (10 by default)
+#[tauri::command]
+pub fn list_recent_hosts(limit: Option<usize>) -> Result<Vec<HostUsageEntry>, String> {
+    let hosts = parse_ssh_config()?;
+    let mut entries = history::load_history().recent(&hosts);
+    entries.truncate(limit.unwrap_or(DEFAULT_HISTORY_LIMIT));
+    Ok(entries)
+}
+
+/// Hosts connected to most often, at most `limit` (10 by default)
+#[tauri::command]
+pub fn list_most_used_hosts(limit: Option<usize>) -> Result<Vec<HostUsageEntry>, String> {
+    let hosts = parse_ssh_config()?;
+    let mut entries = history::load_history().most_used(&hosts);
+    entries.truncate(limit.unwrap_or(DEFAULT_HISTORY_LIMIT));
+    Ok(entries)
+}
+
+/// Pinned hosts, followed by those marked `favorite` in their metadata
+#[tauri::command]
+pub fn list_favorite_hosts() -> Result<Vec<HostUsageEntry>, String> {
+    let hosts = parse_ssh_config()?;
+    Ok(history::load_history().favorites(&hosts))
+}
+
+/// Pin `alias` to the favorites or unpin it; returns the new favorites
+#[tauri::command]
+pub fn set_ssh_host_pinned(
+    app: tauri::AppHandle,
+    alias: String,
+    pinned: bool,
+) -> Result<Vec<HostUsageEntry>, String> {
+    history::set_pinned(&alias, pinned)?;
+    crate::tray::refresh_tray_menu(&app);
+    list_favorite_hosts()
 }
 
 #[tauri::command]
-pub fn read_ssh_config(path: Option<String>) -> Result<String, String> {
+pub fn read_ssh_config(path: Option<String>) -> Result<ConfigSnapshot, String> {
     let config_path = match path {
         Some(p) => PathBuf::from(p),
         None => ssh_config_path(),
@@ -239,333 +283,526 @@ pub fn read_ssh_config(path: Option<String>) -> Result<String, String> {
             config_path.display()
         ));
     }
-    // Security: only allow reading files under ~/.ssh/
-    let home = dirs::home_dir().unwrap_or_default();
-    let ssh_dir = home.join(".ssh");
+    // Security: only allow reading files under ~/.ssh/ or configured config files
     let canonical = config_path
         .canonicalize()
         .map_err(|e| format!("Invalid path: {}", e))?;
-    if !canonical.starts_with(&ssh_dir) {
-        return Err("Only files under ~/.ssh/ can be read".to_string());
+    if !is_allowed_config_path(&canonical) {
+        return Err("Only files under ~/.ssh/ or configured config files can be read".to_string());
     }
-    fs::read_to_string(&canonical).map_err(|e| format!("Failed to read SSH config: {}", e))
-}
-
-#[tauri::command]
-pub fn validate_ssh_config(content: String) -> Result<Vec<String>, String> {
-    let mut warnings = Vec::new();
-
-    // Known SSH config keywords (case-insensitive)
-    let known_keywords: HashSet<&str> = [
-        "host",
-        "match",
-        "hostname",
-        "user",
-        "port",
-        "identityfile",
-        "identitiesonly",
-        "forwardagent",
-        "proxyjump",
-        "proxycommand",
-        "localforward",
-        "remoteforward",
-        "serveraliveinterval",
-        "serveralivecountmax",
-        "stricthostkeychecking",
-        "userknownhostsfile",
-        "loglevel",
-        "compression",
-        "connecttimeout",
-        "connectionattempts",
-        "addkeystoagent",
-        "identityagent",
-        "pubkeyauthentication",
-        "preferredauthentications",
-        "batchmode",
-        "checkhostip",
-        "ciphers",
-        "controlmaster",
-        "controlpath",
-        "controlpersist",
-        "dynamicforward",
-        "escapechar",
-        "exitonforwardfailure",
-        "fingerprinthash",
-        "gatewayports",
-        "globalknownhostsfile",
-        "gssapiauthentication",
-        "gssapidelegatecredentials",
-        "hashknownhosts",
-        "hostbasedauthentication",
-        "hostkeyalgorithms",
-        "hostkeyalias",
-        "include",
-        "ipqos",
-        "kbdinteractiveauthentication",
-        "kexalgorithms",
-        "localcommand",
-        "macs",
-        "numberofpasswordprompts",
-        "passwordauthentication",
-        "permitlocalcommand",
-        "pkcs11provider",
-        "protocol",
-        "pubkeyacceptedalgorithms",
-        "rekeylimit",
-        "requesttty",
-        "sendenv",
-        "setenv",
-        "tcpkeepalive",
-        "tunnel",
-        "tunneldevice",
-        "updatehostkeys",
-        "verifyhostkeydns",
-        "visualhostkey",
-        "xauthlocation",
-        "canonicaldomains",
-        "canonicalizefallbacklocal",
-        "canonicalizehostname",
-        "canonicalizemaxdots",
-        "canonicalizepermittedcnames",
-        "casignaturealgorithms",
-        "certificatefile",
-        "addressfamily",
-        "bindaddress",
-        "bindinterface",
-        "canonicaldomains",
-        "nohostauthenticationforlocalhost",
-        "permitremoteopen",
-        "proxyusefdpass",
-        "revokedhostkeys",
-        "securitykeyprovider",
-        "streamlocalbindmask",
-        "streamlocalbindunlink",
-        "syslogfacility",
-        "tag",
-        "ignorehostkeys",
-    ]
-    .iter()
-    .copied()
-    .collect();
-
-    let bool_keywords: HashSet<&str> = [
-        "forwardagent",
-        "identitiesonly",
-        "compression",
-        "batchmode",
-        "checkhostip",
-        "exitonforwardfailure",
-        "gatewayports",
-        "gssapiauthentication",
-        "gssapidelegatecredentials",
-        "hashknownhosts",
-        "hostbasedauthentication",
-        "passwordauthentication",
-        "permitlocalcommand",
-        "pubkeyauthentication",
-        "tcpkeepalive",
-        "visualhostkey",
-        "canonicalizefallbacklocal",
-        "nohostauthenticationforlocalhost",
-        "streamlocalbindunlink",
-    ]
-    .iter()
-    .copied()
-    .collect();
-
-    // Line-by-line validation
-    for (i, line) in content.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
+    let content =
+        fs::read_to_string(&canonical).map_err(|e| format!("Failed to read SSH config: {}", e))?;
+    Ok(ConfigSnapshot {
+        path: config_path.to_string_lossy().to_string(),
+        version: content_version(&content),
+        modified: modified_millis(&canonical),
+        content,
+    })
+}
+
+/// Check config content for `path` (the main config by default): the
+/// content itself, then the enabled lint rules over it and the files it
+/// includes, and the local ssh client if enabled in the settings
+#[tauri::command]
+pub fn validate_ssh_config(
+    content: String,
+    path: Option<String>,
+) -> Result<Vec<Diagnostic>, String> {
+    let path = path.map(PathBuf::from).unwrap_or_else(ssh_config_path);
+    let mut diagnostics = check_config(&content, &path);
+    let reported_by_client = ssh_client_diagnostics(&content, &path, &diagnostics);
+    diagnostics.extend(reported_by_client);
+    Ok(diagnostics)
+}
+
+/// Built-in checks of `content` as the config at `path`, with the fixes
+/// that apply to them
+fn check_config(content: &str, path: &Path) -> Vec<Diagnostic> {
+    let file = path.to_string_lossy().to_string();
+    let mut diagnostics = validate_content(content, Some(&file), target_version());
+    diagnostics.extend(lint_content(content, path));
+    attach_content_fixes(content, &file, &mut diagnostics);
+    diagnostics
+}
+
+/// What `ssh -G` reports about `content` as the config at `path`, when
+/// enabled in the settings. Problems on lines `reported` already covers
+/// at the same or a higher severity are left out.
+fn ssh_client_diagnostics(content: &str, path: &Path, reported: &[Diagnostic]) -> Vec<Diagnostic> {
+    if !settings::load_settings().ssh_client_check {
+        return Vec::new();
+    }
+    let parsed = parse_config_content(path, content);
+    check_with_ssh_client(content, path, &parsed)
+        .into_iter()
+        .filter(|d| {
+            d.line == 0
+                || !reported
+                    .iter()
+                    .any(|r| r.file == d.file && r.line == d.line && r.severity <= d.severity)
+        })
+        .collect()
+}
+
+/// Most rounds of fixes `apply_config_fixes` makes. Fixes that overlap
+/// are left to the next round, and one fix can reveal another.
+const MAX_FIX_ROUNDS: usize = 5;
 
-        // Split on whitespace or '='
-        let parts: Vec<&str> = trimmed
-            .splitn(2, |c: char| c.is_whitespace() || c == '=')
+/// Apply the suggested fixes for `content` as the config at `path`,
+/// optionally only those for the diagnostic codes in `codes`. The patched
+/// text is returned for the editor rather than saved. Key file permissions
+/// are left to `fix_key_file_permissions`.
+#[tauri::command]
+pub fn apply_config_fixes(
+    content: String,
+    path: Option<String>,
+    codes: Option<Vec<String>>,
+) -> Result<ConfigFixResult, String> {
+    let path = path.map(PathBuf::from).unwrap_or_else(ssh_config_path);
+    let file = path.to_string_lossy().to_string();
+    let mut current = content.clone();
+    let mut applied = Vec::new();
+
+    for _ in 0..MAX_FIX_ROUNDS {
+        let diagnostics = check_config(&current, &path);
+        let selected: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|d| d.suggested_fix.is_some())
+            .filter(|d| codes.as_ref().is_none_or(|c| c.contains(&d.code)))
             .collect();
-        let keyword = parts[0];
-        let keyword_lower = keyword.to_lowercase();
 
-        // Check if keyword is known
-        if !known_keywords.contains(keyword_lower.as_str()) {
-            warnings.push(format!("第 {} 行: 未知指令 \"{}\"", i + 1, keyword));
+        // Text edits only apply to this file; other files' problems are
+        // fixed when they are opened
+        let text_fixes: Vec<&Diagnostic> = selected
+            .into_iter()
+            .filter(|d| d.file.as_deref() == Some(&file))
+            .filter(|d| {
+                d.suggested_fix
+                    .as_ref()
+                    .is_some_and(|f| !f.edits.is_empty())
+            })
+            .collect();
+        let fixes: Vec<&SuggestedFix> = text_fixes
+            .iter()
+            .filter_map(|d| d.suggested_fix.as_ref())
+            .collect();
+        let (next, fixed) = apply_fixes(&current, &fixes);
+        if fixed.is_empty() {
+            break;
         }
+        applied.extend(fixed.into_iter().map(|i| text_fixes[i].clone()));
+        current = next;
+    }
+
+    let label = display_name(&path);
+    Ok(ConfigFixResult {
+        diff: unified_diff(&content, &current, &label, &label),
+        content: current,
+        applied,
+    })
+}
 
-        // Check for missing value
-        let value = parts.get(1).map(|v| v.trim()).unwrap_or("");
-        if value.is_empty() && !keyword_lower.eq("match") {
-            warnings.push(format!("第 {} 行: \"{}\" 缺少值", i + 1, keyword));
+/// Restrict the permissions of the key files in `files`, each of which
+/// must be offered as a fix by the checks of `content` as the config at
+/// `path`. Returns the files that were changed.
+#[tauri::command]
+pub fn fix_key_file_permissions(
+    content: String,
+    path: Option<String>,
+    files: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let path = path.map(PathBuf::from).unwrap_or_else(ssh_config_path);
+    let offered: Vec<ChmodFix> = check_config(&content, &path)
+        .into_iter()
+        .filter_map(|d| d.suggested_fix.and_then(|f| f.chmod))
+        .collect();
+
+    let mut changed = Vec::new();
+    for file in files {
+        if changed.contains(&file) {
             continue;
         }
+        let Some(chmod) = offered.iter().find(|c| c.path == file) else {
+            return Err(format!("No permission fix is offered for {}", file));
+        };
+        set_file_mode(Path::new(&chmod.path), chmod.mode)?;
+        changed.push(file);
+    }
+    Ok(changed)
+}
 
-        // Validate Port range
-        if keyword_lower == "port" {
-            match value.parse::<u16>() {
-                Ok(0) => warnings.push(format!("第 {} 行: Port 不能为 0", i + 1)),
-                Err(_) => warnings.push(format!(
-                    "第 {} 行: Port 值 \"{}\" 不是有效端口号 (1-65535)",
-                    i + 1,
-                    value
-                )),
-                _ => {}
-            }
-        }
+/// Set the mode of the regular file at `path`; symlinks are refused so
+/// the change can't land on another file
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let meta = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !meta.is_file() {
+        return Err(format!("Not a regular file: {}", path.display()));
+    }
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("Failed to change permissions of {}: {}", path.display(), e))
+}
 
-        // Validate ServerAliveInterval / ServerAliveCountMax / ConnectTimeout
-        if [
-            "serveraliveinterval",
-            "serveralivecountmax",
-            "connecttimeout",
-            "connectionattempts",
-            "numberofpasswordprompts",
-        ]
-        .contains(&keyword_lower.as_str())
-        {
-            if value.parse::<u32>().is_err() {
-                warnings.push(format!(
-                    "第 {} 行: {} 值 \"{}\" 应为正整数",
-                    i + 1,
-                    keyword,
-                    value
-                ));
-            }
-        }
+#[cfg(not(unix))]
+fn set_file_mode(_path: &Path, _mode: u32) -> Result<(), String> {
+    Ok(())
+}
 
-        // Validate boolean fields
-        if bool_keywords.contains(keyword_lower.as_str()) {
-            if !["yes", "no"].contains(&value.to_lowercase().as_str()) {
-                warnings.push(format!(
-                    "第 {} 行: {} 值 \"{}\" 应为 yes 或 no",
-                    i + 1,
-                    keyword,
-                    value
-                ));
-            }
-        }
+/// Diagnostics for content about to be written, or an error if it would
+/// not load. Lint findings never block a save.
+fn validate_for_save(content: &str, path: &Path) -> Result<Vec<Diagnostic>, String> {
+    let mut diagnostics =
+        validate_content(content, Some(&path.to_string_lossy()), target_version());
+    let reported_by_client = ssh_client_diagnostics(content, path, &diagnostics);
+    diagnostics.extend(reported_by_client);
+    if has_errors(&diagnostics) {
+        let errors: Vec<String> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(format_diagnostic)
+            .collect();
+        return Err(errors.join("\n"));
+    }
+    diagnostics.extend(lint_content(content, path));
+    Ok(diagnostics)
+}
 
-        // Validate LogLevel
-        if keyword_lower == "loglevel" {
-            let valid_levels = [
-                "quiet", "fatal", "error", "info", "verbose", "debug", "debug1", "debug2", "debug3",
-            ];
-            if !valid_levels.contains(&value.to_lowercase().as_str()) {
-                warnings.push(format!(
-                    "第 {} 行: LogLevel \"{}\" 不是有效级别",
-                    i + 1,
-                    value
-                ));
-            }
-        }
+/// Run the lint rules enabled in the settings over `content` as the
+/// config at `path`. Where `path` is a config root or included from one,
+/// each such root is linted with `content` in place of the file on disk:
+/// problems in `path` are reported, as are those in the other files of
+/// the include graph that `content` introduces.
+fn lint_content(content: &str, path: &Path) -> Vec<Diagnostic> {
+    let overrides = settings::load_settings().lint_rules;
+    let roots = roots_including(path);
+    if roots.is_empty() {
+        let configs = with_following_configs(parse_config_content(path, content));
+        return lint_configs(&configs, &overrides);
+    }
 
-        // Validate AddKeysToAgent
-        if keyword_lower == "addkeystoagent" {
-            let valid = ["yes", "no", "confirm", "ask"];
-            if !valid.contains(&value.to_lowercase().as_str()) && value.parse::<u32>().is_err() {
-                warnings.push(format!(
-                    "第 {} 行: AddKeysToAgent \"{}\" 应为 yes/no/confirm/ask 或秒数",
-                    i + 1,
-                    value
-                ));
+    let file = path.to_string_lossy();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for root in roots {
+        let proposed = match load_config_file_with(&root, path, content) {
+            Ok(config) => lint_configs(&with_following_configs(config), &overrides),
+            Err(e) => {
+                diagnostics.push(root_not_linted(&file, &root, &e));
+                continue;
             }
-        }
-
-        // Validate StrictHostKeyChecking
-        if keyword_lower == "stricthostkeychecking" {
-            let valid = ["yes", "no", "ask", "accept-new", "off"];
-            if !valid.contains(&value.to_lowercase().as_str()) {
-                warnings.push(format!(
-                    "第 {} 行: StrictHostKeyChecking \"{}\" 应为 yes/no/ask/accept-new/off",
-                    i + 1,
-                    value
-                ));
+        };
+        let current = load_config_file(&root)
+            .map(|config| lint_configs(&with_following_configs(config), &overrides))
+            .unwrap_or_default();
+        for diagnostic in proposed {
+            let relevant = is_same_file(diagnostic.file.as_deref(), &file)
+                || !current.iter().any(|d| same_problem(d, &diagnostic));
+            if relevant && !diagnostics.iter().any(|d| same_problem(d, &diagnostic)) {
+                diagnostics.push(diagnostic);
             }
         }
+    }
+    diagnostics
+}
 
-        // Validate RequestTTY
-        if keyword_lower == "requesttty" {
-            let valid = ["yes", "no", "force", "auto"];
-            if !valid.contains(&value.to_lowercase().as_str()) {
-                warnings.push(format!(
-                    "第 {} 行: RequestTTY \"{}\" 应为 yes/no/force/auto",
-                    i + 1,
-                    value
-                ));
-            }
-        }
+/// Whether a diagnostic's `file` is `path`, possibly spelled differently
+fn is_same_file(file: Option<&str>, path: &str) -> bool {
+    file.is_some_and(|f| {
+        f == path || Path::new(f).canonicalize().ok() == Path::new(path).canonicalize().ok()
+    })
+}
 
-        // Validate ControlMaster
-        if keyword_lower == "controlmaster" {
-            let valid = ["yes", "no", "ask", "auto", "autoask"];
-            if !valid.contains(&value.to_lowercase().as_str()) {
-                warnings.push(format!(
-                    "第 {} 行: ControlMaster \"{}\" 应为 yes/no/ask/auto/autoask",
-                    i + 1,
-                    value
-                ));
-            }
-        }
-    }
+/// Whether two diagnostics report the same problem at the same place
+fn same_problem(a: &Diagnostic, b: &Diagnostic) -> bool {
+    a.code == b.code
+        && a.file == b.file
+        && a.line == b.line
+        && a.column_range.start == b.column_range.start
+}
 
-    // Also parse with ssh2-config for structural validation
-    let mut reader = BufReader::new(content.as_bytes());
-    match SshConfig::default().parse(&mut reader, ParseRule::ALLOW_UNKNOWN_FIELDS) {
-        Err(e) => {
-            let msg = format!("{}", e);
-            // Add as error - structural parse failure is fatal
-            return Err(format!("SSH 配置结构错误: {}", msg));
-        }
-        Ok(_) => {}
-    }
+/// OpenSSH release chosen in the settings to check configs against
+fn target_version() -> Option<Version> {
+    settings::load_settings()
+        .openssh_version
+        .as_deref()
+        .and_then(Version::parse)
+}
 
-    Ok(warnings)
+/// OpenSSH releases a target version can be chosen from, and the one
+/// installed locally
+#[tauri::command]
+pub fn get_openssh_versions() -> OpenSshVersions {
+    OpenSshVersions {
+        installed: installed_version().map(|v| v.to_string()),
+        known: known_versions().iter().map(|v| v.to_string()).collect(),
+    }
 }
 
+/// Lint rules with whether each is enabled, for the settings page
 #[tauri::command]
-pub fn save_ssh_config(content: String, path: Option<String>) -> Result<Vec<String>, String> {
-    // Validate first
-    let warnings = validate_ssh_config(content.clone())?;
+pub fn list_lint_rules() -> Vec<LintRuleInfo> {
+    lint_rules(&settings::load_settings().lint_rules)
+}
 
+/// Save editor content to `path` (the main config by default).
+///
+/// `base_version` is the version the editor read and `base` its content
+/// at that time. If the file has changed on disk since, the editor's
+/// changes are merged into the new disk content; when that merge has
+/// conflicts nothing is written and the conflict is returned instead.
+/// Without `base_version` the file is overwritten unconditionally.
+#[tauri::command]
+pub fn save_ssh_config(
+    content: String,
+    path: Option<String>,
+    base_version: Option<String>,
+    base: Option<String>,
+) -> Result<SaveOutcome, String> {
     let config_path = match path {
         Some(p) => PathBuf::from(p),
         None => ssh_config_path(),
     };
+    let canonical = checked_config_path(&config_path)?;
+    let disk_content = fs::read_to_string(&canonical).unwrap_or_default();
+    let disk_version = content_version(&disk_content);
+
+    let changed_on_disk = base_version.is_some_and(|v| v != disk_version);
+    if !changed_on_disk {
+        // Validate first
+        let warnings = validate_for_save(&content, &config_path)?;
+        write_config_file(&config_path, &content)?;
+        return Ok(SaveOutcome::Saved {
+            warnings,
+            version: content_version(&content),
+        });
+    }
 
-    // Security: only allow writing files under ~/.ssh/
-    let home = dirs::home_dir().unwrap_or_default();
-    let ssh_dir = home.join(".ssh");
-    let canonical = config_path
-        .canonicalize()
-        .map_err(|e| format!("Invalid path: {}", e))?;
-    if !canonical.starts_with(&ssh_dir) {
-        return Err("Only files under ~/.ssh/ can be saved".to_string());
+    let merge = merge3(base.as_deref().unwrap_or_default(), &content, &disk_content);
+    if base.is_none() || !merge.conflicts.is_empty() {
+        return Ok(SaveOutcome::Conflict(SaveConflict {
+            path: config_path.to_string_lossy().to_string(),
+            disk_modified: modified_millis(&canonical),
+            disk_content,
+            disk_version,
+            merged: merge.content,
+            hunks: merge.conflicts,
+        }));
+    }
+
+    let warnings = validate_for_save(&merge.content, &config_path)?;
+    write_config_file(&config_path, &merge.content)?;
+    Ok(SaveOutcome::Merged {
+        warnings,
+        version: content_version(&merge.content),
+        content: merge.content,
+    })
+}
+
+/// Resolve `path` and make sure it is an allowed config file.
+/// The file itself doesn't have to exist yet, but its directory does.
+fn checked_config_path(path: &Path) -> Result<PathBuf, String> {
+    let canonical = match path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => {
+            let parent = path
+                .parent()
+                .ok_or_else(|| format!("Invalid path: {}", path.display()))?
+                .canonicalize()
+                .map_err(|e| format!("Invalid path: {}", e))?;
+            parent.join(path.file_name().unwrap_or_default())
+        }
+    };
+    if !is_allowed_config_path(&canonical) {
+        return Err("Only files under ~/.ssh/ or configured config files can be saved".to_string());
+    }
+    Ok(canonical)
+}
+
+/// Write a config file atomically, saving the previous content to the
+/// backup history first
+fn write_config_file(config_path: &Path, content: &str) -> Result<(), String> {
+    // Security: only allow writing files under ~/.ssh/ or configured config files
+    let canonical = checked_config_path(config_path)?;
+
+    backup_file(&canonical, settings::load_settings().backup_retention)?;
+    atomic_write(&canonical, content).map_err(|e| format!("Failed to save SSH config: {}", e))
+}
+
+/// Parse a config file into an editable document; a missing file is empty
+fn read_document(path: &Path) -> Result<ConfigDocument, String> {
+    if !path.exists() {
+        return Ok(ConfigDocument::default());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read SSH config: {}", e))?;
+    Ok(ConfigDocument::parse(&content))
+}
+
+/// Config file that declares `alias`, or an error if there is none
+fn host_file(alias: &str) -> Result<PathBuf, String> {
+    find_host_file(alias).ok_or_else(|| format!("Host \"{}\" not found", alias))
+}
+
+fn ensure_alias_free(alias: &str) -> Result<(), String> {
+    match find_host_file(alias) {
+        Some(path) => Err(format!(
+            "Host \"{}\" already exists in {}",
+            alias,
+            path.display()
+        )),
+        None => Ok(()),
     }
+}
 
-    // Create backup
-    if config_path.exists() {
-        let backup_name = format!(
-            "{}.bak",
-            config_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-        );
-        let backup = config_path.with_file_name(backup_name);
-        fs::copy(&config_path, &backup).map_err(|e| format!("Failed to create backup: {}", e))?;
+/// Error unless hosts can be added to `path`: a configured config root or
+/// one of the files they include
+fn ensure_host_target(path: &Path) -> Result<(), String> {
+    if !config_roots().iter().any(|r| r == path) && roots_including(path).is_empty() {
+        return Err(format!(
+            "{} is not a configured SSH config or included from one",
+            path.display()
+        ));
     }
+    Ok(())
+}
+
+/// Structured definition of a host, for form-based editing
+#[tauri::command]
+pub fn get_ssh_host_definition(alias: String) -> Result<SshHostDefinition, String> {
+    let doc = read_document(&host_file(&alias)?)?;
+    host_definition(&doc, &alias).ok_or_else(|| format!("Host \"{}\" not found", alias))
+}
+
+/// Add a host to `path` (the main config by default), which must be a
+/// configured config root or one of the files they include
+#[tauri::command]
+pub fn create_ssh_host(
+    host: SshHostDefinition,
+    path: Option<String>,
+) -> Result<Vec<SshHost>, String> {
+    ensure_alias_free(&host.alias)?;
+
+    let target = path.map(PathBuf::from).unwrap_or_else(ssh_config_path);
+    ensure_host_target(&target)?;
+
+    let mut doc = read_document(&target)?;
+    create_host(&mut doc, &host)?;
+    write_config_file(&target, &doc.to_string())?;
+    parse_ssh_config()
+}
+
+/// Rewrite the block of `alias` in place to match `host`
+#[tauri::command]
+pub fn update_ssh_host(alias: String, host: SshHostDefinition) -> Result<Vec<SshHost>, String> {
+    if host.alias != alias {
+        ensure_alias_free(&host.alias)?;
+    }
+    let path = host_file(&alias)?;
+    let mut doc = read_document(&path)?;
+    update_host(&mut doc, &alias, &host)?;
+    write_config_file(&path, &doc.to_string())?;
+    parse_ssh_config()
+}
+
+/// Remove `alias` from its file, including any repeated `Host` blocks for it
+#[tauri::command]
+pub fn delete_ssh_host(alias: String) -> Result<Vec<SshHost>, String> {
+    let path = host_file(&alias)?;
+    let mut doc = read_document(&path)?;
+    while doc.host_line_aliases(&alias).is_some() {
+        doc.remove_host(&alias)?;
+    }
+    write_config_file(&path, &doc.to_string())?;
+    parse_ssh_config()
+}
 
-    fs::write(&canonical, &content).map_err(|e| format!("Failed to save SSH config: {}", e))?;
+/// Copy a host's options to a new alias in the same file
+#[tauri::command]
+pub fn duplicate_ssh_host(alias: String, new_alias: String) -> Result<Vec<SshHost>, String> {
+    ensure_alias_free(&new_alias)?;
+    let path = host_file(&alias)?;
+    let mut doc = read_document(&path)?;
+    duplicate_host(&mut doc, &alias, &new_alias)?;
+    write_config_file(&path, &doc.to_string())?;
+    parse_ssh_config()
+}
 
-    Ok(warnings)
+/// Move `alias` with its options and comments to `path`, which must be a
+/// configured config root or one of the files they include
+#[tauri::command]
+pub fn move_ssh_host(alias: String, path: String) -> Result<Vec<SshHost>, String> {
+    let source = host_file(&alias)?;
+    let target = PathBuf::from(path);
+    ensure_host_target(&target)?;
+    if checked_config_path(&source)? == checked_config_path(&target)? {
+        return Err(format!(
+            "Host \"{}\" is already in {}",
+            alias,
+            target.display()
+        ));
+    }
+
+    let mut from = read_document(&source)?;
+    let mut to = read_document(&target)?;
+    move_host(&mut from, &mut to, &alias)?;
+    // Target first: if the second write fails the host is duplicated
+    // rather than lost
+    write_config_file(&target, &to.to_string())?;
+    write_config_file(&source, &from.to_string())?;
+    parse_ssh_config()
 }
 
+/// Saved versions of `path`, or of every config file, newest first
 #[tauri::command]
-pub fn save_app_settings(default_terminal: String, ssh_config_path: String) -> Result<(), String> {
-    let s = AppSettings {
-        default_terminal,
-        ssh_config_path,
+pub fn list_ssh_config_backups(path: Option<String>) -> Result<Vec<ConfigBackup>, String> {
+    let source = match path {
+        Some(p) => Some(checked_config_path(Path::new(&p))?),
+        None => None,
     };
-    settings::save_settings_to_file(&s)
+    Ok(list_backups(source.as_deref()))
+}
+
+/// Unified diff from a backup to the current content of its file
+#[tauri::command]
+pub fn diff_ssh_config_backup(id: String) -> Result<String, String> {
+    let (backup, content) = read_backup(&id)?;
+    let current = fs::read_to_string(&backup.source_path).unwrap_or_default();
+    Ok(unified_diff(
+        &content,
+        &current,
+        &format!("{} (backup)", backup.source_path),
+        &backup.source_path,
+    ))
+}
+
+/// Write a backup back to its file. The content being replaced is backed
+/// up too, so a restore can itself be undone.
+#[tauri::command]
+pub fn restore_ssh_config_backup(id: String) -> Result<Vec<SshHost>, String> {
+    let (backup, content) = read_backup(&id)?;
+    write_config_file(Path::new(&backup.source_path), &content)?;
+    parse_ssh_config()
+}
+
+#[tauri::command]
+pub fn save_app_settings(app: tauri::AppHandle, settings: AppSettings) -> Result<(), String> {
+    validate_group_rules(&settings.group_rules)?;
+    validate_launch_profiles(&settings.launch_profiles)?;
+    validate_workspaces(&settings.workspaces)?;
+    if settings.default_terminal == CUSTOM_TERMINAL || !settings.custom_terminal.is_empty() {
+        validate_terminal_template(&settings.custom_terminal)?;
+    }
+    settings::save_settings_to_file(&settings)?;
+    // Groups in the tray follow the rules
+    crate::tray::refresh_tray_menu(&app);
+    Ok(())
+}
+
+/// The group rules used when the settings don't set any
+#[tauri::command]
+pub fn get_default_group_rules() -> Vec<GroupRule> {
+    default_group_rules()
 }
 
 #[tauri::command]
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ssh::backup::atomic_write;
+use crate::ssh::types::SshHost;
+
+/// Connections made to hosts and the hosts pinned as favorites
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConnectionHistory {
+    /// Usage per alias
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostUsage>,
+    /// Pinned hosts, in the order they were pinned
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HostUsage {
+    /// Connections launched so far
+    pub count: u64,
+    /// Unix time in milliseconds of the latest connection
+    pub last_connected: u64,
+}
+
+/// A host with its usage, as returned to the UI and the tray
+#[derive(Debug, Serialize, Clone)]
+pub struct HostUsageEntry {
+    pub alias: String,
+    pub count: u64,
+    /// Unix milliseconds; 0 if never connected
+    pub last_connected: u64,
+    pub pinned: bool,
+}
+
+pub fn history_path() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".ssh-m").join("history.json")
+}
+
+pub fn load_history() -> ConnectionHistory {
+    fs::read_to_string(history_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(history: &ConnectionHistory) -> Result<(), String> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create history dir: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Failed to serialize history: {}", e))?;
+    atomic_write(&path, &content).map_err(|e| format!("Failed to write history: {}", e))
+}
+
+/// Count a connection to `alias` made now
+pub fn record_connection(alias: &str) -> Result<(), String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let mut history = load_history();
+    let usage = history.hosts.entry(alias.to_string()).or_default();
+    usage.count += 1;
+    usage.last_connected = now;
+    save_history(&history)
+}
+
+/// Pin `alias` to the favorites, or unpin it
+pub fn set_pinned(alias: &str, pinned: bool) -> Result<(), String> {
+    let mut history = load_history();
+    history.pinned.retain(|a| a != alias);
+    if pinned {
+        history.pinned.push(alias.to_string());
+    }
+    save_history(&history)
+}
+
+impl ConnectionHistory {
+    pub fn entry(&self, alias: &str) -> HostUsageEntry {
+        let usage = self.hosts.get(alias).cloned().unwrap_or_default();
+        HostUsageEntry {
+            alias: alias.to_string(),
+            count: usage.count,
+            last_connected: usage.last_connected,
+            pinned: self.pinned.iter().any(|a| a == alias),
+        }
+    }
+
+    /// Hosts in `hosts` connected to before, latest first
+    pub fn recent(&self, hosts: &[SshHost]) -> Vec<HostUsageEntry> {
+        let mut entries = self.used_entries(hosts);
+        entries.sort_by_key(|e| Reverse(e.last_connected));
+        entries
+    }
+
+    /// Hosts in `hosts` connected to before, most connections first; ties
+    /// go to the latest
+    pub fn most_used(&self, hosts: &[SshHost]) -> Vec<HostUsageEntry> {
+        let mut entries = self.used_entries(hosts);
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.last_connected.cmp(&a.last_connected))
+        });
+        entries
+    }
+
+    /// Pinned hosts in pinning order, then the hosts marked `favorite` in
+    /// their metadata. Hosts no longer in `hosts` are left out.
+    pub fn favorites(&self, hosts: &[SshHost]) -> Vec<HostUsageEntry> {
+        let pinned = self
+            .pinned
+            .iter()
+            .filter(|a| hosts.iter().any(|h| &h.name == *a));
+        let marked = hosts
+            .iter()
+            .filter(|h| h.metadata.favorite && !self.pinned.contains(&h.name))
+            .map(|h| &h.name);
+        pinned.chain(marked).map(|a| self.entry(a)).collect()
+    }
+
+    fn used_entries(&self, hosts: &[SshHost]) -> Vec<HostUsageEntry> {
+        self.hosts
+            .keys()
+            .filter(|a| hosts.iter().any(|h| &h.name == *a))
+            .map(|a| self.entry(a))
+            .collect()
+    }
+}
@@ -1,18 +1,38 @@
 mod blockchain;
 mod commands;
 mod crypto;
+mod history;
 mod onepassword;
 pub mod settings;
 mod ssh;
 mod tray;
 
+use tauri::Emitter;
+
 use commands::blockchain::{derive_accounts, get_default_chain_configs, query_balances};
 use commands::onepassword::{check_op_status, list_vault_items, list_vaults};
 use commands::ssh::{
-    get_app_settings, list_ssh_config_files, list_ssh_hosts, open_ssh_terminal, read_ssh_config,
-    refresh_ssh_config, save_app_settings, save_ssh_config, validate_ssh_config,
+    apply_config_fixes, create_ssh_host, delete_ssh_host, detect_terminals, diff_ssh_config_backup,
+    duplicate_ssh_host, fix_key_file_permissions, get_app_settings, get_default_group_rules,
+    get_openssh_versions, get_ssh_host_definition, list_favorite_hosts, list_launch_profiles,
+    list_lint_rules, list_most_used_hosts, list_recent_hosts, list_ssh_config_backups,
+    list_ssh_config_files, list_ssh_host_patterns, list_ssh_hosts, move_ssh_host,
+    open_ssh_terminal, open_workspace, read_ssh_config, refresh_ssh_config, resolve_ssh_host,
+    restore_ssh_config_backup, save_app_settings, save_ssh_config, search_ssh_hosts,
+    set_ssh_host_pinned, update_ssh_host, validate_ssh_config,
 };
 
+/// Event carrying the re-parsed host list after a config file changed
+const SSH_HOSTS_CHANGED_EVENT: &str = "ssh-hosts-changed";
+
+/// Push the new host list to the UI and rebuild the tray menu
+fn on_ssh_config_changed(app: &tauri::AppHandle) {
+    if let Ok(hosts) = ssh::config::parse_ssh_config() {
+        let _ = app.emit(SSH_HOSTS_CHANGED_EVENT, hosts);
+    }
+    tray::refresh_tray_menu(app);
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -20,14 +40,38 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // SSH commands
             list_ssh_hosts,
+            list_ssh_host_patterns,
             refresh_ssh_config,
+            resolve_ssh_host,
             open_ssh_terminal,
+            open_workspace,
+            search_ssh_hosts,
+            list_recent_hosts,
+            list_most_used_hosts,
+            list_favorite_hosts,
+            list_launch_profiles,
+            set_ssh_host_pinned,
             read_ssh_config,
             validate_ssh_config,
+            apply_config_fixes,
+            fix_key_file_permissions,
+            list_lint_rules,
+            get_openssh_versions,
             save_ssh_config,
             list_ssh_config_files,
+            get_ssh_host_definition,
+            create_ssh_host,
+            update_ssh_host,
+            delete_ssh_host,
+            detect_terminals,
+            duplicate_ssh_host,
+            move_ssh_host,
+            list_ssh_config_backups,
+            diff_ssh_config_backup,
+            restore_ssh_config_backup,
             save_app_settings,
             get_app_settings,
+            get_default_group_rules,
             // 1Password commands
             check_op_status,
             list_vaults,
@@ -40,6 +84,13 @@ pub fn run() {
         .setup(|app| {
             // Setup system tray
             tray::setup_tray(app.handle())?;
+
+            // Follow config edits made anywhere, not just in the app
+            let handle = app.handle().clone();
+            let on_change = move || on_ssh_config_changed(&handle);
+            if let Err(e) = ssh::watch::spawn_config_watcher(on_change) {
+                eprintln!("Failed to watch SSH config files: {}", e);
+            }
             Ok(())
         })
         .on_window_event(|window, event| {
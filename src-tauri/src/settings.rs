@@ -1,13 +1,47 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::ssh::backup::DEFAULT_BACKUP_RETENTION;
+use crate::ssh::group::default_group_rules;
+use crate::ssh::types::{GroupRule, LaunchProfile, Workspace};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppSettings {
     #[serde(default = "default_terminal")]
     pub default_terminal: String,
+    /// Argv template of the `custom` terminal, one argument per item
+    #[serde(default)]
+    pub custom_terminal: Vec<String>,
     #[serde(default = "default_ssh_config_path")]
     pub ssh_config_path: String,
+    /// Further config files whose hosts are merged into the list
+    #[serde(default)]
+    pub additional_config_paths: Vec<String>,
+    /// Backups kept per config file
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: usize,
+    /// Lint rules switched on or off by id; rules not listed are on
+    #[serde(default)]
+    pub lint_rules: BTreeMap<String, bool>,
+    /// OpenSSH release configs are checked against, e.g. `8.9`; none
+    /// means the latest
+    #[serde(default)]
+    pub openssh_version: Option<String>,
+    /// Also check configs with the local `ssh -G` and take the effective
+    /// host settings from it
+    #[serde(default)]
+    pub ssh_client_check: bool,
+    /// Rules assigning hosts to groups and tags, in priority order
+    #[serde(default = "default_group_rules")]
+    pub group_rules: Vec<GroupRule>,
+    /// Named ways of connecting offered per host or group
+    #[serde(default)]
+    pub launch_profiles: Vec<LaunchProfile>,
+    /// Saved sets of hosts opened together
+    #[serde(default)]
+    pub workspaces: Vec<Workspace>,
 }
 
 fn default_terminal() -> String {
@@ -18,16 +52,29 @@ fn default_ssh_config_path() -> String {
     "~/.ssh/config".to_string()
 }
 
+fn default_backup_retention() -> usize {
+    DEFAULT_BACKUP_RETENTION
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             default_terminal: default_terminal(),
+            custom_terminal: Vec::new(),
             ssh_config_path: default_ssh_config_path(),
+            additional_config_paths: Vec::new(),
+            backup_retention: default_backup_retention(),
+            lint_rules: BTreeMap::new(),
+            openssh_version: None,
+            ssh_client_check: false,
+            group_rules: default_group_rules(),
+            launch_profiles: Vec::new(),
+            workspaces: Vec::new(),
         }
     }
 }
 
-fn settings_path() -> PathBuf {
+pub fn settings_path() -> PathBuf {
     let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
     home.join(".ssh-m").join("settings.json")
 }
@@ -0,0 +1,210 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::types::ConfigBackup;
+
+/// Backups kept per config file unless the settings say otherwise
+pub const DEFAULT_BACKUP_RETENTION: usize = 20;
+
+fn backups_dir() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".ssh-m").join("backups")
+}
+
+fn index_path() -> PathBuf {
+    backups_dir().join("index.json")
+}
+
+fn backup_content_path(id: &str) -> PathBuf {
+    backups_dir().join(format!("{}.conf", id))
+}
+
+/// Version token of a file's content (64-bit FNV-1a, hex). Stable across
+/// runs, so a token from one read can be compared with a later one.
+pub fn content_version(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Modification time of `path` in Unix milliseconds
+pub fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// Replace `path` with `content` without ever leaving a partly written
+/// file: the content goes to a temp file next to it, which is flushed and
+/// renamed over the original. Symlinks are followed so the link itself
+/// survives. The original's mode and owner are carried over; new files
+/// get 0600.
+pub fn atomic_write(path: &Path, content: &str) -> Result<(), String> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = target
+        .parent()
+        .ok_or_else(|| format!("Invalid path: {}", target.display()))?;
+
+    // A uniquely named temp file, so concurrent writers never share one;
+    // it is removed on drop if anything below fails
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".ssh-m-")
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    write_temp(tmp.as_file_mut(), &target, content)?;
+    tmp.persist(&target)
+        .map_err(|e| format!("Failed to replace file: {}", e.error))?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Ok(d) = fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
+fn write_temp(file: &mut fs::File, target: &Path, content: &str) -> Result<(), String> {
+    let existing = fs::metadata(target).ok();
+
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+
+    #[cfg(unix)]
+    if let Some(meta) = &existing {
+        use std::os::unix::fs::{fchown, MetadataExt, PermissionsExt};
+        file.set_permissions(fs::Permissions::from_mode(meta.mode() & 0o7777))
+            .map_err(|e| format!("Failed to set permissions: {}", e))?;
+        // Only root can hand a file to someone else; for the owner this is a no-op
+        let _ = fchown(&*file, Some(meta.uid()), Some(meta.gid()));
+    }
+    #[cfg(not(unix))]
+    let _ = existing;
+
+    file.sync_all()
+        .map_err(|e| format!("Failed to flush temp file: {}", e))
+}
+
+fn load_index() -> Vec<ConfigBackup> {
+    fs::read_to_string(index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(entries: &[ConfigBackup]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize backup index: {}", e))?;
+    atomic_write(&index_path(), &content)
+}
+
+/// Backups of `source` (or of every file), newest first
+pub fn list_backups(source: Option<&Path>) -> Vec<ConfigBackup> {
+    let source = source.map(|p| p.to_string_lossy().to_string());
+    let mut entries: Vec<ConfigBackup> = load_index()
+        .into_iter()
+        .filter(|b| source.as_ref().is_none_or(|s| &b.source_path == s))
+        .collect();
+    entries.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    entries
+}
+
+/// Look up a backup by id and read its content
+pub fn read_backup(id: &str) -> Result<(ConfigBackup, String), String> {
+    // Only ids from the index are accepted, so `id` never picks the path
+    let backup = load_index()
+        .into_iter()
+        .find(|b| b.id == id)
+        .ok_or_else(|| format!("Backup \"{}\" not found", id))?;
+    let content = fs::read_to_string(backup_content_path(&backup.id))
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+    Ok((backup, content))
+}
+
+/// Snapshot the current content of `path` before it gets overwritten,
+/// then drop the oldest snapshots of that file beyond `retention`.
+/// Nothing is stored if the file doesn't exist or matches the latest
+/// snapshot.
+pub fn backup_file(path: &Path, retention: usize) -> Result<Option<ConfigBackup>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return Ok(None),
+    };
+    let source_path = path.to_string_lossy().to_string();
+
+    let mut index = load_index();
+    let latest = index
+        .iter()
+        .filter(|b| b.source_path == source_path)
+        .max_by_key(|b| b.created_at);
+    if let Some(latest) = latest {
+        if fs::read_to_string(backup_content_path(&latest.id))
+            .ok()
+            .as_deref()
+            == Some(&content)
+        {
+            return Ok(None);
+        }
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let name: String = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut id = format!("{}-{}", created_at, name);
+    let mut n = 1;
+    while index.iter().any(|b| b.id == id) {
+        id = format!("{}-{}-{}", created_at, name, n);
+        n += 1;
+    }
+
+    let dir = backups_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup dir: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&dir, fs::Permissions::from_mode(0o700));
+    }
+    atomic_write(&backup_content_path(&id), &content)
+        .map_err(|e| format!("Failed to create backup: {}", e))?;
+
+    let backup = ConfigBackup {
+        id,
+        source_path: source_path.clone(),
+        created_at,
+        size: content.len() as u64,
+    };
+    index.push(backup.clone());
+
+    // Retention: keep the newest `retention` snapshots of this file
+    let mut own: Vec<(u64, String)> = index
+        .iter()
+        .filter(|b| b.source_path == source_path)
+        .map(|b| (b.created_at, b.id.clone()))
+        .collect();
+    own.sort();
+    let excess = own.len().saturating_sub(retention.max(1));
+    let expired: Vec<String> = own.into_iter().take(excess).map(|(_, id)| id).collect();
+    for id in &expired {
+        let _ = fs::remove_file(backup_content_path(id));
+    }
+    index.retain(|b| !expired.contains(&b.id));
+
+    save_index(&index)?;
+    Ok(Some(backup))
+}
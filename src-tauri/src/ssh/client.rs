@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tempfile::NamedTempFile;
+
+use super::config::{config_file_args, parse_match_criteria, MatchCriterion};
+use super::cst::ConfigDocument;
+use super::include::{ConfigFile, ConfigItem};
+use super::parser::column_range;
+use super::resolve::MULTI_VALUE_KEYWORDS;
+use super::types::{
+    ColumnRange, Diagnostic, ResolveBackend, ResolvedHost, ResolvedOption, Severity,
+};
+use super::validate::format_message;
+
+/// How long a single `ssh -G` run may take
+const SSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Aliases checked per validation; parse errors outside any host block
+/// show up for the first one already
+const MAX_CHECKED_ALIASES: usize = 50;
+
+/// Alias used when the content defines none, so ssh still parses it
+const PLACEHOLDER_ALIAS: &str = "ssh-m-check";
+
+/// Check `content`, the candidate content of the config at `path`, with
+/// the local ssh client: it is written to a temporary file and
+/// `ssh -F <tmp> -G <alias>` is run for each alias it defines. Errors ssh
+/// prints are mapped back to `path` (or to the included file they are in).
+///
+/// `parsed` is the content with its includes resolved. Configs using
+/// `Match exec` are not checked, since `ssh -G` would run the commands.
+pub fn check_content(content: &str, path: &Path, parsed: &ConfigFile) -> Vec<Diagnostic> {
+    let file = path.to_string_lossy().to_string();
+    if uses_match_exec(parsed) {
+        return vec![notice(
+            &file,
+            "ssh-client-skipped",
+            "ssh_client_skipped_exec",
+        )];
+    }
+
+    let Ok(tmp) = write_temp_config(content) else {
+        return vec![notice(
+            &file,
+            "ssh-client-skipped",
+            "ssh_client_skipped_temp",
+        )];
+    };
+    let mut aliases = ConfigDocument::parse(content).host_aliases();
+    aliases.truncate(MAX_CHECKED_ALIASES);
+    if aliases.is_empty() {
+        aliases.push(PLACEHOLDER_ALIAS.to_string());
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for alias in &aliases {
+        let args = [
+            "-F".to_string(),
+            tmp.path().to_string_lossy().to_string(),
+            "-G".to_string(),
+            "--".to_string(),
+            alias.clone(),
+        ];
+        let Some(output) = run_ssh(&args) else {
+            diagnostics.push(notice(
+                &file,
+                "ssh-client-skipped",
+                "ssh_client_unavailable",
+            ));
+            break;
+        };
+        for diagnostic in parse_errors(&output, tmp.path(), &file, content) {
+            let duplicate = diagnostics.iter().any(|d| {
+                d.file == diagnostic.file && d.line == diagnostic.line && d.args == diagnostic.args
+            });
+            if !duplicate {
+                diagnostics.push(diagnostic);
+            }
+        }
+        // A config ssh can't load fails the same way for every alias
+        if !output.status.success() {
+            break;
+        }
+    }
+
+    diagnostics
+}
+
+/// Effective settings for `alias` as printed by `ssh -G`, using the config
+/// root at `root`. Options are annotated with the file and line our own
+/// resolution (`builtin`) got them from, where the values agree.
+///
+/// Returns `None` if ssh isn't available or fails, or the config uses
+/// `Match exec`.
+pub fn resolve_with_client(
+    alias: &str,
+    root: &Path,
+    configs: &[ConfigFile],
+    builtin: &ResolvedHost,
+) -> Option<ResolvedHost> {
+    if configs.iter().any(uses_match_exec) {
+        return None;
+    }
+    let mut args = config_file_args(root);
+    args.extend(["-G".to_string(), "--".to_string(), alias.to_string()]);
+    let output = run_ssh(&args).filter(|o| o.status.success())?;
+
+    let mut options = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((keyword, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let source = builtin
+            .options
+            .iter()
+            .filter(|o| o.keyword == keyword && o.source_file.is_some())
+            .find(|o| {
+                o.value.eq_ignore_ascii_case(value) || !MULTI_VALUE_KEYWORDS.contains(&keyword)
+            });
+        options.push(ResolvedOption {
+            keyword: keyword.to_string(),
+            value: value.to_string(),
+            source_file: source.and_then(|o| o.source_file.clone()),
+            source_line: source.and_then(|o| o.source_line),
+            conditional: None,
+        });
+    }
+
+    let first = |keyword: &str| {
+        options
+            .iter()
+            .find(|o| o.keyword == keyword)
+            .map(|o| o.value.clone())
+    };
+    Some(ResolvedHost {
+        alias: alias.to_string(),
+        hostname: first("hostname").unwrap_or_else(|| builtin.hostname.clone()),
+        user: first("user").unwrap_or_else(|| builtin.user.clone()),
+        port: first("port")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(builtin.port),
+        computed_by: ResolveBackend::SshClient,
+        options,
+    })
+}
+
+/// Whether any `Match` line in `file` or the files it includes uses `exec`
+fn uses_match_exec(file: &ConfigFile) -> bool {
+    file.items.iter().any(|item| match item {
+        ConfigItem::Directive(d) if d.key() == "match" => parse_match_criteria(&d.args)
+            .map(|conditions| {
+                conditions
+                    .iter()
+                    .any(|c| matches!(c.criterion, MatchCriterion::Exec(_)))
+            })
+            // Criteria we can't parse might hide an exec
+            .unwrap_or(true),
+        ConfigItem::Directive(_) => false,
+        ConfigItem::Include { files } => files.iter().any(uses_match_exec),
+    })
+}
+
+/// Write `content` to a new file only the current user can read, as ssh
+/// refuses configs others can write. The file is removed when dropped.
+fn write_temp_config(content: &str) -> Result<NamedTempFile, String> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("ssh-m-check-").suffix(".conf");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o600));
+    }
+    let mut file = builder
+        .tempfile()
+        .map_err(|e| format!("Failed to create temporary config: {}", e))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write temporary config: {}", e))?;
+    Ok(file)
+}
+
+/// Run ssh with `args`, killing it after [`SSH_TIMEOUT`]. `None` if ssh
+/// couldn't be started or timed out.
+fn run_ssh(args: &[String]) -> Option<Output> {
+    let mut child = Command::new("ssh")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return child.wait_with_output().ok(),
+            Ok(None) if started.elapsed() < SSH_TIMEOUT => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
+
+/// Turn the messages ssh printed about its config into diagnostics.
+/// Messages look like `<file> line <n>: <message>`; ones without a line
+/// are reported for the whole file. `content` is the content of `file`.
+fn parse_errors(output: &Output, tmp: &Path, file: &str, content: &str) -> Vec<Diagnostic> {
+    let tmp = tmp.to_string_lossy();
+    let failed = !output.status.success();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let mut diagnostics = Vec::new();
+    for message in stderr.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let located = message.split_once(" line ").and_then(|(source, rest)| {
+            let (line, text) = rest.split_once(": ")?;
+            Some((
+                source.trim_end_matches(':'),
+                line.parse::<usize>().ok()?,
+                text,
+            ))
+        });
+        let (source, line, text) = match located {
+            Some(located) => located,
+            // `<file>: terminating, 1 bad configuration options` only
+            // repeats the errors already reported
+            None if message.contains("terminating") && !diagnostics.is_empty() => continue,
+            None => (tmp.as_ref(), 0, message),
+        };
+        let (source, column_range) = if source == tmp {
+            let range = content
+                .lines()
+                .nth(line.wrapping_sub(1))
+                .map(|l| column_range(l, l.trim()))
+                .unwrap_or(ColumnRange { start: 0, end: 0 });
+            (file.to_string(), range)
+        } else {
+            (source.to_string(), ColumnRange { start: 0, end: 0 })
+        };
+        // ssh carries on past deprecated and unsupported options
+        let ignored =
+            text.starts_with("Deprecated option") || text.starts_with("Unsupported option");
+        let severity = if failed && !ignored {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        let args = BTreeMap::from([("message".to_string(), text.replace(tmp.as_ref(), file))]);
+        diagnostics.push(Diagnostic {
+            file: Some(source),
+            line,
+            column_range,
+            severity,
+            code: "ssh-client".to_string(),
+            message_key: "ssh_client_error".to_string(),
+            message: format_message("ssh_client_error", &args),
+            args,
+            suggested_fix: None,
+        });
+    }
+    diagnostics
+}
+
+fn notice(file: &str, code: &str, message_key: &str) -> Diagnostic {
+    Diagnostic {
+        file: Some(file.to_string()),
+        line: 0,
+        column_range: ColumnRange { start: 0, end: 0 },
+        severity: Severity::Info,
+        code: code.to_string(),
+        message_key: message_key.to_string(),
+        args: BTreeMap::new(),
+        message: format_message(message_key, &BTreeMap::new()),
+        suggested_fix: None,
+    }
+}
@@ -1,203 +1,432 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use ssh2_config::{ParseRule, SshConfig};
+use super::cst::ConfigDocument;
+use super::group::{classify, HostFacts};
+use super::include::{
+    expand_path_tokens, include_base_dir, load_config_file, ConfigFile, ConfigItem,
+};
+use super::metadata::parse_metadata;
+use super::pattern::is_wildcard;
+use super::resolve::{expand_tilde, load_root_configs, resolve_in};
+use super::types::{GroupRule, ResolvedHost, SshHost, SshHostPattern};
+use crate::settings;
 
-use super::types::{SshHost, SshHostGroup};
-
-/// Get the path to the user's SSH config file
-pub fn ssh_config_path() -> PathBuf {
+/// The config ssh reads when no `-F` is given
+pub fn default_config_path() -> PathBuf {
     let home = dirs::home_dir().expect("Cannot determine home directory");
     home.join(".ssh").join("config")
 }
 
-/// Parse ~/.ssh/config and return a list of SSH hosts
-pub fn parse_ssh_config() -> Result<Vec<SshHost>, String> {
-    let config_path = ssh_config_path();
-    if !config_path.exists() {
-        return Err(format!(
-            "SSH config not found at: {}",
-            config_path.display()
-        ));
+/// Get the path to the main SSH config file, as set in the app settings
+pub fn ssh_config_path() -> PathBuf {
+    config_roots()
+        .into_iter()
+        .next()
+        .unwrap_or_else(default_config_path)
+}
+
+/// All top-level config files from the settings: the main config followed
+/// by any additional ones, with `~` and `${VAR}` expanded. Their hosts are
+/// merged into one list; where an alias is defined in several, the first
+/// file wins.
+pub fn config_roots() -> Vec<PathBuf> {
+    let settings = settings::load_settings();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for path in std::iter::once(&settings.ssh_config_path).chain(&settings.additional_config_paths)
+    {
+        let path = path.trim();
+        if path.is_empty() {
+            continue;
+        }
+        let expanded = PathBuf::from(expand_path_tokens(path));
+        if !roots.contains(&expanded) {
+            roots.push(expanded);
+        }
+    }
+    if roots.is_empty() {
+        roots.push(default_config_path());
+    }
+    roots
+}
+
+/// Extra ssh arguments needed for ssh to read `root`: none for the
+/// default config, `-F <root>` for any other
+pub fn config_file_args(root: &Path) -> Vec<String> {
+    if root == default_config_path() {
+        Vec::new()
+    } else {
+        vec!["-F".to_string(), root.to_string_lossy().to_string()]
     }
+}
+
+/// Whether config files at `path` may be read and written: anything under
+/// ~/.ssh/, plus the configured roots and the files they include wherever
+/// they live. `path` must already be canonical.
+pub fn is_allowed_config_path(path: &Path) -> bool {
+    if include_base_dir()
+        .canonicalize()
+        .is_ok_and(|dir| path.starts_with(dir))
+    {
+        return true;
+    }
+    let included = load_user_configs()
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|(_, configs)| configs.into_iter().take(1))
+        .flat_map(|c| c.included_files());
+    config_roots()
+        .into_iter()
+        .chain(included)
+        .any(|f| canonical_target(&f).as_deref() == Some(path))
+}
 
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read SSH config: {}", e))?;
+/// Existing config roots that are `path` itself or include it, directly
+/// or further down the include graph
+pub fn roots_including(path: &Path) -> Vec<PathBuf> {
+    let Some(target) = canonical_target(path) else {
+        return Vec::new();
+    };
+    let is_target = |p: &Path| canonical_target(p).as_deref() == Some(target.as_path());
+    config_roots()
+        .into_iter()
+        .filter(|root| root.exists())
+        .filter(|root| {
+            is_target(root)
+                || load_config_file(root)
+                    .is_ok_and(|f| f.included_files().iter().any(|p| is_target(p)))
+        })
+        .collect()
+}
 
-    // Also read to check for 1Password agent globally
-    let has_global_1password_agent =
-        content.contains("agent.sock") && content.contains("1password");
+/// Canonical form of a file that may not exist yet, as long as its
+/// directory does
+fn canonical_target(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let parent = path.parent()?.canonicalize().ok()?;
+        Some(parent.join(path.file_name()?))
+    })
+}
 
-    // Parse using ssh2-config crate
-    let mut reader = BufReader::new(content.as_bytes());
-    let config = SshConfig::default()
-        .parse(&mut reader, ParseRule::ALLOW_UNKNOWN_FIELDS)
-        .map_err(|e| format!("Failed to parse SSH config: {}", e))?;
+/// Path to the system-wide SSH client config, read after the user config
+pub fn system_config_path() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        PathBuf::from(r"C:\ProgramData\ssh\ssh_config")
+    } else {
+        PathBuf::from("/etc/ssh/ssh_config")
+    }
+}
 
-    // We also manually parse to get host names since ssh2-config
-    // only provides query-based access
-    let hosts = parse_host_entries(&content, &config, has_global_1password_agent);
+/// Load every config root that exists, following Include directives
+pub fn load_user_configs() -> Result<Vec<(PathBuf, Vec<ConfigFile>)>, String> {
+    let roots: Vec<PathBuf> = config_roots().into_iter().filter(|r| r.exists()).collect();
+    if roots.is_empty() {
+        return Err(format!(
+            "SSH config not found at: {}",
+            ssh_config_path().display()
+        ));
+    }
+    roots
+        .into_iter()
+        .map(|root| load_root_configs(&root).map(|configs| (root, configs)))
+        .collect()
+}
 
-    Ok(hosts)
+/// First `Host` line declaring `alias` across the config roots, with the
+/// root it was found under
+pub fn find_host_block(alias: &str) -> Option<(PathBuf, HostBlock)> {
+    load_user_configs()
+        .ok()?
+        .into_iter()
+        .find_map(|(root, configs)| {
+            let block = collect_host_blocks(configs.first()?)
+                .into_iter()
+                .find(|b| b.aliases().any(|a| a == alias))?;
+            Some((root, block))
+        })
 }
 
-/// Manually parse host entries from config content
-/// since ssh2-config doesn't expose the host list directly
-fn parse_host_entries(
-    content: &str,
-    _config: &SshConfig,
-    has_global_1password_agent: bool,
-) -> Vec<SshHost> {
+/// Config root whose files declare `alias` on a `Host` line
+pub fn find_host_root(alias: &str) -> Option<PathBuf> {
+    find_host_block(alias).map(|(root, _)| root)
+}
+
+/// Parse the configured SSH config files (following Include directives)
+/// and return a list of SSH hosts
+pub fn parse_ssh_config() -> Result<Vec<SshHost>, String> {
+    let rules = settings::load_settings().group_rules;
+    let mut seen = HashSet::new();
     let mut hosts = Vec::new();
-    let mut current_host: Option<String> = None;
-    let mut current_hostname = String::new();
-    let mut current_port: u16 = 22;
-    let mut current_user = String::new();
-    let mut current_identity_file: Option<String> = None;
-    let mut current_proxy_jump: Option<String> = None;
-    let mut current_has_1p_agent = false;
-
-    let home = dirs::home_dir()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
+    for (_, configs) in load_user_configs()? {
+        parse_host_entries(&configs, &rules, &mut seen, &mut hosts);
+    }
+    Ok(hosts)
+}
 
-        // Handle Include directives - skip for now
-        if trimmed.to_lowercase().starts_with("include") {
+/// Wildcard and negated `Host` blocks from the configured config files.
+/// These apply to other hosts but can't be connected to directly.
+pub fn parse_ssh_host_patterns() -> Result<Vec<SshHostPattern>, String> {
+    let mut patterns = Vec::new();
+    for (_, configs) in load_user_configs()? {
+        let Some(config) = configs.first() else {
             continue;
-        }
+        };
+        patterns.extend(
+            collect_host_blocks(config)
+                .into_iter()
+                .filter(|b| b.has_patterns())
+                .map(|b| SshHostPattern {
+                    patterns: b.patterns,
+                    source_file: b.source_file.to_string_lossy().to_string(),
+                    source_line: b.source_line,
+                }),
+        );
+    }
+    Ok(patterns)
+}
 
-        if trimmed.to_lowercase().starts_with("host ")
-            && !trimmed.to_lowercase().starts_with("hostname")
-        {
-            // Save previous host if any
-            if let Some(name) = current_host.take() {
-                if name != "*" && !name.contains('*') && !name.contains('?') {
-                    hosts.push(build_ssh_host(
-                        &name,
-                        &current_hostname,
-                        current_port,
-                        &current_user,
-                        current_identity_file.take(),
-                        current_proxy_jump.take(),
-                        current_has_1p_agent || has_global_1password_agent,
-                    ));
-                }
-            }
+/// A `Host` line found while walking the include tree
+pub struct HostBlock {
+    pub patterns: Vec<String>,
+    pub source_file: PathBuf,
+    pub source_line: usize,
+}
 
-            // Start new host
-            let host_name = trimmed[5..].trim().to_string();
-            current_host = Some(host_name.clone());
-            current_hostname = host_name;
-            current_port = 22;
-            current_user = String::new();
-            current_identity_file = None;
-            current_proxy_jump = None;
-            current_has_1p_agent = false;
-        } else if let Some(ref _host) = current_host {
-            let lower = trimmed.to_lowercase();
-            if lower.starts_with("hostname") {
-                current_hostname = extract_value(trimmed);
-            } else if lower.starts_with("port") {
-                current_port = extract_value(trimmed).parse().unwrap_or(22);
-            } else if lower.starts_with("user ") {
-                current_user = extract_value(trimmed);
-            } else if lower.starts_with("identityfile") {
-                let path = extract_value(trimmed).replace('~', &home);
-                current_identity_file = Some(path);
-            } else if lower.starts_with("proxyjump") {
-                current_proxy_jump = Some(extract_value(trimmed));
-            } else if lower.starts_with("identityagent") && lower.contains("1password") {
-                current_has_1p_agent = true;
-            }
-        } else {
-            // Global config (Host *)
-            let lower = trimmed.to_lowercase();
-            if lower.starts_with("user ") && current_user.is_empty() {
-                // Will apply to all subsequent hosts without explicit user
-            }
-        }
+impl HostBlock {
+    /// Entries that name a concrete host, i.e. without wildcards or `!`
+    pub fn aliases(&self) -> impl Iterator<Item = &str> {
+        self.patterns
+            .iter()
+            .filter(|p| !p.starts_with('!') && !is_wildcard(p))
+            .map(|p| p.as_str())
     }
 
-    // Save last host
-    if let Some(name) = current_host.take() {
-        if name != "*" && !name.contains('*') && !name.contains('?') {
-            hosts.push(build_ssh_host(
-                &name,
-                &current_hostname,
-                current_port,
-                &current_user,
-                current_identity_file,
-                current_proxy_jump,
-                current_has_1p_agent || has_global_1password_agent,
-            ));
-        }
+    /// Whether the line contains wildcard or negated entries
+    pub fn has_patterns(&self) -> bool {
+        self.patterns
+            .iter()
+            .any(|p| p.starts_with('!') || is_wildcard(p))
     }
+}
 
-    hosts
+/// All `Host` lines in `file` and the files it includes, in config order
+pub fn collect_host_blocks(file: &ConfigFile) -> Vec<HostBlock> {
+    let mut blocks = Vec::new();
+    collect_blocks(file, &mut blocks);
+    blocks
 }
 
-fn extract_value(line: &str) -> String {
-    // Handle both "Key Value" and "Key=Value" formats
-    let parts: Vec<&str> = if line.contains('=') {
-        line.splitn(2, '=').collect()
-    } else {
-        line.splitn(2, char::is_whitespace).collect()
-    };
+fn collect_blocks(file: &ConfigFile, blocks: &mut Vec<HostBlock>) {
+    for item in &file.items {
+        match item {
+            ConfigItem::Directive(d) if d.key() == "host" => blocks.push(HostBlock {
+                patterns: d.args.clone(),
+                source_file: file.path.clone(),
+                source_line: d.line,
+            }),
+            ConfigItem::Directive(_) => {}
+            ConfigItem::Include { files } => {
+                for inc in files {
+                    collect_blocks(inc, blocks);
+                }
+            }
+        }
+    }
+}
 
-    if parts.len() > 1 {
-        parts[1].trim().trim_matches('"').to_string()
-    } else {
-        String::new()
+/// Build one host entry per alias of every `Host` line in a config root,
+/// with fields taken from the effective (first-match-wins) settings and
+/// tagged with the file and line the alias was declared on. An alias that
+/// appears on several Host lines (or was `seen` in an earlier root) is
+/// listed once, at its first declaration. Group and tags come from `rules`
+/// and the comments of the block.
+fn parse_host_entries(
+    configs: &[ConfigFile],
+    rules: &[GroupRule],
+    seen: &mut HashSet<String>,
+    hosts: &mut Vec<SshHost>,
+) {
+    let blocks = configs.first().map(collect_host_blocks).unwrap_or_default();
+    let mut documents: HashMap<PathBuf, ConfigDocument> = HashMap::new();
+    for block in &blocks {
+        let document = documents
+            .entry(block.source_file.clone())
+            .or_insert_with(|| {
+                ConfigDocument::parse(&fs::read_to_string(&block.source_file).unwrap_or_default())
+            });
+        let comments = document.block_comments(block.source_line - 1);
+        for alias in block.aliases() {
+            if seen.insert(alias.to_string()) {
+                let resolved = resolve_in(configs, alias);
+                hosts.push(build_ssh_host(alias, block, &resolved, rules, &comments));
+            }
+        }
     }
 }
 
 fn build_ssh_host(
-    name: &str,
-    hostname: &str,
-    port: u16,
-    user: &str,
-    identity_file: Option<String>,
-    proxy_jump: Option<String>,
-    is_1password_agent: bool,
+    alias: &str,
+    block: &HostBlock,
+    resolved: &ResolvedHost,
+    rules: &[GroupRule],
+    comments: &[&str],
 ) -> SshHost {
-    let group = determine_group(name, hostname, &proxy_jump);
+    let proxy_jump = resolved
+        .configured("proxyjump")
+        .filter(|v| !v.eq_ignore_ascii_case("none"))
+        .map(|v| v.to_string());
+    let is_1password_agent = resolved
+        .configured("identityagent")
+        .is_some_and(|v| v.to_lowercase().contains("1password"));
+    let source_file = block.source_file.to_string_lossy().to_string();
+    let facts = HostFacts {
+        alias,
+        hostname: &resolved.hostname,
+        has_proxy_jump: proxy_jump.is_some(),
+        source_file: &source_file,
+    };
+    let (group, tags) = classify(rules, &facts, comments);
 
     SshHost {
-        name: name.to_string(),
-        hostname: hostname.to_string(),
-        port,
-        user: user.to_string(),
-        identity_file,
+        name: alias.to_string(),
+        hostname: resolved.hostname.clone(),
+        port: resolved.port,
+        user: resolved.configured("user").unwrap_or_default().to_string(),
+        identity_file: resolved.configured("identityfile").map(expand_tilde),
         proxy_jump,
         is_1password_agent,
         group,
+        tags,
+        source_file,
+        source_line: block.source_line,
+        host_patterns: block.patterns.clone(),
+        metadata: parse_metadata(comments),
     }
 }
 
-fn determine_group(name: &str, hostname: &str, proxy_jump: &Option<String>) -> SshHostGroup {
-    let lower_name = name.to_lowercase();
-    let lower_host = hostname.to_lowercase();
+/// A single criterion of a `Match` line
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchCriterion {
+    All,
+    Canonical,
+    Final,
+    Exec(String),
+    LocalNetwork(String),
+    Host(String),
+    OriginalHost(String),
+    Tagged(String),
+    Command(String),
+    User(String),
+    LocalUser(String),
+}
 
-    if lower_name.contains("github") || lower_host.contains("github") {
-        SshHostGroup::Github
-    } else if proxy_jump.is_some() {
-        SshHostGroup::Proxy
-    } else if lower_host.ends_with(".local")
-        || lower_host.starts_with("192.168.")
-        || lower_host.starts_with("10.")
-        || lower_host == "localhost"
-        || lower_host == "127.0.0.1"
-    {
-        SshHostGroup::Local
-    } else {
-        SshHostGroup::Direct
+/// A `Match` criterion, possibly negated with `!`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCondition {
+    pub negated: bool,
+    pub criterion: MatchCriterion,
+}
+
+impl MatchCriterion {
+    fn name(&self) -> &'static str {
+        match self {
+            MatchCriterion::All => "all",
+            MatchCriterion::Canonical => "canonical",
+            MatchCriterion::Final => "final",
+            MatchCriterion::Exec(_) => "exec",
+            MatchCriterion::LocalNetwork(_) => "localnetwork",
+            MatchCriterion::Host(_) => "host",
+            MatchCriterion::OriginalHost(_) => "originalhost",
+            MatchCriterion::Tagged(_) => "tagged",
+            MatchCriterion::Command(_) => "command",
+            MatchCriterion::User(_) => "user",
+            MatchCriterion::LocalUser(_) => "localuser",
+        }
+    }
+
+    fn argument(&self) -> Option<&str> {
+        match self {
+            MatchCriterion::All | MatchCriterion::Canonical | MatchCriterion::Final => None,
+            MatchCriterion::Exec(a)
+            | MatchCriterion::LocalNetwork(a)
+            | MatchCriterion::Host(a)
+            | MatchCriterion::OriginalHost(a)
+            | MatchCriterion::Tagged(a)
+            | MatchCriterion::Command(a)
+            | MatchCriterion::User(a)
+            | MatchCriterion::LocalUser(a) => Some(a),
+        }
+    }
+}
+
+impl std::fmt::Display for MatchCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.negated {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.criterion.name())?;
+        match self.criterion.argument() {
+            Some(arg) if arg.contains(char::is_whitespace) => write!(f, " \"{}\"", arg),
+            Some(arg) => write!(f, " {}", arg),
+            None => Ok(()),
+        }
     }
 }
+
+/// Parse the arguments of a `Match` line into its criteria
+pub fn parse_match_criteria(args: &[String]) -> Result<Vec<MatchCondition>, String> {
+    let mut conditions = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(raw) = iter.next() {
+        let (negated, name) = match raw.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, raw.as_str()),
+        };
+        let name = name.to_lowercase();
+
+        let criterion = match name.as_str() {
+            "all" => MatchCriterion::All,
+            "canonical" => MatchCriterion::Canonical,
+            "final" => MatchCriterion::Final,
+            _ => {
+                let arg = iter
+                    .next()
+                    .ok_or_else(|| format!("Match \"{}\" requires an argument", name))?
+                    .clone();
+                match name.as_str() {
+                    "exec" => MatchCriterion::Exec(arg),
+                    "localnetwork" => MatchCriterion::LocalNetwork(arg),
+                    "host" => MatchCriterion::Host(arg),
+                    "originalhost" => MatchCriterion::OriginalHost(arg),
+                    "tagged" => MatchCriterion::Tagged(arg),
+                    "command" => MatchCriterion::Command(arg),
+                    "user" => MatchCriterion::User(arg),
+                    "localuser" => MatchCriterion::LocalUser(arg),
+                    _ => return Err(format!("Unsupported Match attribute \"{}\"", name)),
+                }
+            }
+        };
+        conditions.push(MatchCondition { negated, criterion });
+    }
+
+    if conditions.is_empty() {
+        return Err("Match requires at least one criterion".to_string());
+    }
+    // "all" may only stand alone or follow canonical/final
+    let has_all = conditions
+        .iter()
+        .any(|c| c.criterion == MatchCriterion::All);
+    let others = conditions.iter().any(|c| {
+        !matches!(
+            c.criterion,
+            MatchCriterion::All | MatchCriterion::Canonical | MatchCriterion::Final
+        )
+    });
+    if has_all && others {
+        return Err("Match \"all\" cannot be combined with other criteria".to_string());
+    }
+
+    Ok(conditions)
+}
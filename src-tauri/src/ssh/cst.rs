@@ -0,0 +1,955 @@
+use std::fmt;
+use std::ops::Range;
+
+use super::parser::split_args;
+
+/// Lossless syntax tree of an SSH config file.
+///
+/// Every byte of the input is kept (comments, blank lines, indentation,
+/// `Key=Value` vs `Key Value` style, quoting and line endings), so
+/// `ConfigDocument::parse(s).to_string() == s`. Structured edits only touch
+/// the lines they change.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigDocument {
+    lines: Vec<CstLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CstLine {
+    pub kind: LineKind,
+    /// `"\n"`, `"\r\n"`, or `""` for an unterminated last line
+    pub ending: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum LineKind {
+    /// Empty or whitespace-only line
+    Blank(String),
+    /// Comment line, including its indentation
+    Comment(String),
+    Directive(DirectiveLine),
+    /// Line that can't be parsed as a directive (e.g. starts with `=`)
+    Invalid(String),
+}
+
+/// A `Keyword value` line split into its exact source pieces
+#[derive(Debug, Clone)]
+pub struct DirectiveLine {
+    pub indent: String,
+    pub keyword: String,
+    /// Whitespace and/or `=` between keyword and value
+    pub separator: String,
+    /// Raw value text, quotes included
+    pub value: String,
+    /// Trailing whitespace and comment, if any
+    pub trailing: String,
+}
+
+impl DirectiveLine {
+    fn parse(line: &str) -> Option<Self> {
+        let body = line.trim_start();
+        if body.is_empty() || body.starts_with('#') {
+            return None;
+        }
+        let indent = &line[..line.len() - body.len()];
+
+        let kw_end = body
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(body.len());
+        let (keyword, rest) = body.split_at(kw_end);
+        if keyword.is_empty() {
+            return None;
+        }
+
+        let after_ws = rest.trim_start();
+        let after_eq = after_ws.strip_prefix('=').unwrap_or(after_ws).trim_start();
+        let (separator, rest) = rest.split_at(rest.len() - after_eq.len());
+
+        let value_end = value_end(rest);
+        let (value, trailing) = rest.split_at(value_end);
+
+        Some(Self {
+            indent: indent.to_string(),
+            keyword: keyword.to_string(),
+            separator: separator.to_string(),
+            value: value.to_string(),
+            trailing: trailing.to_string(),
+        })
+    }
+
+    /// Lowercased keyword
+    pub fn key(&self) -> String {
+        self.keyword.to_lowercase()
+    }
+
+    /// Value split into arguments with quotes removed
+    pub fn args(&self) -> Vec<String> {
+        split_args(&self.value)
+    }
+}
+
+impl fmt::Display for DirectiveLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}{}",
+            self.indent, self.keyword, self.separator, self.value, self.trailing
+        )
+    }
+}
+
+/// Byte offset where the value ends and trailing whitespace / comment begins
+fn value_end(s: &str) -> usize {
+    let mut quote: Option<char> = None;
+    let mut prev_ws = true;
+    let mut end = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote.is_none() || quote == Some('"') => {
+                // skip the escaped character
+                if let Some((j, next)) = chars.next() {
+                    end = j + next.len_utf8();
+                } else {
+                    end = i + 1;
+                }
+                prev_ws = false;
+                continue;
+            }
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            _ if quote == Some(c) => quote = None,
+            '#' if quote.is_none() && prev_ws => return end,
+            _ => {}
+        }
+        prev_ws = quote.is_none() && c.is_whitespace();
+        if !prev_ws {
+            end = i + c.len_utf8();
+        }
+    }
+    end
+}
+
+/// Quote a single argument if it contains whitespace or special characters
+pub fn quote_value(value: &str) -> String {
+    if !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '\'' || c == '#' || c == '\\')
+    {
+        return value.to_string();
+    }
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+/// Quote each argument of `value` as needed and join them with spaces
+pub fn quote_args(value: &str) -> String {
+    split_args(value)
+        .iter()
+        .map(|a| quote_value(a))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `value` would end in a comment if written after a keyword
+pub fn has_comment(value: &str) -> bool {
+    value_end(value) < value.trim_end().len()
+}
+
+impl CstLine {
+    fn parse(text: &str, ending: &str) -> Self {
+        let kind = if text.trim().is_empty() {
+            LineKind::Blank(text.to_string())
+        } else if text.trim_start().starts_with('#') {
+            LineKind::Comment(text.to_string())
+        } else {
+            match DirectiveLine::parse(text) {
+                Some(d) => LineKind::Directive(d),
+                None => LineKind::Invalid(text.to_string()),
+            }
+        };
+        Self {
+            kind,
+            ending: ending.to_string(),
+        }
+    }
+
+    pub fn directive(&self) -> Option<&DirectiveLine> {
+        match &self.kind {
+            LineKind::Directive(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    fn directive_mut(&mut self) -> Option<&mut DirectiveLine> {
+        match &mut self.kind {
+            LineKind::Directive(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        matches!(self.kind, LineKind::Blank(_))
+    }
+
+    fn is_comment(&self) -> bool {
+        matches!(self.kind, LineKind::Comment(_))
+    }
+
+    /// Whether this is a `Host` or `Match` line, which starts a new block
+    fn is_block_header(&self) -> bool {
+        self.directive()
+            .is_some_and(|d| matches!(d.key().as_str(), "host" | "match"))
+    }
+}
+
+impl fmt::Display for CstLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LineKind::Blank(s) | LineKind::Comment(s) | LineKind::Invalid(s) => write!(f, "{}", s)?,
+            LineKind::Directive(d) => write!(f, "{}", d)?,
+        }
+        write!(f, "{}", self.ending)
+    }
+}
+
+/// Line ranges of a `Host`/`Match` block
+struct BlockSpan {
+    /// First of the comment lines directly above the header
+    start: usize,
+    header: usize,
+    /// One past the last directive in the block
+    body_end: usize,
+    /// Where the next block (including its leading comments) starts
+    end: usize,
+}
+
+impl ConfigDocument {
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .split_inclusive('\n')
+            .map(|raw| {
+                let text = raw.strip_suffix('\n').unwrap_or(raw);
+                match text.strip_suffix('\r') {
+                    Some(t) if raw.ends_with('\n') => CstLine::parse(t, "\r\n"),
+                    _ if raw.ends_with('\n') => CstLine::parse(text, "\n"),
+                    _ => CstLine::parse(text, ""),
+                }
+            })
+            .collect();
+        Self { lines }
+    }
+
+    pub fn lines(&self) -> &[CstLine] {
+        &self.lines
+    }
+
+    /// Every concrete alias declared on a `Host` line, in file order
+    pub fn host_aliases(&self) -> Vec<String> {
+        let mut aliases = Vec::new();
+        for line in &self.lines {
+            if let Some(d) = line.directive().filter(|d| d.key() == "host") {
+                for arg in d.args() {
+                    if !arg.starts_with('!') && !arg.contains(['*', '?']) && !aliases.contains(&arg)
+                    {
+                        aliases.push(arg);
+                    }
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Directives in the block of the first `Host` line that names `alias`
+    pub fn host_directives(&self, alias: &str) -> Option<Vec<&DirectiveLine>> {
+        let span = self.host_span(alias)?;
+        Some(
+            self.lines[span.header + 1..span.body_end]
+                .iter()
+                .filter_map(|l| l.directive())
+                .collect(),
+        )
+    }
+
+    /// Aliases sharing the `Host` line that declares `alias`, including itself
+    pub fn host_line_aliases(&self, alias: &str) -> Option<Vec<String>> {
+        let span = self.host_span(alias)?;
+        self.lines[span.header].directive().map(|d| d.args())
+    }
+
+    /// Add a new `Host` block. It goes before a `Host *` block, if any, so
+    /// that its options take precedence over the catch-all defaults.
+    pub fn add_host(
+        &mut self,
+        aliases: &[String],
+        options: &[(String, String)],
+    ) -> Result<(), String> {
+        if aliases.is_empty() {
+            return Err("At least one alias is required".to_string());
+        }
+        for alias in aliases {
+            if self.host_span(alias).is_some() {
+                return Err(format!("Host \"{}\" already exists", alias));
+            }
+        }
+
+        let indent = self.default_indent();
+        let separator = self.default_separator();
+        let header_value = aliases
+            .iter()
+            .map(|a| quote_value(a))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut block = vec![self.new_directive("", "Host", &separator, &header_value)];
+        for (keyword, value) in options {
+            block.push(self.new_directive(&indent, keyword, &separator, value));
+        }
+        self.insert_block(block);
+        Ok(())
+    }
+
+    /// Remove `alias`. If it shares its `Host` line with other aliases only
+    /// the alias is removed from the line; otherwise the whole block goes,
+    /// including the comments directly above it.
+    pub fn remove_host(&mut self, alias: &str) -> Result<(), String> {
+        let span = self
+            .host_span(alias)
+            .ok_or_else(|| format!("Host \"{}\" not found", alias))?;
+        let aliases = self.lines[span.header]
+            .directive()
+            .map(|d| d.args())
+            .unwrap_or_default();
+
+        if aliases.len() > 1 {
+            self.rewrite_header(span.header, |args| {
+                args.into_iter().filter(|a| a != alias).collect()
+            });
+        } else {
+            self.lines.drain(span.start..span.end);
+        }
+        Ok(())
+    }
+
+    /// Set every occurrence of a (possibly repeated) option in the block of
+    /// `alias`, e.g. several `IdentityFile` or `LocalForward` lines. Existing
+    /// lines are updated in place, keeping their keyword spelling, separator
+    /// and trailing comment; extra ones are removed and missing ones
+    /// appended after the block's last directive.
+    pub fn set_option_values(
+        &mut self,
+        alias: &str,
+        keyword: &str,
+        values: &[String],
+    ) -> Result<(), String> {
+        let span = self
+            .host_span(alias)
+            .ok_or_else(|| format!("Host \"{}\" not found", alias))?;
+        let key = keyword.to_lowercase();
+
+        let existing: Vec<usize> = (span.header + 1..span.body_end)
+            .filter(|&i| self.lines[i].directive().is_some_and(|d| d.key() == key))
+            .collect();
+
+        for (&i, value) in existing.iter().zip(values) {
+            if let Some(d) = self.lines[i].directive_mut() {
+                // a line with the same arguments keeps its own quoting
+                if d.args() != split_args(value) {
+                    d.value = value.clone();
+                }
+            }
+        }
+        for &i in existing.iter().skip(values.len()).rev() {
+            self.lines.remove(i);
+        }
+
+        // nothing was removed in this case, so `existing` indices still hold
+        if values.len() > existing.len() {
+            let indent = self.block_indent(span.header);
+            let separator = self.block_separator(span.header);
+            let at = existing.last().map(|&i| i + 1).unwrap_or(span.body_end);
+            let new_lines: Vec<CstLine> = values[existing.len()..]
+                .iter()
+                .map(|v| self.new_directive(&indent, keyword, &separator, v))
+                .collect();
+            self.insert_lines(at, new_lines);
+        }
+        Ok(())
+    }
+
+    /// Remove every occurrence of `keyword` from the block of `alias`.
+    /// Returns whether anything was removed.
+    pub fn unset_option(&mut self, alias: &str, keyword: &str) -> Result<bool, String> {
+        let had = self
+            .host_directives(alias)
+            .ok_or_else(|| format!("Host \"{}\" not found", alias))?
+            .iter()
+            .any(|d| d.key() == keyword.to_lowercase());
+        self.set_option_values(alias, keyword, &[])?;
+        Ok(had)
+    }
+
+    /// Rename an alias on its `Host` line, leaving the rest of the line as is
+    pub fn rename_alias(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if old == new {
+            return Ok(());
+        }
+        if self.host_span(new).is_some() {
+            return Err(format!("Host \"{}\" already exists", new));
+        }
+        let span = self
+            .host_span(old)
+            .ok_or_else(|| format!("Host \"{}\" not found", old))?;
+        self.rewrite_header(span.header, |args| {
+            args.into_iter()
+                .map(|a| if a == old { new.to_string() } else { a })
+                .collect()
+        });
+        Ok(())
+    }
+
+    /// Give `alias` a `Host` block of its own if it shares its line with
+    /// other aliases: it is removed from the line, and a copy of the block's
+    /// options for it alone goes directly after the block, so the order in
+    /// which ssh applies blocks doesn't change.
+    pub fn split_host(&mut self, alias: &str) -> Result<(), String> {
+        let span = self
+            .host_span(alias)
+            .ok_or_else(|| format!("Host \"{}\" not found", alias))?;
+        let Some(mut copy) = self.shared_block_copy(&span, alias) else {
+            return Ok(());
+        };
+        self.remove_host(alias)?;
+        copy.insert(0, self.blank_line());
+        self.insert_lines(span.body_end, copy);
+        Ok(())
+    }
+
+    /// Remove `alias` and return it as a standalone block of lines, ready to
+    /// be inserted into another document. An alias that shares its `Host`
+    /// line is split out with a copy of the block's options.
+    pub fn take_host(&mut self, alias: &str) -> Result<Vec<CstLine>, String> {
+        let span = self
+            .host_span(alias)
+            .ok_or_else(|| format!("Host \"{}\" not found", alias))?;
+
+        let mut block: Vec<CstLine> = match self.shared_block_copy(&span, alias) {
+            Some(lines) => {
+                self.remove_host(alias)?;
+                lines
+            }
+            None => self.lines.drain(span.start..span.end).collect(),
+        };
+
+        while block.last().is_some_and(|l| l.is_blank()) {
+            block.pop();
+        }
+        Ok(block)
+    }
+
+    /// Insert a block previously returned by [`ConfigDocument::take_host`]
+    pub fn insert_host_lines(&mut self, lines: Vec<CstLine>) -> Result<(), String> {
+        let aliases: Vec<String> = lines
+            .iter()
+            .find(|l| l.is_block_header())
+            .and_then(|l| l.directive())
+            .map(|d| d.args())
+            .unwrap_or_default();
+        for alias in &aliases {
+            if self.host_span(alias).is_some() {
+                return Err(format!("Host \"{}\" already exists", alias));
+            }
+        }
+        self.insert_block(lines);
+        Ok(())
+    }
+
+    /// Line indices of the block whose `Host`/`Match` line is at index
+    /// `header`: from its leading comments up to the next block's
+    pub fn block_range(&self, header: usize) -> Option<Range<usize>> {
+        self.spans()
+            .into_iter()
+            .find(|s| s.header == header)
+            .map(|s| s.start..s.end)
+    }
+
+    /// Text after the `#` of the comments belonging to the block whose
+    /// `Host`/`Match` line is at index `header`: those directly above it,
+    /// then those between its directives
+    pub fn block_comments(&self, header: usize) -> Vec<&str> {
+        let Some(span) = self.spans().into_iter().find(|s| s.header == header) else {
+            return Vec::new();
+        };
+        self.lines[span.start..span.body_end]
+            .iter()
+            .filter_map(|l| match &l.kind {
+                LineKind::Comment(text) => text.trim_start().strip_prefix('#'),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// [`ConfigDocument::block_comments`] of the block of `alias`
+    pub fn host_comments(&self, alias: &str) -> Option<Vec<&str>> {
+        self.host_span(alias)
+            .map(|span| self.block_comments(span.header))
+    }
+
+    /// Replace the comments of the block of `alias` that `matches` accepts
+    /// (given the text after the `#`) with one comment `#<text>`. The first
+    /// such comment is rewritten in place and the others removed; without
+    /// one, the comment goes directly above the `Host` line. `None` removes
+    /// them all.
+    pub fn set_host_comment(
+        &mut self,
+        alias: &str,
+        matches: impl Fn(&str) -> bool,
+        text: Option<&str>,
+    ) -> Result<(), String> {
+        let span = self
+            .host_span(alias)
+            .ok_or_else(|| format!("Host \"{}\" not found", alias))?;
+        let existing: Vec<usize> = (span.start..span.body_end)
+            .filter(|&i| match &self.lines[i].kind {
+                LineKind::Comment(c) => c.trim_start().strip_prefix('#').is_some_and(&matches),
+                _ => false,
+            })
+            .collect();
+
+        let mut remove = existing.as_slice();
+        if let Some(text) = text {
+            match existing.first() {
+                Some(&i) => {
+                    if let LineKind::Comment(c) = &mut self.lines[i].kind {
+                        let indent = c[..c.len() - c.trim_start().len()].to_string();
+                        *c = format!("{}#{}", indent, text);
+                    }
+                    remove = &existing[1..];
+                }
+                None => {
+                    let indent = self.lines[span.header]
+                        .directive()
+                        .map(|d| d.indent.clone())
+                        .unwrap_or_default();
+                    let comment = CstLine {
+                        kind: LineKind::Comment(format!("{}#{}", indent, text)),
+                        ending: self.line_ending(),
+                    };
+                    self.lines.insert(span.header, comment);
+                    return Ok(());
+                }
+            }
+        }
+        for &i in remove.iter().rev() {
+            self.lines.remove(i);
+        }
+        Ok(())
+    }
+
+    fn spans(&self) -> Vec<BlockSpan> {
+        let headers: Vec<usize> = (0..self.lines.len())
+            .filter(|&i| self.lines[i].is_block_header())
+            .collect();
+        let lead_start = |header: usize| {
+            let mut start = header;
+            while start > 0 && self.lines[start - 1].is_comment() {
+                start -= 1;
+            }
+            start
+        };
+
+        headers
+            .iter()
+            .enumerate()
+            .map(|(n, &header)| {
+                let next = headers.get(n + 1).copied();
+                let end = next.map(lead_start).unwrap_or(self.lines.len());
+                let body_end = (header + 1..next.unwrap_or(self.lines.len()))
+                    .rev()
+                    .find(|&i| self.lines[i].directive().is_some())
+                    .map(|i| i + 1)
+                    .unwrap_or(header + 1);
+                BlockSpan {
+                    start: lead_start(header),
+                    header,
+                    body_end,
+                    end,
+                }
+            })
+            .collect()
+    }
+
+    /// The header and options of the block at `span` with `alias` alone on
+    /// the `Host` line, or `None` if no other alias shares the line
+    fn shared_block_copy(&self, span: &BlockSpan, alias: &str) -> Option<Vec<CstLine>> {
+        let shared = self.lines[span.header]
+            .directive()
+            .is_some_and(|d| d.args().len() > 1);
+        if !shared {
+            return None;
+        }
+        let mut lines = self.lines[span.header..span.body_end].to_vec();
+        if let Some(d) = lines[0].directive_mut() {
+            d.value = quote_value(alias);
+            d.trailing.clear();
+        }
+        Some(lines)
+    }
+
+    fn host_span(&self, alias: &str) -> Option<BlockSpan> {
+        self.spans().into_iter().find(|s| {
+            self.lines[s.header]
+                .directive()
+                .is_some_and(|d| d.key() == "host" && d.args().iter().any(|a| a == alias))
+        })
+    }
+
+    /// Replace the arguments of a header line, keeping the original text of
+    /// untouched arguments when the line has no quoting
+    fn rewrite_header(&mut self, header: usize, f: impl FnOnce(Vec<String>) -> Vec<String>) {
+        let Some(d) = self.lines[header].directive_mut() else {
+            return;
+        };
+        let new_args = f(d.args());
+        d.value = if d.value.contains(['"', '\'', '\\']) {
+            new_args
+                .iter()
+                .map(|a| quote_value(a))
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            replace_words(&d.value, &new_args)
+        };
+    }
+
+    fn insert_block(&mut self, mut block: Vec<CstLine>) {
+        let catch_all = self.spans().into_iter().find(|s| {
+            self.lines[s.header]
+                .directive()
+                .is_some_and(|d| d.key() == "host" && d.args() == ["*"])
+        });
+
+        match catch_all {
+            Some(span) => {
+                block.push(self.blank_line());
+                self.insert_lines(span.start, block);
+            }
+            None => {
+                if self.lines.last().is_some_and(|l| !l.is_blank()) {
+                    block.insert(0, self.blank_line());
+                }
+                let at = self.lines.len();
+                self.insert_lines(at, block);
+            }
+        }
+    }
+
+    /// Insert lines at `at`, giving them the document's line ending. When
+    /// appending to a file without a final newline, that property is kept.
+    fn insert_lines(&mut self, at: usize, mut new_lines: Vec<CstLine>) {
+        if new_lines.is_empty() {
+            return;
+        }
+        let ending = self.line_ending();
+        for line in &mut new_lines {
+            line.ending = ending.clone();
+        }
+        if at == self.lines.len() {
+            if let Some(last) = self.lines.last_mut() {
+                if last.ending.is_empty() {
+                    last.ending = ending;
+                    if let Some(new_last) = new_lines.last_mut() {
+                        new_last.ending.clear();
+                    }
+                }
+            }
+        }
+        self.lines.splice(at..at, new_lines);
+    }
+
+    fn new_directive(&self, indent: &str, keyword: &str, separator: &str, value: &str) -> CstLine {
+        CstLine {
+            kind: LineKind::Directive(DirectiveLine {
+                indent: indent.to_string(),
+                keyword: keyword.to_string(),
+                separator: separator.to_string(),
+                value: value.to_string(),
+                trailing: String::new(),
+            }),
+            ending: self.line_ending(),
+        }
+    }
+
+    fn blank_line(&self) -> CstLine {
+        CstLine {
+            kind: LineKind::Blank(String::new()),
+            ending: self.line_ending(),
+        }
+    }
+
+    fn line_ending(&self) -> String {
+        match self.lines.first().map(|l| l.ending.as_str()) {
+            Some("\r\n") => "\r\n".to_string(),
+            _ => "\n".to_string(),
+        }
+    }
+
+    /// Indentation used for options inside blocks across the document
+    fn default_indent(&self) -> String {
+        self.spans()
+            .iter()
+            .find_map(|s| {
+                self.lines[s.header + 1..s.body_end]
+                    .iter()
+                    .filter_map(|l| l.directive())
+                    .find(|d| !d.indent.is_empty())
+                    .map(|d| d.indent.clone())
+            })
+            .unwrap_or_else(|| "    ".to_string())
+    }
+
+    fn default_separator(&self) -> String {
+        self.lines
+            .iter()
+            .filter_map(|l| l.directive())
+            .find(|d| !d.separator.is_empty())
+            .map(|d| normalize_separator(&d.separator))
+            .unwrap_or_else(|| " ".to_string())
+    }
+
+    fn block_indent(&self, header: usize) -> String {
+        self.lines[header + 1..]
+            .iter()
+            .take_while(|l| !l.is_block_header())
+            .filter_map(|l| l.directive())
+            .map(|d| d.indent.clone())
+            .next()
+            .unwrap_or_else(|| self.default_indent())
+    }
+
+    fn block_separator(&self, header: usize) -> String {
+        self.lines[header + 1..]
+            .iter()
+            .take_while(|l| !l.is_block_header())
+            .filter_map(|l| l.directive())
+            .find(|d| !d.separator.is_empty())
+            .map(|d| normalize_separator(&d.separator))
+            .unwrap_or_else(|| self.default_separator())
+    }
+}
+
+impl fmt::Display for ConfigDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keep the `=` style of a separator but not its alignment padding
+fn normalize_separator(separator: &str) -> String {
+    match (separator.contains('='), separator.starts_with('=')) {
+        (true, true) => {
+            if separator.ends_with(char::is_whitespace) {
+                "= ".to_string()
+            } else {
+                "=".to_string()
+            }
+        }
+        (true, false) => " = ".to_string(),
+        (false, _) => " ".to_string(),
+    }
+}
+
+/// Rebuild an unquoted whitespace-separated value with `new_args`, reusing
+/// the original spacing for as many positions as possible
+fn replace_words(value: &str, new_args: &[String]) -> String {
+    let gaps: Vec<&str> = value
+        .split(|c: char| !c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let mut result = String::new();
+    for (i, arg) in new_args.iter().enumerate() {
+        if i > 0 {
+            result.push_str(gaps.get(i - 1).copied().unwrap_or(" "));
+        }
+        result.push_str(arg);
+    }
+    result
+}
+
+/// Move `alias` from one document to another
+pub fn move_host(
+    from: &mut ConfigDocument,
+    to: &mut ConfigDocument,
+    alias: &str,
+) -> Result<(), String> {
+    if to.host_span(alias).is_some() {
+        return Err(format!(
+            "Host \"{}\" already exists in the target file",
+            alias
+        ));
+    }
+    let block = from.take_host(alias)?;
+    to.insert_host_lines(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edited(content: &str, edit: impl FnOnce(&mut ConfigDocument)) -> String {
+        let mut doc = ConfigDocument::parse(content);
+        edit(&mut doc);
+        doc.to_string()
+    }
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_round_trips_byte_for_byte() {
+        let cases = [
+            "",
+            "\n",
+            "Host a\n    User u\n",
+            "Host a\r\n    User u\r\n\r\nHost b\r\n    Port 22\r\n",
+            "Host\ta\n\tUser\tu\n\t\n",
+            "Host=a\nUser = u\n  Port=22\nIdentityFile= ~/.ssh/id\n",
+            "Host a\n    User u",
+            "Host a\r\n    User u",
+            "# only a comment\n",
+            "# one\n  # two\n\n#three",
+            "Host a b # shared\n    User u   # trailing\n",
+            "IdentityFile \"~/my keys/id\" 'x y'\n",
+            "=invalid\n  \t  \n",
+            "Match host a exec \"true\"\n    User u\nInclude conf.d/*\n",
+        ];
+        for content in cases {
+            assert_eq!(
+                ConfigDocument::parse(content).to_string(),
+                content,
+                "{:?}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn split_host_places_the_alias_right_after_its_block() {
+        let content = "Host a b # shared\n    User u\n\nHost c\n    User x\n";
+        assert_eq!(
+            edited(content, |doc| doc.split_host("a").unwrap()),
+            "Host b # shared\n    User u\n\nHost a\n    User u\n\nHost c\n    User x\n"
+        );
+        // An alias with a block of its own is left alone
+        assert_eq!(edited(content, |doc| doc.split_host("c").unwrap()), content);
+        assert!(ConfigDocument::parse(content).split_host("z").is_err());
+    }
+
+    #[test]
+    fn rename_alias_keeps_the_rest_of_the_line() {
+        let content = "Host  a   b # note\n    User u\n";
+        assert_eq!(
+            edited(content, |doc| doc.rename_alias("a", "z").unwrap()),
+            "Host  z   b # note\n    User u\n"
+        );
+        let mut doc = ConfigDocument::parse(content);
+        assert!(doc.rename_alias("a", "b").is_err());
+        assert!(doc.rename_alias("z", "y").is_err());
+        assert_eq!(doc.to_string(), content);
+    }
+
+    #[test]
+    fn set_option_values_updates_in_place() {
+        let content = "Host a\n  IdentityFile=~/one # first\n  User u\n  IdentityFile=~/two\n";
+        assert_eq!(
+            edited(content, |doc| {
+                doc.set_option_values("a", "IdentityFile", &values(&["~/new", "~/two"]))
+                    .unwrap()
+            }),
+            "Host a\n  IdentityFile=~/new # first\n  User u\n  IdentityFile=~/two\n"
+        );
+        assert_eq!(
+            edited(content, |doc| {
+                doc.set_option_values("a", "IdentityFile", &values(&["~/one", "~/two", "~/three"]))
+                    .unwrap()
+            }),
+            "Host a\n  IdentityFile=~/one # first\n  User u\n  IdentityFile=~/two\n  IdentityFile=~/three\n"
+        );
+        // The same arguments keep their own quoting
+        let quoted = "Host a\n    User \"u\"\n";
+        assert_eq!(
+            edited(quoted, |doc| doc
+                .set_option_values("a", "User", &values(&["u"]))
+                .unwrap()),
+            quoted
+        );
+        assert!(ConfigDocument::parse(content)
+            .set_option_values("z", "User", &values(&["u"]))
+            .is_err());
+    }
+
+    #[test]
+    fn set_option_values_with_no_values_removes_every_line() {
+        let content = "Host a\n  IdentityFile ~/one\n  User u\n  identityfile ~/two\n\nHost b\n  IdentityFile ~/b\n";
+        assert_eq!(
+            edited(content, |doc| doc
+                .set_option_values("a", "IdentityFile", &[])
+                .unwrap()),
+            "Host a\n  User u\n\nHost b\n  IdentityFile ~/b\n"
+        );
+        assert_eq!(
+            edited(content, |doc| doc
+                .set_option_values("a", "Port", &[])
+                .unwrap()),
+            content
+        );
+    }
+
+    #[test]
+    fn appended_lines_follow_the_document() {
+        // Line endings, indentation, separator and a missing final newline
+        assert_eq!(
+            edited("Host a\r\n\tUser=u\r\n", |doc| {
+                doc.set_option_values("a", "Port", &values(&["22"]))
+                    .unwrap()
+            }),
+            "Host a\r\n\tUser=u\r\n\tPort=22\r\n"
+        );
+        assert_eq!(
+            edited("Host a\n    User u", |doc| {
+                doc.set_option_values("a", "Port", &values(&["22"]))
+                    .unwrap()
+            }),
+            "Host a\n    User u\n    Port 22"
+        );
+    }
+
+    #[test]
+    fn move_host_takes_the_block_with_its_comments() {
+        let mut from =
+            ConfigDocument::parse("Host a\n    User u\n\n# the b host\nHost b\n    User x\n");
+        let mut to = ConfigDocument::parse("Host c\n    User y\n\nHost *\n    User z\n");
+        move_host(&mut from, &mut to, "b").unwrap();
+        assert_eq!(from.to_string(), "Host a\n    User u\n\n");
+        assert_eq!(
+            to.to_string(),
+            "Host c\n    User y\n\n# the b host\nHost b\n    User x\n\nHost *\n    User z\n"
+        );
+
+        // A shared alias is split out, the others stay
+        let mut from = ConfigDocument::parse("Host a b\n    User u\n");
+        let mut to = ConfigDocument::parse("");
+        move_host(&mut from, &mut to, "a").unwrap();
+        assert_eq!(from.to_string(), "Host b\n    User u\n");
+        assert_eq!(to.to_string(), "Host a\n    User u\n");
+
+        let mut from = ConfigDocument::parse("Host a\n");
+        let mut to = ConfigDocument::parse("Host a\n");
+        assert!(move_host(&mut from, &mut to, "a").is_err());
+        assert!(move_host(&mut from, &mut to, "z").is_err());
+        assert_eq!(from.to_string(), "Host a\n");
+    }
+}
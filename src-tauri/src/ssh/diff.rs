@@ -0,0 +1,231 @@
+use super::types::ConflictHunk;
+
+/// Lines of unchanged context shown around each change
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Shortest edit script between two line lists, via their longest common
+/// subsequence. Common leading and trailing lines are peeled off first so
+/// the quadratic part only covers the changed region.
+fn line_edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Edit<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut edits: Vec<Edit> = old[..prefix].iter().map(|l| Edit::Equal(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            edits.push(Edit::Equal(a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            edits.push(Edit::Delete(a[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(b[j]));
+            j += 1;
+        }
+    }
+    edits.extend(a[i..].iter().map(|l| Edit::Delete(l)));
+    edits.extend(b[j..].iter().map(|l| Edit::Insert(l)));
+    edits.extend(old[old.len() - suffix..].iter().map(|l| Edit::Equal(l)));
+    edits
+}
+
+/// Unified diff (`diff -u` style) from `old` to `new`. Empty when the
+/// contents have the same lines.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = line_edits(&old_lines, &new_lines);
+
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Equal(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Line positions in old/new before each edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut o, mut n) = (0, 0);
+    for edit in &edits {
+        positions.push((o, n));
+        match edit {
+            Edit::Equal(_) => {
+                o += 1;
+                n += 1;
+            }
+            Edit::Delete(_) => o += 1,
+            Edit::Insert(_) => n += 1,
+        }
+    }
+    positions.push((o, n));
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    let mut k = 0;
+    while k < changes.len() {
+        // Merge changes whose context would overlap into one hunk
+        let mut last = k;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT + 1 {
+            last += 1;
+        }
+        let start = changes[k].saturating_sub(CONTEXT);
+        let end = (changes[last] + CONTEXT + 1).min(edits.len());
+
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        let (old_count, new_count) = (old_end - old_start, new_end - new_start);
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 {
+                old_start
+            } else {
+                old_start + 1
+            },
+            old_count,
+            if new_count == 0 {
+                new_start
+            } else {
+                new_start + 1
+            },
+            new_count
+        ));
+        for edit in &edits[start..end] {
+            let (prefix, line) = match edit {
+                Edit::Equal(l) => (' ', l),
+                Edit::Delete(l) => ('-', l),
+                Edit::Insert(l) => ('+', l),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+        k = last + 1;
+    }
+    out
+}
+
+/// For each line of `old`, the index of the line it is kept as in `new`
+fn line_matches(old: &[&str], new: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = Vec::with_capacity(old.len());
+    let mut j = 0;
+    for edit in line_edits(old, new) {
+        match edit {
+            Edit::Equal(_) => {
+                matches.push(Some(j));
+                j += 1;
+            }
+            Edit::Delete(_) => matches.push(None),
+            Edit::Insert(_) => j += 1,
+        }
+    }
+    matches
+}
+
+/// Result of a three-way merge
+pub struct Merge {
+    /// Merged text; conflicting regions are wrapped in
+    /// `<<<<<<<` / `=======` / `>>>>>>>` markers
+    pub content: String,
+    pub conflicts: Vec<ConflictHunk>,
+}
+
+/// Merge the changes made to `base` in `ours` and in `theirs`, line by
+/// line like `diff3 -m`. Regions changed on only one side take that side;
+/// regions changed identically on both sides are taken once; anything
+/// else is a conflict. Line endings are kept as they are.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> Merge {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let to_ours = line_matches(&base, &ours);
+    let to_theirs = line_matches(&base, &theirs);
+
+    let mut content = String::new();
+    let mut conflicts = Vec::new();
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        // Next base line kept by both sides marks the end of a chunk
+        let stable = (b..base.len()).find_map(|k| match (to_ours[k], to_theirs[k]) {
+            (Some(ko), Some(kt)) => Some((k, ko, kt)),
+            _ => None,
+        });
+        let (kb, ko, kt) = stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        if (kb, ko, kt) != (b, o, t) {
+            let (base_chunk, our_chunk, their_chunk) = (&base[b..kb], &ours[o..ko], &theirs[t..kt]);
+            if our_chunk == base_chunk || our_chunk == their_chunk {
+                content.extend(their_chunk.iter().copied());
+            } else if their_chunk == base_chunk {
+                content.extend(our_chunk.iter().copied());
+            } else {
+                let line = content.matches('\n').count() + 1;
+                push_marked(&mut content, "<<<<<<< ours", our_chunk);
+                push_marked(&mut content, "=======", their_chunk);
+                content.push_str(">>>>>>> disk\n");
+                conflicts.push(ConflictHunk {
+                    line,
+                    base: chunk_lines(base_chunk),
+                    ours: chunk_lines(our_chunk),
+                    theirs: chunk_lines(their_chunk),
+                });
+            }
+        }
+
+        if kb == base.len() {
+            break;
+        }
+        content.push_str(base[kb]);
+        (b, o, t) = (kb + 1, ko + 1, kt + 1);
+    }
+
+    Merge { content, conflicts }
+}
+
+fn push_marked(content: &mut String, marker: &str, lines: &[&str]) {
+    content.push_str(marker);
+    content.push('\n');
+    for line in lines {
+        content.push_str(line);
+    }
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+}
+
+fn chunk_lines(lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|l| l.trim_end_matches(['\r', '\n']).to_string())
+        .collect()
+}
@@ -0,0 +1,295 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use super::config::find_host_block;
+use super::cst::{has_comment, quote_args, quote_value, ConfigDocument};
+use super::metadata::{format_metadata, is_metadata_comment, parse_metadata, validate_metadata};
+use super::types::{HostOption, SshHostDefinition};
+
+/// Options that have a dedicated field in [`SshHostDefinition`], spelled
+/// the way new lines are written
+const TYPED_KEYWORDS: &[&str] = &[
+    "HostName",
+    "User",
+    "Port",
+    "IdentityFile",
+    "ProxyJump",
+    "LocalForward",
+    "RemoteForward",
+    "DynamicForward",
+];
+
+/// Keywords that can't be set as host options
+const STRUCTURAL_KEYWORDS: &[&str] = &["host", "match", "include"];
+
+/// Config file (a root or an included file) whose `Host` line declares `alias`
+pub fn find_host_file(alias: &str) -> Option<PathBuf> {
+    find_host_block(alias).map(|(_, block)| block.source_file)
+}
+
+/// Check a definition before writing it, so that no value can break out
+/// of its line or turn into a pattern
+pub fn validate_definition(def: &SshHostDefinition) -> Result<(), String> {
+    let alias = def.alias.as_str();
+    if alias.is_empty() {
+        return Err("Host alias cannot be empty".to_string());
+    }
+    if alias
+        .contains(|c: char| c.is_whitespace() || matches!(c, '*' | '?' | '!' | '#' | '"' | '\''))
+    {
+        return Err(format!(
+            "Invalid host alias \"{}\": wildcards, quotes and whitespace are not allowed",
+            alias
+        ));
+    }
+
+    for (keyword, value) in raw_values(def) {
+        if value.contains(['\n', '\r']) {
+            return Err(format!("{} value cannot contain line breaks", keyword));
+        }
+    }
+    for (keyword, value) in argument_values(def) {
+        if has_comment(value) {
+            return Err(format!("{} value cannot contain a comment", keyword));
+        }
+    }
+
+    for option in &def.extra_options {
+        let key = option.keyword.to_lowercase();
+        if option.keyword.is_empty()
+            || option
+                .keyword
+                .contains(|c: char| c.is_whitespace() || c == '=' || c == '#')
+        {
+            return Err(format!("Invalid option keyword \"{}\"", option.keyword));
+        }
+        if STRUCTURAL_KEYWORDS.contains(&key.as_str()) {
+            return Err(format!(
+                "\"{}\" cannot be used as a host option",
+                option.keyword
+            ));
+        }
+        // A Port that isn't a number is kept as it was written
+        if key == "port" && def.port.is_none() {
+            continue;
+        }
+        if TYPED_KEYWORDS.iter().any(|k| k.to_lowercase() == key) {
+            return Err(format!(
+                "\"{}\" must be set through its dedicated field",
+                option.keyword
+            ));
+        }
+    }
+    validate_metadata(&def.metadata)
+}
+
+/// Read the block of `alias` back into a typed definition. `Include`
+/// lines in the block are left out: they stay where they are on update.
+/// A Port that isn't a number (`${SSH_PORT}`, a service name) becomes an
+/// extra option, so it is written back unchanged.
+pub fn host_definition(doc: &ConfigDocument, alias: &str) -> Option<SshHostDefinition> {
+    let mut def = SshHostDefinition {
+        alias: alias.to_string(),
+        ..Default::default()
+    };
+
+    let mut port_seen = false;
+    for d in doc.host_directives(alias)? {
+        let first = d.args().into_iter().next().unwrap_or_default();
+        match d.key().as_str() {
+            "include" => {}
+            // ssh keeps the first value of single-valued options
+            "hostname" => {
+                def.hostname.get_or_insert(first);
+            }
+            "user" => {
+                def.user.get_or_insert(first);
+            }
+            "port" => {
+                if !port_seen {
+                    port_seen = true;
+                    match first.parse() {
+                        Ok(port) => def.port = Some(port),
+                        Err(_) => def.extra_options.push(HostOption {
+                            keyword: d.keyword.clone(),
+                            value: d.value.clone(),
+                        }),
+                    }
+                }
+            }
+            "proxyjump" => {
+                def.proxy_jump.get_or_insert(first);
+            }
+            "identityfile" => def.identity_files.push(first),
+            "localforward" => def.local_forwards.push(d.value.clone()),
+            "remoteforward" => def.remote_forwards.push(d.value.clone()),
+            "dynamicforward" => def.dynamic_forwards.push(d.value.clone()),
+            _ => def.extra_options.push(HostOption {
+                keyword: d.keyword.clone(),
+                value: d.value.clone(),
+            }),
+        }
+    }
+    def.metadata = parse_metadata(&doc.host_comments(alias)?);
+    Some(def)
+}
+
+/// Values of the typed fields as written to the config, keyed by keyword,
+/// in [`TYPED_KEYWORDS`] order. Single-valued fields are one argument;
+/// forwards are argument lists, re-quoted argument by argument.
+fn typed_values(def: &SshHostDefinition) -> Vec<(&'static str, Vec<String>)> {
+    let quoted = |values: &[String]| values.iter().map(|v| quote_value(v)).collect();
+    let quoted_args = |values: &[String]| values.iter().map(|v| quote_args(v)).collect();
+    vec![
+        ("HostName", quoted(def.hostname.as_slice())),
+        ("User", quoted(def.user.as_slice())),
+        ("Port", def.port.iter().map(|p| p.to_string()).collect()),
+        ("IdentityFile", quoted(&def.identity_files)),
+        ("ProxyJump", quoted(def.proxy_jump.as_slice())),
+        ("LocalForward", quoted_args(&def.local_forwards)),
+        ("RemoteForward", quoted_args(&def.remote_forwards)),
+        ("DynamicForward", quoted_args(&def.dynamic_forwards)),
+    ]
+}
+
+/// Extra options grouped by keyword, in order of first appearance, with
+/// their values re-quoted argument by argument
+fn extra_values(def: &SshHostDefinition) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for option in &def.extra_options {
+        let value = quote_args(&option.value);
+        match groups
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&option.keyword))
+        {
+            Some((_, values)) => values.push(value),
+            None => groups.push((option.keyword.clone(), vec![value])),
+        }
+    }
+    groups
+}
+
+/// All options of a definition as `(keyword, value)` lines, quoted
+fn definition_options(def: &SshHostDefinition) -> Vec<(String, String)> {
+    let typed = typed_values(def)
+        .into_iter()
+        .flat_map(|(k, values)| values.into_iter().map(move |v| (k.to_string(), v)));
+    let extra = extra_values(def)
+        .into_iter()
+        .flat_map(|(k, values)| values.into_iter().map(move |v| (k.clone(), v)));
+    typed.chain(extra).collect()
+}
+
+/// Values of a definition as entered, keyed by keyword
+fn raw_values(def: &SshHostDefinition) -> impl Iterator<Item = (&str, &str)> {
+    let single = [
+        ("HostName", &def.hostname),
+        ("User", &def.user),
+        ("ProxyJump", &def.proxy_jump),
+    ]
+    .into_iter()
+    .filter_map(|(k, v)| v.as_deref().map(|v| (k, v)));
+    let identity = def
+        .identity_files
+        .iter()
+        .map(|v| ("IdentityFile", v.as_str()));
+    single.chain(identity).chain(argument_values(def))
+}
+
+/// Values that are argument lists in config syntax, keyed by keyword
+fn argument_values(def: &SshHostDefinition) -> impl Iterator<Item = (&str, &str)> {
+    let forwards = [
+        ("LocalForward", &def.local_forwards),
+        ("RemoteForward", &def.remote_forwards),
+        ("DynamicForward", &def.dynamic_forwards),
+    ]
+    .into_iter()
+    .flat_map(|(k, values)| values.iter().map(move |v| (k, v.as_str())));
+    let extra = def
+        .extra_options
+        .iter()
+        .map(|o| (o.keyword.as_str(), o.value.as_str()));
+    forwards.chain(extra)
+}
+
+/// Add a new `Host` block for `def`
+pub fn create_host(doc: &mut ConfigDocument, def: &SshHostDefinition) -> Result<(), String> {
+    validate_definition(def)?;
+    doc.add_host(std::slice::from_ref(&def.alias), &definition_options(def))?;
+    set_metadata(doc, &def.alias, def)
+}
+
+/// Write the metadata of `def` into the `#@ssh-m` comment of `alias`,
+/// leaving the comments alone if they already say the same
+fn set_metadata(
+    doc: &mut ConfigDocument,
+    alias: &str,
+    def: &SshHostDefinition,
+) -> Result<(), String> {
+    let current = doc.host_comments(alias).map(|c| parse_metadata(&c));
+    if current.as_ref() == Some(&def.metadata) {
+        return Ok(());
+    }
+    doc.set_host_comment(
+        alias,
+        is_metadata_comment,
+        format_metadata(&def.metadata).as_deref(),
+    )
+}
+
+/// Make the block of `alias` match `def`: rename if needed, set every
+/// typed field, extra option and metadata field, and drop options no
+/// longer present. `Include` lines are left alone.
+/// Untouched lines, comments and formatting are kept. An alias sharing its
+/// `Host` line with others is first split into its own block, right after
+/// the shared one, so the other aliases are not affected.
+pub fn update_host(
+    doc: &mut ConfigDocument,
+    alias: &str,
+    def: &SshHostDefinition,
+) -> Result<(), String> {
+    validate_definition(def)?;
+    doc.split_host(alias)?;
+    doc.rename_alias(alias, &def.alias)?;
+
+    let alias = def.alias.as_str();
+    let extra = extra_values(def);
+    let mut keep = HashSet::from(["include".to_string()]);
+    for (keyword, values) in typed_values(def) {
+        keep.insert(keyword.to_lowercase());
+        // An empty typed field doesn't remove the same keyword set as an
+        // extra option (a Port that isn't a number)
+        if values.is_empty() && extra.iter().any(|(k, _)| k.eq_ignore_ascii_case(keyword)) {
+            continue;
+        }
+        doc.set_option_values(alias, keyword, &values)?;
+    }
+    for (keyword, values) in extra {
+        keep.insert(keyword.to_lowercase());
+        doc.set_option_values(alias, &keyword, &values)?;
+    }
+
+    let stale: Vec<String> = doc
+        .host_directives(alias)
+        .unwrap_or_default()
+        .iter()
+        .filter(|d| !keep.contains(&d.key()))
+        .map(|d| d.keyword.clone())
+        .collect();
+    for keyword in stale {
+        doc.unset_option(alias, &keyword)?;
+    }
+    set_metadata(doc, alias, def)
+}
+
+/// Copy the block of `alias` to a new block named `new_alias`
+pub fn duplicate_host(
+    doc: &mut ConfigDocument,
+    alias: &str,
+    new_alias: &str,
+) -> Result<(), String> {
+    let mut def =
+        host_definition(doc, alias).ok_or_else(|| format!("Host \"{}\" not found", alias))?;
+    def.alias = new_alias.to_string();
+    create_host(doc, &def)
+}
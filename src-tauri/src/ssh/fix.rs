@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use super::cst::ConfigDocument;
+use super::types::{Diagnostic, SuggestedFix, TextEdit, TextPosition};
+use super::validate::format_message;
+
+/// Attach fixes that need the file's text to diagnostics reported on
+/// `file`: a `Host *` block that comes too early is moved to the end.
+pub fn attach_content_fixes(content: &str, file: &str, diagnostics: &mut [Diagnostic]) {
+    let doc = ConfigDocument::parse(content);
+    for diagnostic in diagnostics.iter_mut() {
+        if diagnostic.suggested_fix.is_some() || diagnostic.file.as_deref() != Some(file) {
+            continue;
+        }
+        if diagnostic.code == "wildcard-host-first" && diagnostic.line > 0 {
+            diagnostic.suggested_fix = move_block_to_end(&doc, diagnostic.line - 1);
+        }
+    }
+}
+
+/// Fix moving the block whose header is at line index `header` to the end
+/// of the document
+fn move_block_to_end(doc: &ConfigDocument, header: usize) -> Option<SuggestedFix> {
+    let range = doc.block_range(header)?;
+    let lines = doc.lines();
+    if range.end == lines.len() {
+        return None;
+    }
+
+    let ending = match lines.first().map(|l| l.ending.as_str()) {
+        Some("\r\n") => "\r\n",
+        _ => "\n",
+    };
+    let mut block_end = range.end;
+    while block_end > range.start && lines[block_end - 1].is_blank() {
+        block_end -= 1;
+    }
+    let mut new_text = String::new();
+    if lines.last().is_some_and(|l| l.ending.is_empty()) {
+        new_text.push_str(ending);
+    }
+    if lines.last().is_some_and(|l| !l.is_blank()) {
+        new_text.push_str(ending);
+    }
+    for line in &lines[range.start..block_end] {
+        new_text.push_str(line.to_string().trim_end_matches(['\r', '\n']));
+        new_text.push_str(ending);
+    }
+
+    let at_line = |line: usize| TextPosition { line, column: 0 };
+    let end_of_file = at_line(lines.len() + 1);
+    Some(SuggestedFix {
+        message_key: "fix_move_to_end".to_string(),
+        args: BTreeMap::new(),
+        message: format_message("fix_move_to_end", &BTreeMap::new()),
+        edits: vec![
+            TextEdit {
+                start: at_line(range.start + 1),
+                end: at_line(range.end + 1),
+                new_text: String::new(),
+            },
+            TextEdit {
+                start: end_of_file,
+                end: end_of_file,
+                new_text,
+            },
+        ],
+        chmod: None,
+    })
+}
+
+/// Apply the text edits of the given fixes to `content`. A fix whose edits
+/// overlap one already accepted is skipped as a whole; the indices of the
+/// fixes that were applied are returned with the new content.
+pub fn apply_fixes(content: &str, fixes: &[&SuggestedFix]) -> (String, Vec<usize>) {
+    let line_starts = line_starts(content);
+    let offset = |pos: &TextPosition| position_offset(content, &line_starts, pos);
+
+    let mut accepted: Vec<(usize, usize, &str)> = Vec::new();
+    let mut applied = Vec::new();
+    for (i, fix) in fixes.iter().enumerate() {
+        let edits: Vec<(usize, usize, &str)> = fix
+            .edits
+            .iter()
+            .map(|e| {
+                (
+                    offset(&e.start),
+                    offset(&e.end).max(offset(&e.start)),
+                    e.new_text.as_str(),
+                )
+            })
+            .collect();
+        let overlaps = edits.iter().any(|a| {
+            accepted
+                .iter()
+                .any(|b| (a.0 < b.1 && b.0 < a.1) || (a.0 == b.0 && (a.0 == a.1 || b.0 == b.1)))
+        });
+        if !overlaps {
+            accepted.extend(edits);
+            applied.push(i);
+        }
+    }
+
+    // Later edits first so earlier offsets stay valid
+    accepted.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+    let mut result = content.to_string();
+    for (start, end, new_text) in accepted {
+        result.replace_range(start..end, new_text);
+    }
+    (result, applied)
+}
+
+/// Byte offset at which each line starts
+fn line_starts(content: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+    if starts.last() == Some(&content.len()) {
+        starts.pop();
+    }
+    starts
+}
+
+/// Byte offset of `pos`; columns past the end of a line stop at its
+/// line ending, lines past the last one are the end of the content
+fn position_offset(content: &str, line_starts: &[usize], pos: &TextPosition) -> usize {
+    let Some(&start) = pos.line.checked_sub(1).and_then(|i| line_starts.get(i)) else {
+        return content.len();
+    };
+    let line = content[start..].split('\n').next().unwrap_or("");
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let column = line
+        .char_indices()
+        .nth(pos.column)
+        .map(|(i, _)| i)
+        .unwrap_or(line.len());
+    start + column
+}
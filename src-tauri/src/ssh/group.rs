@@ -0,0 +1,195 @@
+use std::net::IpAddr;
+
+use super::parser::split_args;
+use super::pattern::{match_pattern, match_pattern_list};
+use super::resolve::expand_tilde;
+use super::types::GroupRule;
+
+/// Group of hosts no rule or comment assigns one to
+pub const DEFAULT_GROUP: &str = "direct";
+
+/// Prefix of the comments that set a host's group and tags, e.g.
+/// `# ssh-m: group=prod tags=db,eu`
+const ANNOTATION_PREFIX: &str = "ssh-m:";
+
+/// What group rules are matched against
+pub struct HostFacts<'a> {
+    pub alias: &'a str,
+    pub hostname: &'a str,
+    pub has_proxy_jump: bool,
+    pub source_file: &'a str,
+}
+
+/// The rules used until the user changes them: code hosting sites,
+/// hosts behind a jump host, and private or loopback addresses
+pub fn default_group_rules() -> Vec<GroupRule> {
+    let rule = |group: &str| GroupRule {
+        group: Some(group.to_string()),
+        tags: Vec::new(),
+        alias: None,
+        hostname: None,
+        cidr: None,
+        proxy_jump: None,
+        source_file: None,
+    };
+    vec![
+        GroupRule {
+            alias: Some("*github*,*gitlab*,*bitbucket*".to_string()),
+            ..rule("git")
+        },
+        GroupRule {
+            hostname: Some(
+                "github.com,*.github.com,gitlab.com,*.gitlab.com,gitlab.*,bitbucket.org,\
+                 *.bitbucket.org,ssh.dev.azure.com,codeberg.org"
+                    .to_string(),
+            ),
+            ..rule("git")
+        },
+        GroupRule {
+            proxy_jump: Some(true),
+            ..rule("proxy")
+        },
+        GroupRule {
+            cidr: Some(
+                "127.0.0.0/8,10.0.0.0/8,172.16.0.0/12,192.168.0.0/16,169.254.0.0/16,\
+                 100.64.0.0/10,::1/128,fc00::/7,fe80::/10"
+                    .to_string(),
+            ),
+            ..rule("local")
+        },
+        GroupRule {
+            hostname: Some("localhost,*.local,*.localdomain".to_string()),
+            ..rule("local")
+        },
+    ]
+}
+
+/// Group and tags of a host: the group of the first matching rule that
+/// sets one, and the tags of all matching rules. `comments` are the
+/// host's block comments; a `# ssh-m:` comment there overrides the group
+/// and adds tags.
+pub fn classify(rules: &[GroupRule], host: &HostFacts, comments: &[&str]) -> (String, Vec<String>) {
+    let mut group = None;
+    let mut tags: Vec<String> = Vec::new();
+    for rule in rules.iter().filter(|r| rule_matches(r, host)) {
+        if group.is_none() {
+            group = rule.group.clone();
+        }
+        tags.extend(rule.tags.iter().cloned());
+    }
+
+    for (key, value) in annotation_fields(comments, ANNOTATION_PREFIX) {
+        match key.as_str() {
+            "group" if !value.is_empty() => group = Some(value),
+            "tags" => tags.extend(value.split(',').map(|t| t.trim().to_string())),
+            _ => {}
+        }
+    }
+
+    let mut unique: Vec<String> = Vec::new();
+    for tag in tags {
+        if !tag.is_empty() && !unique.contains(&tag) {
+            unique.push(tag);
+        }
+    }
+    (group.unwrap_or_else(|| DEFAULT_GROUP.to_string()), unique)
+}
+
+/// `key=value` fields of the comments starting with `prefix`, in order
+pub fn annotation_fields(comments: &[&str], prefix: &str) -> Vec<(String, String)> {
+    comments
+        .iter()
+        .filter_map(|c| c.trim().strip_prefix(prefix))
+        .flat_map(split_args)
+        .filter_map(|field| {
+            let (key, value) = field.split_once('=')?;
+            Some((key.to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+fn rule_matches(rule: &GroupRule, host: &HostFacts) -> bool {
+    let alias = rule
+        .alias
+        .as_deref()
+        .is_none_or(|p| match_pattern_list(host.alias, p, true));
+    let hostname = rule
+        .hostname
+        .as_deref()
+        .is_none_or(|p| match_pattern_list(host.hostname, p, true));
+    let cidr = rule
+        .cidr
+        .as_deref()
+        .is_none_or(|list| in_any_network(host.hostname, list));
+    let proxy_jump = rule.proxy_jump.is_none_or(|p| p == host.has_proxy_jump);
+    let source_file = rule
+        .source_file
+        .as_deref()
+        .is_none_or(|p| match_pattern(host.source_file, &expand_tilde(p)));
+    alias && hostname && cidr && proxy_jump && source_file
+}
+
+/// Whether `hostname` is an IP address inside one of the comma-separated
+/// networks in `list`. Names are not looked up.
+fn in_any_network(hostname: &str, list: &str) -> bool {
+    // Link-local addresses may carry a zone, e.g. `fe80::1%eth0`
+    let address = hostname.split('%').next().unwrap_or(hostname);
+    let Ok(address) = address.parse::<IpAddr>() else {
+        return false;
+    };
+    list.split(',')
+        .filter_map(|cidr| parse_network(cidr.trim()).ok())
+        .any(|(network, prefix)| in_network(address, network, prefix))
+}
+
+/// Parse `address/prefix`; a bare address is a network of one
+fn parse_network(cidr: &str) -> Result<(IpAddr, u32), String> {
+    let (address, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+    let address: IpAddr = address
+        .parse()
+        .map_err(|_| format!("Invalid network address: {}", cidr))?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = if prefix.is_empty() {
+        max
+    } else {
+        prefix
+            .parse::<u32>()
+            .ok()
+            .filter(|p| *p <= max)
+            .ok_or_else(|| format!("Invalid network prefix: {}", cidr))?
+    };
+    Ok((address, prefix))
+}
+
+fn in_network(address: IpAddr, network: IpAddr, prefix: u32) -> bool {
+    let (address, network, bits) = match (address, network) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => (u32::from(a) as u128, u32::from(n) as u128, 32),
+        (IpAddr::V6(a), IpAddr::V6(n)) => (u128::from(a), u128::from(n), 128),
+        // An IPv4-mapped IPv6 address against an IPv4 network
+        (IpAddr::V6(a), IpAddr::V4(n)) => match a.to_ipv4_mapped() {
+            Some(a) => (u32::from(a) as u128, u32::from(n) as u128, 32),
+            None => return false,
+        },
+        (IpAddr::V4(_), IpAddr::V6(_)) => return false,
+    };
+    if prefix == 0 {
+        return true;
+    }
+    let shift = bits - prefix;
+    address >> shift == network >> shift
+}
+
+/// Check the rules from the settings before they are saved
+pub fn validate_group_rules(rules: &[GroupRule]) -> Result<(), String> {
+    for (i, rule) in rules.iter().enumerate() {
+        if let Some(list) = &rule.cidr {
+            for cidr in list.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+                parse_network(cidr).map_err(|e| format!("Group rule {}: {}", i + 1, e))?;
+            }
+        }
+        if rule.group.as_deref().is_some_and(|g| g.trim().is_empty()) {
+            return Err(format!("Group rule {}: group name is empty", i + 1));
+        }
+    }
+    Ok(())
+}
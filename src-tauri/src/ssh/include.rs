@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+
+use super::parser::{parse_directives, Directive};
+
+/// Maximum Include nesting depth, same as OpenSSH's READCONF_MAX_DEPTH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A config file with its Include directives resolved into sub-files
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub items: Vec<ConfigItem>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfigItem {
+    Directive(Directive),
+    /// An `Include` line, replaced by the files its patterns expanded to
+    Include {
+        files: Vec<ConfigFile>,
+    },
+}
+
+/// Directory that relative Include paths in user configs are resolved
+/// against. OpenSSH uses `~/.ssh`, even when the config is given via `ssh -F`.
+pub fn include_base_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(".ssh")
+}
+
+/// Expand `~` and `${VAR}` in an Include argument
+pub fn expand_path_tokens(pattern: &str) -> String {
+    let home = dirs::home_dir().unwrap_or_default();
+    let mut expanded = if pattern == "~" {
+        home.to_string_lossy().to_string()
+    } else if let Some(rest) = pattern.strip_prefix("~/") {
+        home.join(rest).to_string_lossy().to_string()
+    } else {
+        pattern.to_string()
+    };
+
+    while let Some(start) = expanded.find("${") {
+        let Some(len) = expanded[start..].find('}') else {
+            break;
+        };
+        let name = &expanded[start + 2..start + len];
+        let value = std::env::var(name).unwrap_or_default();
+        expanded.replace_range(start..start + len + 1, &value);
+    }
+    expanded
+}
+
+/// Expand a single Include pattern into the list of files it refers to,
+/// sorted like glob(3). Missing files and unmatched globs are ignored.
+pub fn expand_include_pattern(pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let expanded = expand_path_tokens(pattern);
+    let full_pattern = if Path::new(&expanded).is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded).to_string_lossy().to_string()
+    };
+
+    let mut files = Vec::new();
+    if let Ok(paths) = glob(&full_pattern) {
+        for entry in paths.flatten() {
+            if entry.is_file() {
+                files.push(entry);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Load a config file and recursively resolve its Include directives.
+///
+/// Cycles are broken by skipping any file that is already being loaded
+/// further up the include chain; the same file may still be included from
+/// several unrelated places, as OpenSSH allows.
+pub fn load_config_file(path: &Path) -> Result<ConfigFile, String> {
+    load_config_file_in(path, &include_base_dir())
+}
+
+/// Like [`load_config_file`], resolving relative Include paths against
+/// `base_dir` (e.g. `/etc/ssh` for the system-wide config)
+pub fn load_config_file_in(path: &Path, base_dir: &Path) -> Result<ConfigFile, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read SSH config {}: {}", path.display(), e))?;
+    let mut chain = Vec::new();
+    Ok(load_with_content(
+        path, &content, base_dir, None, &mut chain, 0,
+    ))
+}
+
+/// Load the config at `root` with the file at `path` taken to contain
+/// `content` (e.g. unsaved editor text) instead of what is on disk,
+/// wherever it appears in the include graph
+pub fn load_config_file_with(
+    root: &Path,
+    path: &Path,
+    content: &str,
+) -> Result<ConfigFile, String> {
+    let target = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let substitute = (target.as_path(), content);
+    let root_canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let root_content = if root_canonical == target {
+        content.to_string()
+    } else {
+        fs::read_to_string(root)
+            .map_err(|e| format!("Failed to read SSH config {}: {}", root.display(), e))?
+    };
+    let mut chain = Vec::new();
+    Ok(load_with_content(
+        root,
+        &root_content,
+        &include_base_dir(),
+        Some(substitute),
+        &mut chain,
+        0,
+    ))
+}
+
+/// Build a config file from `content` (e.g. unsaved editor text) as if it
+/// were stored at `path`, resolving its Include directives from disk
+pub fn parse_config_content(path: &Path, content: &str) -> ConfigFile {
+    let mut chain = Vec::new();
+    load_with_content(path, content, &include_base_dir(), None, &mut chain, 0)
+}
+
+/// `substitute` is a canonical path and the content to use for it in
+/// place of the file on disk
+fn load_with_content(
+    path: &Path,
+    content: &str,
+    base_dir: &Path,
+    substitute: Option<(&Path, &str)>,
+    chain: &mut Vec<PathBuf>,
+    depth: usize,
+) -> ConfigFile {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    chain.push(canonical);
+
+    let mut items = Vec::new();
+    for directive in parse_directives(content) {
+        if directive.key() != "include" {
+            items.push(ConfigItem::Directive(directive));
+            continue;
+        }
+
+        let mut files = Vec::new();
+        if depth < MAX_INCLUDE_DEPTH {
+            for pattern in &directive.args {
+                for inc_path in expand_include_pattern(pattern, base_dir) {
+                    let inc_canonical =
+                        inc_path.canonicalize().unwrap_or_else(|_| inc_path.clone());
+                    if chain.contains(&inc_canonical) {
+                        continue;
+                    }
+                    let inc_content = match substitute {
+                        Some((target, text)) if target == inc_canonical => text.to_string(),
+                        _ => match fs::read_to_string(&inc_path) {
+                            Ok(text) => text,
+                            Err(_) => continue,
+                        },
+                    };
+                    files.push(load_with_content(
+                        &inc_path,
+                        &inc_content,
+                        base_dir,
+                        substitute,
+                        chain,
+                        depth + 1,
+                    ));
+                }
+            }
+        }
+        items.push(ConfigItem::Include { files });
+    }
+
+    chain.pop();
+    ConfigFile {
+        path: path.to_path_buf(),
+        items,
+    }
+}
+
+impl ConfigFile {
+    /// All files referenced by Include directives, recursively, in
+    /// include order and without duplicates. Does not include `self`.
+    pub fn included_files(&self) -> Vec<PathBuf> {
+        let mut seen = HashSet::new();
+        seen.insert(self.path.clone());
+        let mut result = Vec::new();
+        self.collect_included(&mut seen, &mut result);
+        result
+    }
+
+    fn collect_included(&self, seen: &mut HashSet<PathBuf>, result: &mut Vec<PathBuf>) {
+        for item in &self.items {
+            if let ConfigItem::Include { files } = item {
+                for file in files {
+                    if seen.insert(file.path.clone()) {
+                        result.push(file.path.clone());
+                    }
+                    file.collect_included(seen, result);
+                }
+            }
+        }
+    }
+}
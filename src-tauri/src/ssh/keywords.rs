@@ -0,0 +1,371 @@
+use std::fmt;
+use std::process::Command;
+
+use super::parser::split_args;
+use Grammar::*;
+
+/// An OpenSSH release, e.g. `9.5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl Version {
+    /// Parse `9.5`, `9.5p1` or `ssh -V` output such as
+    /// `OpenSSH_9.5p1, OpenSSL 3.0.13`
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix("OpenSSH_").unwrap_or(text);
+        let (major, rest) = text.split_once('.')?;
+        let minor: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// What a keyword's value must look like
+#[derive(Debug, Clone, Copy)]
+pub enum Grammar {
+    /// Anything ssh accepts without further structure (paths, host names,
+    /// patterns, commands)
+    Text,
+    YesNo,
+    /// One of the listed words, case-insensitive
+    OneOf(&'static [&'static str]),
+    /// A non-negative integer
+    Integer,
+    /// A port number, 1-65535
+    Port,
+    /// Seconds, or a sequence of numbers with s/m/h/d/w units (`1h30m`)
+    TimeInterval,
+    /// Words from the list and/or a time interval (`confirm 10m`)
+    OneOfOrTime(&'static [&'static str]),
+    /// Comma-separated algorithm names; the list may start with `+`
+    /// (append), `-` (remove) or `^` (prepend to the defaults)
+    AlgorithmList,
+    /// `[bind_address:]port host:hostport`, either side may be a socket path
+    LocalForward,
+    /// As LocalForward; with only the listen side it is a SOCKS proxy
+    RemoteForward,
+    /// `[bind_address:]port`
+    DynamicForward,
+    /// `yes`, `no` or `interval:<milliseconds>`
+    KeystrokeTiming,
+}
+
+impl Grammar {
+    /// Whether `value` (the raw text after the keyword) is valid. Quotes
+    /// are removed first, as ssh does, so `Port "22"` is accepted.
+    pub fn accepts(&self, value: &str) -> bool {
+        let value = &split_args(value).join(" ");
+        let lower = value.to_lowercase();
+        match self {
+            Text => true,
+            YesNo => matches!(lower.as_str(), "yes" | "no"),
+            OneOf(words) => words.contains(&lower.as_str()),
+            Integer => value.parse::<u32>().is_ok(),
+            Port => value.parse::<u16>().is_ok_and(|p| p > 0),
+            TimeInterval => is_time_interval(value),
+            OneOfOrTime(words) => lower
+                .split_whitespace()
+                .all(|w| words.contains(&w) || is_time_interval(w)),
+            AlgorithmList => is_algorithm_list(value),
+            LocalForward => match value.split_whitespace().collect::<Vec<_>>()[..] {
+                [listen, connect] => is_listen_spec(listen) && is_connect_spec(connect),
+                _ => false,
+            },
+            RemoteForward => match value.split_whitespace().collect::<Vec<_>>()[..] {
+                [listen] => is_listen_spec(listen),
+                [listen, connect] => is_listen_spec(listen) && is_connect_spec(connect),
+                _ => false,
+            },
+            DynamicForward => is_listen_spec(value) && !value.contains('/'),
+            KeystrokeTiming => match lower.strip_prefix("interval:") {
+                Some(ms) => ms.parse::<u32>().is_ok(),
+                None => matches!(lower.as_str(), "yes" | "no"),
+            },
+        }
+    }
+}
+
+/// A keyword that replaced another, and when
+#[derive(Debug, Clone, Copy)]
+pub struct Deprecation {
+    pub since: Version,
+    pub replacement: Option<&'static str>,
+}
+
+/// An ssh_config(5) keyword
+#[derive(Debug, Clone, Copy)]
+pub struct Keyword {
+    /// Spelled as in the manual
+    pub name: &'static str,
+    pub grammar: Grammar,
+    /// Release that added the keyword; `None` for keywords older than any
+    /// release still in use
+    pub since: Option<Version>,
+    pub deprecated: Option<Deprecation>,
+}
+
+impl Keyword {
+    const fn since(self, major: u8, minor: u8) -> Self {
+        Self {
+            since: Some(Version { major, minor }),
+            ..self
+        }
+    }
+
+    const fn deprecated(self, major: u8, minor: u8, replacement: Option<&'static str>) -> Self {
+        Self {
+            deprecated: Some(Deprecation {
+                since: Version { major, minor },
+                replacement,
+            }),
+            ..self
+        }
+    }
+}
+
+const fn kw(name: &'static str, grammar: Grammar) -> Keyword {
+    Keyword {
+        name,
+        grammar,
+        since: None,
+        deprecated: None,
+    }
+}
+
+const LOG_LEVELS: &[&str] = &[
+    "quiet", "fatal", "error", "info", "verbose", "debug", "debug1", "debug2", "debug3",
+];
+
+/// Client keywords known to ssh_config(5), including deprecated ones
+pub const KEYWORDS: &[Keyword] = &[
+    kw(
+        "AddKeysToAgent",
+        OneOfOrTime(&["yes", "no", "ask", "confirm"]),
+    )
+    .since(7, 2),
+    kw("AddressFamily", OneOf(&["any", "inet", "inet6"])),
+    kw("BatchMode", YesNo),
+    kw("BindAddress", Text),
+    kw("BindInterface", Text).since(7, 9),
+    kw("CanonicalDomains", Text).since(6, 5),
+    kw("CanonicalizeFallbackLocal", YesNo).since(6, 5),
+    kw(
+        "CanonicalizeHostname",
+        OneOf(&["yes", "no", "always", "none"]),
+    )
+    .since(6, 5),
+    kw("CanonicalizeMaxDots", Integer).since(6, 5),
+    kw("CanonicalizePermittedCNAMEs", Text).since(6, 5),
+    kw("CASignatureAlgorithms", AlgorithmList).since(7, 9),
+    kw("CertificateFile", Text).since(7, 2),
+    kw("ChallengeResponseAuthentication", YesNo).deprecated(
+        8,
+        7,
+        Some("KbdInteractiveAuthentication"),
+    ),
+    kw("ChannelTimeout", Text).since(9, 2),
+    kw("CheckHostIP", YesNo),
+    kw("Cipher", Text).deprecated(7, 4, None),
+    kw("Ciphers", AlgorithmList),
+    kw("ClearAllForwardings", YesNo),
+    kw("Compression", YesNo),
+    kw("CompressionLevel", Integer).deprecated(7, 4, None),
+    kw("ConnectionAttempts", Integer),
+    kw("ConnectTimeout", TimeInterval),
+    kw(
+        "ControlMaster",
+        OneOf(&["yes", "no", "ask", "auto", "autoask"]),
+    ),
+    kw("ControlPath", Text),
+    kw("ControlPersist", OneOfOrTime(&["yes", "no"])),
+    kw("DynamicForward", DynamicForward),
+    kw("EnableEscapeCommandline", YesNo).since(9, 2),
+    kw("EnableSSHKeysign", YesNo),
+    kw("EscapeChar", Text),
+    kw("ExitOnForwardFailure", YesNo),
+    kw("FingerprintHash", OneOf(&["md5", "sha256"])).since(6, 8),
+    kw("ForkAfterAuthentication", YesNo).since(8, 7),
+    // Also takes a socket path or environment variable since 8.1
+    kw("ForwardAgent", Text),
+    kw("ForwardX11", YesNo),
+    kw("ForwardX11Timeout", TimeInterval),
+    kw("ForwardX11Trusted", YesNo),
+    kw("GatewayPorts", YesNo),
+    kw("GlobalKnownHostsFile", Text),
+    kw("GSSAPIAuthentication", YesNo),
+    kw("GSSAPIDelegateCredentials", YesNo),
+    kw("HashKnownHosts", YesNo),
+    kw("Host", Text),
+    kw("HostbasedAcceptedAlgorithms", AlgorithmList).since(8, 5),
+    kw("HostbasedAuthentication", YesNo),
+    kw("HostbasedKeyTypes", AlgorithmList).deprecated(8, 5, Some("HostbasedAcceptedAlgorithms")),
+    kw("HostKeyAlgorithms", AlgorithmList),
+    kw("HostKeyAlias", Text),
+    kw("HostName", Text),
+    kw("IdentitiesOnly", YesNo),
+    kw("IdentityAgent", Text).since(7, 3),
+    kw("IdentityFile", Text),
+    kw("IgnoreUnknown", Text).since(6, 3),
+    kw("Include", Text).since(7, 3),
+    kw("IPQoS", Text),
+    kw("KbdInteractiveAuthentication", YesNo),
+    kw("KbdInteractiveDevices", Text),
+    kw("KexAlgorithms", AlgorithmList),
+    kw("KnownHostsCommand", Text).since(8, 5),
+    kw("LocalCommand", Text),
+    kw("LocalForward", LocalForward),
+    kw("LogLevel", OneOf(LOG_LEVELS)),
+    kw("LogVerbose", Text).since(8, 5),
+    kw("MACs", AlgorithmList),
+    kw("Match", Text).since(6, 5),
+    kw("NoHostAuthenticationForLocalhost", YesNo),
+    kw("NumberOfPasswordPrompts", Integer),
+    kw("ObscureKeystrokeTiming", KeystrokeTiming).since(9, 5),
+    kw("PasswordAuthentication", YesNo),
+    kw("PermitLocalCommand", YesNo),
+    kw("PermitRemoteOpen", Text).since(8, 2),
+    kw("PKCS11Provider", Text),
+    kw("Port", Port),
+    kw("PreferredAuthentications", Text),
+    kw("Protocol", Text).deprecated(7, 4, None),
+    kw("ProxyCommand", Text),
+    kw("ProxyJump", Text).since(7, 3),
+    kw("ProxyUseFdpass", YesNo).since(6, 5),
+    kw("PubkeyAcceptedAlgorithms", AlgorithmList).since(8, 5),
+    kw("PubkeyAcceptedKeyTypes", AlgorithmList).deprecated(8, 5, Some("PubkeyAcceptedAlgorithms")),
+    kw(
+        "PubkeyAuthentication",
+        OneOf(&["yes", "no", "unbound", "host-bound"]),
+    ),
+    kw("RekeyLimit", Text),
+    kw("RemoteCommand", Text).since(7, 6),
+    kw("RemoteForward", RemoteForward),
+    kw("RequestTTY", OneOf(&["yes", "no", "force", "auto"])),
+    kw("RequiredRSASize", Integer).since(9, 1),
+    kw("RevokedHostKeys", Text).since(6, 8),
+    kw("RhostsRSAAuthentication", YesNo).deprecated(7, 4, None),
+    kw("RSAAuthentication", YesNo).deprecated(7, 4, None),
+    kw("SecurityKeyProvider", Text).since(8, 2),
+    kw("SendEnv", Text),
+    kw("ServerAliveCountMax", Integer),
+    kw("ServerAliveInterval", TimeInterval),
+    kw("SessionType", OneOf(&["none", "subsystem", "default"])).since(8, 7),
+    kw("SetEnv", Text).since(7, 8),
+    kw("StdinNull", YesNo).since(8, 7),
+    kw("StreamLocalBindMask", Text).since(6, 7),
+    kw("StreamLocalBindUnlink", YesNo).since(6, 7),
+    kw(
+        "StrictHostKeyChecking",
+        OneOf(&["yes", "no", "ask", "accept-new", "off"]),
+    ),
+    kw("SyslogFacility", Text),
+    kw("Tag", Text).since(9, 4),
+    kw("TCPKeepAlive", YesNo),
+    kw(
+        "Tunnel",
+        OneOf(&["yes", "no", "point-to-point", "ethernet"]),
+    ),
+    kw("TunnelDevice", Text),
+    kw("UpdateHostKeys", OneOf(&["yes", "no", "ask"])).since(6, 8),
+    kw("UsePrivilegedPort", YesNo).deprecated(7, 5, None),
+    kw("User", Text),
+    kw("UserKnownHostsFile", Text),
+    kw("UseRoaming", YesNo).deprecated(7, 2, None),
+    kw("VerifyHostKeyDNS", OneOf(&["yes", "no", "ask"])),
+    kw("VisualHostKey", YesNo),
+    kw("XAuthLocation", Text),
+];
+
+/// Look up a keyword, case-insensitively
+pub fn find_keyword(name: &str) -> Option<&'static Keyword> {
+    KEYWORDS.iter().find(|k| k.name.eq_ignore_ascii_case(name))
+}
+
+/// Releases the keyword table refers to, newest first
+pub fn known_versions() -> Vec<Version> {
+    let mut versions: Vec<Version> = KEYWORDS
+        .iter()
+        .flat_map(|k| [k.since, k.deprecated.map(|d| d.since)])
+        .flatten()
+        .collect();
+    versions.sort_by(|a, b| b.cmp(a));
+    versions.dedup();
+    versions
+}
+
+/// Version of the local `ssh` client, from `ssh -V`
+pub fn installed_version() -> Option<Version> {
+    let output = Command::new("ssh").arg("-V").output().ok()?;
+    // ssh -V prints to stderr
+    let text = String::from_utf8_lossy(&output.stderr);
+    Version::parse(&text)
+}
+
+/// `30`, `10m`, `1h30m`: numbers, each optionally followed by a unit
+fn is_time_interval(value: &str) -> bool {
+    let mut rest = value;
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return false;
+        }
+        rest = &rest[digits..];
+        if let Some(unit) = rest.chars().next().filter(|c| "sSmMhHdDwW".contains(*c)) {
+            rest = &rest[unit.len_utf8()..];
+        }
+    }
+    true
+}
+
+fn is_algorithm_list(value: &str) -> bool {
+    let list = value.strip_prefix(['+', '-', '^']).unwrap_or(value);
+    !list.is_empty()
+        && list.split(',').all(|name| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "@.-_*?!".contains(c))
+        })
+}
+
+/// `host:port` or `[v6addr]:port`, split at the port
+fn split_port(spec: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = spec.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        return Some((host, port));
+    }
+    spec.rsplit_once(':')
+}
+
+/// Listening side of a forward: `[bind_address:]port` or a socket path
+fn is_listen_spec(spec: &str) -> bool {
+    if spec.contains('/') {
+        return true;
+    }
+    let port = split_port(spec).map(|(_, p)| p).unwrap_or(spec);
+    port.parse::<u16>().is_ok()
+}
+
+/// Connecting side of a forward: `host:hostport` or a socket path
+fn is_connect_spec(spec: &str) -> bool {
+    if spec.starts_with('/') {
+        return true;
+    }
+    split_port(spec)
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok_and(|p| p > 0))
+}
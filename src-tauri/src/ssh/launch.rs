@@ -0,0 +1,488 @@
+use std::net::Ipv6Addr;
+
+use super::config::{config_file_args, find_host_root, ssh_config_path};
+use super::profile::parse_ssh_options;
+use super::resolve::resolve_host;
+use super::types::LaunchProfile;
+
+/// A validated `ssh` invocation, kept as an argument vector and only turned
+/// into a command line by quoting it for the shell that will run it.
+///
+/// The destination is either an alias declared on a `Host` line of the
+/// configured files, or an explicit `user@host[:port]` target whose parts
+/// have been checked; anything else is refused, so text from the webview
+/// can never reach a shell or an ssh option unchecked. A local tmux
+/// session running several such invocations can be launched the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshLaunch {
+    /// `ssh` followed by its arguments
+    argv: Vec<String>,
+    /// The alias or `user@host[:port]` launched
+    target: String,
+    /// The parts of an explicit target; `None` for an alias
+    explicit: Option<(String, String, Option<u16>)>,
+}
+
+/// Where a launch connects to, for showing it or passing it to a terminal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub host: String,
+    pub user: String,
+    pub port: u16,
+}
+
+impl SshLaunch {
+    /// Build the invocation for `target`, a config alias or `user@host[:port]`
+    pub fn for_target(target: &str) -> Result<Self, String> {
+        if let Some(root) = find_host_root(target) {
+            if !is_safe_alias(target) {
+                return Err(format!(
+                    "Host alias \"{}\" can't be launched safely",
+                    target
+                ));
+            }
+            let mut argv = vec!["ssh".to_string()];
+            argv.extend(config_file_args(&root));
+            argv.extend(["--".to_string(), target.to_string()]);
+            return Ok(Self {
+                argv,
+                target: target.to_string(),
+                explicit: None,
+            });
+        }
+
+        let (user, host, port) = parse_explicit_target(target)?;
+        let mut argv = vec!["ssh".to_string()];
+        argv.extend(config_file_args(&ssh_config_path()));
+        if let Some(port) = port {
+            argv.extend(["-p".to_string(), port.to_string()]);
+        }
+        argv.extend([
+            "-l".to_string(),
+            user.clone(),
+            "--".to_string(),
+            host.clone(),
+        ]);
+        Ok(Self {
+            argv,
+            target: target.to_string(),
+            explicit: Some((user, host, port)),
+        })
+    }
+
+    /// Add a launch profile's ssh options before the destination and its
+    /// remote command after it, with a terminal allocated for the command
+    /// unless the options already say whether to (`-t`, `-T`, `-At`, ...)
+    pub fn with_profile(mut self, profile: &LaunchProfile) -> Self {
+        let mut options = profile.ssh_options.clone();
+        let command = profile
+            .remote_command
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+        let sets_tty = parse_ssh_options(&options)
+            .is_ok_and(|flags| flags.iter().any(|(f, _)| matches!(f, 't' | 'T')));
+        if command.is_some() && !sets_tty {
+            options.push("-t".to_string());
+        }
+        let at = self
+            .argv
+            .iter()
+            .position(|a| a == "--")
+            .unwrap_or(self.argv.len());
+        self.argv.splice(at..at, options);
+        self.argv.extend(command.map(str::to_string));
+        self
+    }
+
+    /// A local tmux session `session` with one tiled pane per launch,
+    /// optionally with input synchronized across the panes
+    pub fn tmux_session(
+        session: &str,
+        launches: &[SshLaunch],
+        synchronize: bool,
+    ) -> Result<Self, String> {
+        check_tmux_session_name(session)?;
+        let (first, rest) = launches
+            .split_first()
+            .ok_or_else(|| "A tmux session needs at least one host".to_string())?;
+        let mut argv: Vec<String> = ["tmux", "new-session", "-s", session, "-n", session]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        // tmux runs each pane's command with the shell
+        argv.push(first.posix_command());
+        for launch in rest {
+            argv.extend([";".to_string(), "split-window".to_string()]);
+            argv.push(launch.posix_command());
+            // Re-tile after each split so panes don't get too small to split
+            argv.extend([";", "select-layout", "tiled"].map(str::to_string));
+        }
+        if synchronize {
+            argv.extend([";", "set-window-option", "synchronize-panes", "on"].map(str::to_string));
+        }
+        Ok(Self {
+            argv,
+            target: session.to_string(),
+            explicit: None,
+        })
+    }
+
+    /// Attach to the existing local tmux session `session`
+    pub fn tmux_attach(session: &str) -> Result<Self, String> {
+        check_tmux_session_name(session)?;
+        Ok(Self {
+            // `=` matches the name exactly rather than as a prefix
+            argv: vec![
+                "tmux".to_string(),
+                "attach-session".to_string(),
+                "-t".to_string(),
+                format!("={}", session),
+            ],
+            target: session.to_string(),
+            explicit: None,
+        })
+    }
+
+    /// The alias or `user@host[:port]` this launch was built for, or the
+    /// tmux session name
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Whether this launch connects to an explicit `user@host[:port]`
+    /// rather than a config alias
+    pub fn is_explicit(&self) -> bool {
+        self.explicit.is_some()
+    }
+
+    /// Effective host, user and port: resolved from the config, with the
+    /// user and port of an explicit target taking precedence
+    pub fn endpoint(&self) -> Endpoint {
+        let (name, user, port) = match &self.explicit {
+            Some((user, host, port)) => (host.as_str(), Some(user.as_str()), *port),
+            None => (self.target.as_str(), None, None),
+        };
+        let resolved = resolve_host(name).ok();
+        Endpoint {
+            host: resolved
+                .as_ref()
+                .map_or_else(|| name.to_string(), |r| r.hostname.clone()),
+            user: user
+                .map(str::to_string)
+                .or_else(|| resolved.as_ref().map(|r| r.user.clone()))
+                .unwrap_or_default(),
+            port: port.or(resolved.map(|r| r.port)).unwrap_or(22),
+        }
+    }
+
+    /// Program and arguments, for spawning without a shell
+    pub fn argv(&self) -> &[String] {
+        &self.argv
+    }
+
+    /// The invocation as a POSIX shell command line
+    pub fn posix_command(&self) -> String {
+        self.argv
+            .iter()
+            .map(|a| shell_quote(a))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The POSIX command line as an AppleScript string literal, quotes
+    /// included, e.g. for `do script`
+    pub fn applescript_literal(&self) -> String {
+        format!("\"{}\"", applescript_escape(&self.posix_command()))
+    }
+
+    /// A bash script that deletes itself and runs the invocation, for
+    /// terminals that open `.command` files
+    pub fn command_script(&self) -> String {
+        format!("#!/bin/bash\nrm -f \"$0\"\nexec {}\n", self.posix_command())
+    }
+
+    /// The invocation as a cmd.exe command line
+    pub fn cmd_command(&self) -> Result<String, String> {
+        let args: Result<Vec<String>, String> = self.argv.iter().map(|a| cmd_quote(a)).collect();
+        Ok(args?.join(" "))
+    }
+}
+
+/// Split and check an explicit `user@host[:port]` target. IPv6 addresses
+/// go in brackets: `user@[::1]:2222`.
+fn parse_explicit_target(target: &str) -> Result<(String, String, Option<u16>), String> {
+    let unknown = || {
+        format!(
+            "\"{}\" is neither a configured host alias nor a user@host[:port] target",
+            target
+        )
+    };
+    let (user, rest) = target.split_once('@').ok_or_else(unknown)?;
+    if !is_valid_user(user) {
+        return Err(format!("Invalid user name \"{}\"", user));
+    }
+
+    let (host, port) = if let Some(bracketed) = rest.strip_prefix('[') {
+        let (address, after) = bracketed.split_once(']').ok_or_else(unknown)?;
+        if address.parse::<Ipv6Addr>().is_err() {
+            return Err(format!("Invalid IPv6 address \"{}\"", address));
+        }
+        let port = match after {
+            "" => None,
+            _ => Some(after.strip_prefix(':').ok_or_else(unknown)?),
+        };
+        (address, port)
+    } else {
+        match rest.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (rest, None),
+        }
+    };
+    if !host.contains(':') && !is_valid_hostname(host) {
+        return Err(format!("Invalid host name \"{}\"", host));
+    }
+    let port = port
+        .map(|p| {
+            p.parse::<u16>()
+                .ok()
+                .filter(|p| *p > 0)
+                .ok_or_else(|| format!("Invalid port \"{}\"", p))
+        })
+        .transpose()?;
+    Ok((user.to_string(), host.to_string(), port))
+}
+
+/// tmux session names can't contain `.` or `:`; keep them to plain words
+fn check_tmux_session_name(session: &str) -> Result<(), String> {
+    let valid = !session.is_empty()
+        && !session.starts_with('-')
+        && session
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid tmux session name \"{}\"", session))
+    }
+}
+
+/// Letters, digits, `.`, `_` and `-`, not starting with `-`
+fn is_valid_user(user: &str) -> bool {
+    !user.is_empty()
+        && !user.starts_with('-')
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// A DNS name or IPv4 address: dot-separated labels of letters, digits and
+/// `-`, none starting or ending with `-`
+fn is_valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Aliases come from the config, but patterns, a leading `-` or control
+/// characters still must not reach a command line
+fn is_safe_alias(alias: &str) -> bool {
+    !alias.is_empty()
+        && !alias.starts_with('-')
+        && !alias.contains(['*', '?', '!'])
+        && !alias.chars().any(char::is_control)
+}
+
+/// Quote `arg` for a POSIX shell, leaving plain words as they are
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./@:%+=,".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Escape `s` for use inside an AppleScript string literal
+pub fn applescript_escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('"', "\\\"")
+}
+
+/// Quote `arg` for cmd.exe. Inside double quotes cmd treats `&|<>^()` as
+/// literal text, but `%` and `!` still expand variables and a `"` can't be
+/// escaped, so arguments containing those are refused. Backslashes before
+/// the closing quote are doubled, as the program's argv parser would read
+/// `\"` as an escaped quote.
+pub fn cmd_quote(arg: &str) -> Result<String, String> {
+    if arg.contains(['"', '%', '!']) || arg.chars().any(char::is_control) {
+        return Err(format!("\"{}\" can't be passed to cmd.exe safely", arg));
+    }
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./@:+\\".contains(c));
+    if plain {
+        Ok(arg.to_string())
+    } else {
+        let trailing = arg.len() - arg.trim_end_matches('\\').len();
+        Ok(format!("\"{}{}\"", arg, "\\".repeat(trailing)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_quote_leaves_plain_words() {
+        for arg in [
+            "ssh",
+            "-p",
+            "2222",
+            "user@host",
+            "/tmp/a.conf",
+            "a=b,c:d%h+",
+        ] {
+            assert_eq!(shell_quote(arg), arg);
+        }
+    }
+
+    #[test]
+    fn shell_quote_quotes_hostile_args() {
+        let cases = [
+            ("", "''"),
+            ("a b", "'a b'"),
+            ("it's", r"'it'\''s'"),
+            ("a;rm -rf ~", "'a;rm -rf ~'"),
+            ("$(id)", "'$(id)'"),
+            ("`id`", "'`id`'"),
+            ("a\"b", "'a\"b'"),
+            ("x\ny", "'x\ny'"),
+            ("~", "'~'"),
+            ("*", "'*'"),
+        ];
+        for (arg, quoted) in cases {
+            assert_eq!(shell_quote(arg), quoted, "{:?}", arg);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_quote_round_trips_through_sh() {
+        let args = ["it's", "a;b", "$(id)", "`id`", "\\", "-n", "a\"b'c", " x "];
+        let line: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf '%s\\0' {}", line.join(" ")))
+            .output()
+            .unwrap();
+        let printed: Vec<&str> = std::str::from_utf8(&output.stdout)
+            .unwrap()
+            .split_terminator('\0')
+            .collect();
+        assert_eq!(printed, args);
+    }
+
+    #[test]
+    fn applescript_escape_escapes_quotes_and_backslashes() {
+        let cases = [
+            ("ssh host", "ssh host"),
+            (r#"say "hi""#, r#"say \"hi\""#),
+            (r"a\b", r"a\\b"),
+            (r#"\""#, r#"\\\""#),
+            ("'$(id)'", "'$(id)'"),
+        ];
+        for (s, escaped) in cases {
+            assert_eq!(applescript_escape(s), escaped, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn cmd_quote_quotes_metacharacters() {
+        let cases = [
+            ("ssh", "ssh"),
+            ("user@host", "user@host"),
+            (r"C:\Users\me\.ssh\config", r"C:\Users\me\.ssh\config"),
+            ("a b", "\"a b\""),
+            ("a&calc", "\"a&calc\""),
+            ("a|b", "\"a|b\""),
+            ("a^b", "\"a^b\""),
+            ("(a)", "\"(a)\""),
+            ("a;b", "\"a;b\""),
+            ("", "\"\""),
+            ("C:\\a b\\", "\"C:\\a b\\\\\""),
+            (r"C:\My Keys\\", r#""C:\My Keys\\\\""#),
+            (r"a b\c", r#""a b\c""#),
+        ];
+        for (arg, quoted) in cases {
+            assert_eq!(cmd_quote(arg).as_deref(), Ok(quoted), "{:?}", arg);
+        }
+    }
+
+    #[test]
+    fn cmd_quote_refuses_what_cmd_would_expand() {
+        for arg in ["%PATH%", "a!b!", "a\"b", "a\r\nb", "\u{1b}"] {
+            assert!(cmd_quote(arg).is_err(), "{:?}", arg);
+        }
+    }
+
+    #[test]
+    fn parse_explicit_target_accepts_valid_targets() {
+        let cases = [
+            ("root@example.com", ("root", "example.com", None)),
+            ("deploy@10.0.0.1:2222", ("deploy", "10.0.0.1", Some(2222))),
+            ("a.b_c-d@host-1", ("a.b_c-d", "host-1", None)),
+            ("me@[fd00::1]", ("me", "fd00::1", None)),
+            ("me@[fd00::1]:22", ("me", "fd00::1", Some(22))),
+            ("me@[::1]:65535", ("me", "::1", Some(65535))),
+        ];
+        for (target, (user, host, port)) in cases {
+            assert_eq!(
+                parse_explicit_target(target),
+                Ok((user.to_string(), host.to_string(), port)),
+                "{}",
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn parse_explicit_target_refuses_hostile_targets() {
+        let targets = [
+            "host",
+            "-oProxyCommand=id@host",
+            "user@-oProxyCommand=id",
+            "user@host;id",
+            "user@$(id)",
+            "user@`id`",
+            "us'er@host",
+            "us\"er@host",
+            "user@host name",
+            "user@host\nid",
+            "user@",
+            "@host",
+            "user@host:0",
+            "user@host:99999",
+            "user@host:22;id",
+            "user@fd00::1",
+            "user@[fd00::1",
+            "user@[fd00::1]x",
+            "user@[not-v6]",
+            "user@[fd00::1]:",
+            "user@-host",
+            "user@host-",
+        ];
+        for target in targets {
+            assert!(parse_explicit_target(target).is_err(), "{:?}", target);
+        }
+    }
+}
@@ -0,0 +1,647 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::include::{expand_path_tokens, ConfigFile, ConfigItem};
+use super::parser::Directive;
+use super::pattern::{is_wildcard, match_host_patterns};
+use super::resolve::{resolve_in, MULTI_VALUE_KEYWORDS};
+use super::types::{ColumnRange, Diagnostic, LintRuleInfo, Severity};
+use super::validate::format_message;
+
+/// A check over a whole config tree. Each rule can be switched off in the
+/// settings; all are on by default.
+struct LintRule {
+    id: &'static str,
+    check: fn(&LintContext) -> Vec<Diagnostic>,
+}
+
+const RULES: &[LintRule] = &[
+    LintRule {
+        id: "global-option-shadows",
+        check: global_option_shadows,
+    },
+    LintRule {
+        id: "duplicate-host-alias",
+        check: duplicate_host_alias,
+    },
+    LintRule {
+        id: "wildcard-host-first",
+        check: wildcard_host_first,
+    },
+    LintRule {
+        id: "identity-file-missing",
+        check: identity_file_missing,
+    },
+    LintRule {
+        id: "identity-file-permissions",
+        check: identity_file_permissions,
+    },
+    LintRule {
+        id: "proxyjump-undefined",
+        check: proxyjump_undefined,
+    },
+    LintRule {
+        id: "proxyjump-cycle",
+        check: proxyjump_cycle,
+    },
+    LintRule {
+        id: "local-forward-collision",
+        check: local_forward_collision,
+    },
+];
+
+/// Block a directive belongs to
+#[derive(Debug, Clone, PartialEq)]
+enum Section {
+    /// Before the first `Host` or `Match` line
+    Global,
+    Host {
+        patterns: Vec<String>,
+        file: PathBuf,
+        line: usize,
+    },
+    Match,
+}
+
+impl Section {
+    /// Name of the block for messages
+    fn label(&self) -> String {
+        match self {
+            Section::Global => "Global".to_string(),
+            Section::Host { patterns, .. } => patterns.join(" "),
+            Section::Match => "Match".to_string(),
+        }
+    }
+}
+
+/// A directive of the linted tree, in the order ssh reads it
+struct Entry<'a> {
+    file: &'a Path,
+    directive: &'a Directive,
+    section: Section,
+}
+
+impl Entry<'_> {
+    fn report(
+        &self,
+        severity: Severity,
+        code: &str,
+        message_key: &str,
+        args: &[(&str, &str)],
+    ) -> Diagnostic {
+        let args = args
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Diagnostic {
+            file: Some(self.file.to_string_lossy().to_string()),
+            line: self.directive.line,
+            column_range: self.directive.column_range,
+            severity,
+            code: code.to_string(),
+            message_key: message_key.to_string(),
+            message: format_message(message_key, &args),
+            args,
+            suggested_fix: None,
+        }
+    }
+
+    fn same_line(&self, other: &Entry) -> bool {
+        self.file == other.file && self.directive.line == other.directive.line
+    }
+}
+
+struct LintContext<'a> {
+    /// The linted root followed by the configs ssh reads after it
+    configs: &'a [ConfigFile],
+    /// Every directive of the root and the files it includes
+    entries: Vec<Entry<'a>>,
+}
+
+impl LintContext<'_> {
+    /// `Host` lines, in order
+    fn host_lines(&self) -> impl Iterator<Item = &Entry<'_>> {
+        self.entries.iter().filter(|e| e.directive.key() == "host")
+    }
+
+    /// Aliases that can be connected to directly
+    fn aliases(&self) -> Vec<&str> {
+        let mut aliases = Vec::new();
+        for entry in self.host_lines() {
+            for pattern in &entry.directive.args {
+                if is_alias(pattern) && !aliases.contains(&pattern.as_str()) {
+                    aliases.push(pattern.as_str());
+                }
+            }
+        }
+        aliases
+    }
+}
+
+/// Run the enabled rules over `configs`; only the first config and the
+/// files it includes are reported on. `overrides` maps rule ids to
+/// whether they are enabled.
+pub fn lint_configs(configs: &[ConfigFile], overrides: &BTreeMap<String, bool>) -> Vec<Diagnostic> {
+    let Some(root) = configs.first() else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    collect_entries(root, &mut Section::Global, &mut entries);
+    let ctx = LintContext { configs, entries };
+
+    RULES
+        .iter()
+        .filter(|rule| overrides.get(rule.id).copied().unwrap_or(true))
+        .flat_map(|rule| (rule.check)(&ctx))
+        .collect()
+}
+
+/// Every rule with whether it is enabled under `overrides`
+pub fn lint_rules(overrides: &BTreeMap<String, bool>) -> Vec<LintRuleInfo> {
+    RULES
+        .iter()
+        .map(|rule| LintRuleInfo {
+            id: rule.id.to_string(),
+            enabled: overrides.get(rule.id).copied().unwrap_or(true),
+        })
+        .collect()
+}
+
+/// Warning on `file` that the config root at `root` couldn't be loaded,
+/// so the rules didn't run over it
+pub fn root_not_linted(file: &str, root: &Path, error: &str) -> Diagnostic {
+    let args: BTreeMap<String, String> = [
+        ("root".to_string(), root.to_string_lossy().to_string()),
+        ("message".to_string(), error.to_string()),
+    ]
+    .into();
+    Diagnostic {
+        file: Some(file.to_string()),
+        line: 0,
+        column_range: ColumnRange { start: 0, end: 0 },
+        severity: Severity::Warning,
+        code: "lint-skipped".to_string(),
+        message_key: "lint_root_skipped".to_string(),
+        message: format_message("lint_root_skipped", &args),
+        args,
+        suggested_fix: None,
+    }
+}
+
+/// Flatten `file` into entries. An included file starts in the block its
+/// Include line is in, and that block continues after it, as in ssh.
+fn collect_entries<'a>(file: &'a ConfigFile, section: &mut Section, entries: &mut Vec<Entry<'a>>) {
+    for item in &file.items {
+        match item {
+            ConfigItem::Directive(d) => {
+                match d.key().as_str() {
+                    "host" => {
+                        *section = Section::Host {
+                            patterns: d.args.clone(),
+                            file: file.path.clone(),
+                            line: d.line,
+                        }
+                    }
+                    "match" => *section = Section::Match,
+                    _ => {}
+                }
+                entries.push(Entry {
+                    file: &file.path,
+                    directive: d,
+                    section: section.clone(),
+                });
+            }
+            ConfigItem::Include { files } => {
+                for inc in files {
+                    collect_entries(inc, &mut section.clone(), entries);
+                }
+            }
+        }
+    }
+}
+
+fn is_alias(pattern: &str) -> bool {
+    !pattern.starts_with('!') && !is_wildcard(pattern)
+}
+
+/// Options where only the first value counts, so a later one is ignored
+fn is_single_value_option(key: &str) -> bool {
+    !["host", "match", "include"].contains(&key) && !MULTI_VALUE_KEYWORDS.contains(&key)
+}
+
+/// Host-specific options that an option before the first `Host` line
+/// already set, so they never take effect
+fn global_option_shadows(ctx: &LintContext) -> Vec<Diagnostic> {
+    let mut globals: HashMap<String, &Entry> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for entry in &ctx.entries {
+        let key = entry.directive.key();
+        if !is_single_value_option(&key) {
+            continue;
+        }
+        if entry.section == Section::Global {
+            globals.entry(key).or_insert(entry);
+        } else if let Some(global) = globals.get(&key) {
+            diagnostics.push(entry.report(
+                Severity::Warning,
+                "global-option-shadows",
+                "shadowed_by_global",
+                &[
+                    ("keyword", &entry.directive.keyword),
+                    ("file", &global.file.to_string_lossy()),
+                    ("line", &global.directive.line.to_string()),
+                ],
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// Aliases declared on more than one `Host` line
+fn duplicate_host_alias(ctx: &LintContext) -> Vec<Diagnostic> {
+    let mut first: HashMap<&str, &Entry> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for entry in ctx.host_lines() {
+        for alias in entry.directive.args.iter().filter(|p| is_alias(p)) {
+            let Some(prev) = first.get(alias.as_str()) else {
+                first.insert(alias, entry);
+                continue;
+            };
+            if prev.same_line(entry) {
+                continue;
+            }
+            // Repeating a host within one file is a common way to split
+            // its options; across files it is more likely a mistake
+            let severity = if prev.file == entry.file {
+                Severity::Info
+            } else {
+                Severity::Warning
+            };
+            diagnostics.push(entry.report(
+                severity,
+                "duplicate-host-alias",
+                "duplicate_host_alias",
+                &[
+                    ("alias", alias),
+                    ("file", &prev.file.to_string_lossy()),
+                    ("line", &prev.directive.line.to_string()),
+                ],
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// `Host *` blocks that come before specific hosts setting the same
+/// options, so the wildcard values win
+fn wildcard_host_first(ctx: &LintContext) -> Vec<Diagnostic> {
+    // Host blocks in order, with the single-value options each one sets
+    // (as written)
+    let mut blocks: Vec<(&Entry, Vec<&str>)> = Vec::new();
+    for entry in &ctx.entries {
+        if entry.directive.key() == "host" {
+            blocks.push((entry, Vec::new()));
+            continue;
+        }
+        let Some((host, keywords)) = blocks.last_mut() else {
+            continue;
+        };
+        let keyword = entry.directive.keyword.as_str();
+        if entry.section == host.section
+            && is_single_value_option(&entry.directive.key())
+            && !keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword))
+        {
+            keywords.push(keyword);
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for (i, (star, star_keywords)) in blocks.iter().enumerate() {
+        if star.directive.args != ["*"] {
+            continue;
+        }
+        let mut keywords: Vec<&str> = Vec::new();
+        let mut hosts: Vec<String> = Vec::new();
+        for (host, host_keywords) in &blocks[i + 1..] {
+            if !host.directive.args.iter().any(|p| is_alias(p)) {
+                continue;
+            }
+            let shadowed: Vec<&str> = star_keywords
+                .iter()
+                .copied()
+                .filter(|k| host_keywords.iter().any(|h| h.eq_ignore_ascii_case(k)))
+                .collect();
+            if shadowed.is_empty() {
+                continue;
+            }
+            for keyword in shadowed {
+                if !keywords.contains(&keyword) {
+                    keywords.push(keyword);
+                }
+            }
+            hosts.push(host.directive.args.join(" "));
+        }
+        if !hosts.is_empty() {
+            diagnostics.push(star.report(
+                Severity::Warning,
+                "wildcard-host-first",
+                "wildcard_host_first",
+                &[
+                    ("keywords", &keywords.join(", ")),
+                    ("hosts", &hosts.join(", ")),
+                ],
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// IdentityFile entries that can be checked on disk, with their path.
+/// Paths with ssh tokens or relative paths depend on the connection and
+/// are skipped.
+fn identity_files<'a>(ctx: &'a LintContext) -> impl Iterator<Item = (&'a Entry<'a>, PathBuf)> {
+    ctx.entries.iter().filter_map(|entry| {
+        if entry.directive.key() != "identityfile" {
+            return None;
+        }
+        let value = entry.directive.args.first()?;
+        if value.eq_ignore_ascii_case("none") || value.contains('%') || value.contains("${") {
+            return None;
+        }
+        let path = PathBuf::from(expand_path_tokens(value));
+        path.is_absolute().then_some((entry, path))
+    })
+}
+
+fn identity_file_missing(ctx: &LintContext) -> Vec<Diagnostic> {
+    identity_files(ctx)
+        .filter(|(_, path)| !path.exists())
+        .map(|(entry, path)| {
+            entry.report(
+                Severity::Warning,
+                "identity-file-missing",
+                "identity_file_missing",
+                &[("path", &path.to_string_lossy())],
+            )
+        })
+        .collect()
+}
+
+/// Private keys readable by other users, which ssh refuses to use. Only
+/// regular files are reported: the fix must not follow a symlink.
+fn identity_file_permissions(ctx: &LintContext) -> Vec<Diagnostic> {
+    #[cfg(unix)]
+    {
+        use super::types::{ChmodFix, SuggestedFix};
+        use std::os::unix::fs::PermissionsExt;
+        identity_files(ctx)
+            .filter_map(|(entry, path)| {
+                let meta = std::fs::symlink_metadata(&path).ok()?;
+                if !meta.is_file() {
+                    return None;
+                }
+                let mode = meta.permissions().mode() & 0o777;
+                (mode & 0o077 != 0).then(|| {
+                    let path = path.to_string_lossy().to_string();
+                    let mut diagnostic = entry.report(
+                        Severity::Warning,
+                        "identity-file-permissions",
+                        "identity_file_permissions",
+                        &[("path", &path), ("mode", &format!("{:o}", mode))],
+                    );
+                    let args = BTreeMap::from([
+                        ("path".to_string(), path.clone()),
+                        ("mode".to_string(), "600".to_string()),
+                    ]);
+                    diagnostic.suggested_fix = Some(SuggestedFix {
+                        message_key: "fix_chmod".to_string(),
+                        message: format_message("fix_chmod", &args),
+                        args,
+                        edits: Vec::new(),
+                        chmod: Some(ChmodFix { path, mode: 0o600 }),
+                    });
+                    diagnostic
+                })
+            })
+            .collect()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = ctx;
+        Vec::new()
+    }
+}
+
+/// Host part of one ProxyJump hop, `[user@]host[:port]` or an ssh:// URI
+fn jump_host(hop: &str) -> &str {
+    let hop = hop.trim();
+    let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+    let host = hop.rsplit_once('@').map_or(hop, |(_, h)| h);
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    host.split(':').next().unwrap_or(host)
+}
+
+/// Hops of a ProxyJump value, or none for `ProxyJump none`
+fn jump_hosts(value: &str) -> Vec<&str> {
+    if value.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    value
+        .split(',')
+        .map(jump_host)
+        .filter(|h| !h.is_empty())
+        .collect()
+}
+
+/// ProxyJump hops that look like an alias (no dots, not an address) but
+/// that no `Host` line declares or matches
+fn proxyjump_undefined(ctx: &LintContext) -> Vec<Diagnostic> {
+    let aliases = ctx.aliases();
+    let patterns: Vec<&Vec<String>> = ctx
+        .host_lines()
+        .map(|e| &e.directive.args)
+        .filter(|args| args.iter().any(|p| is_wildcard(p)) && args.as_slice() != ["*"])
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for entry in &ctx.entries {
+        if entry.directive.key() != "proxyjump" {
+            continue;
+        }
+        let value = entry.directive.value();
+        for host in jump_hosts(&value) {
+            let looks_like_alias = !host.contains(['.', ':', '%']) && host != "localhost";
+            let known =
+                aliases.contains(&host) || patterns.iter().any(|p| match_host_patterns(host, p));
+            if looks_like_alias && !known {
+                diagnostics.push(entry.report(
+                    Severity::Warning,
+                    "proxyjump-undefined",
+                    "proxyjump_undefined",
+                    &[("alias", host)],
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Hosts that end up jumping through themselves
+fn proxyjump_cycle(ctx: &LintContext) -> Vec<Diagnostic> {
+    // Effective ProxyJump of each host
+    let mut jumps: HashMap<String, Jump> = HashMap::new();
+    let mut jumps_of = |host: &str| -> Vec<String> {
+        jumps
+            .entry(host.to_string())
+            .or_insert_with(|| effective_jump(ctx, host))
+            .hosts
+            .clone()
+    };
+
+    let mut seen_cycles: HashSet<Vec<String>> = HashSet::new();
+    let mut cycles: Vec<Vec<String>> = Vec::new();
+    for alias in ctx.aliases() {
+        // Depth-first search from each alias, tracking the current path
+        let mut path: Vec<String> = vec![alias.to_string()];
+        let mut stack: Vec<Vec<String>> = vec![jumps_of(alias)];
+        let mut visited: HashSet<String> = HashSet::from([alias.to_string()]);
+        while let Some(next) = stack.last_mut() {
+            let Some(host) = next.pop() else {
+                stack.pop();
+                path.pop();
+                continue;
+            };
+            if let Some(pos) = path.iter().position(|h| *h == host) {
+                let cycle = path[pos..].to_vec();
+                let mut key = cycle.clone();
+                key.sort();
+                if seen_cycles.insert(key) {
+                    cycles.push(cycle);
+                }
+                continue;
+            }
+            if visited.insert(host.clone()) {
+                stack.push(jumps_of(&host));
+                path.push(host);
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for cycle in cycles {
+        let mut shown = cycle.clone();
+        shown.push(cycle[0].clone());
+        let shown = shown.join(" → ");
+        // Report on the ProxyJump lines of the cycle that are in this tree
+        for host in &cycle {
+            let Some((file, line)) = jumps.get(host).and_then(|j| j.at.clone()) else {
+                continue;
+            };
+            let entry = ctx
+                .entries
+                .iter()
+                .find(|e| e.directive.line == line && e.file.to_string_lossy() == file);
+            let column_range = entry
+                .map(|e| e.directive.column_range)
+                .unwrap_or(ColumnRange { start: 0, end: 0 });
+            let args = BTreeMap::from([("cycle".to_string(), shown.clone())]);
+            diagnostics.push(Diagnostic {
+                file: Some(file),
+                line,
+                column_range,
+                severity: Severity::Error,
+                code: "proxyjump-cycle".to_string(),
+                message_key: "proxyjump_cycle".to_string(),
+                message: format_message("proxyjump_cycle", &args),
+                args,
+                suggested_fix: None,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Jump hosts ssh would use for a host
+struct Jump {
+    hosts: Vec<String>,
+    /// File and line of the ProxyJump that set them
+    at: Option<(String, usize)>,
+}
+
+fn effective_jump(ctx: &LintContext, host: &str) -> Jump {
+    let resolved = resolve_in(ctx.configs, host);
+    let Some(option) = resolved
+        .options
+        .iter()
+        .find(|o| o.keyword == "proxyjump" && o.conditional.is_none())
+    else {
+        return Jump {
+            hosts: Vec::new(),
+            at: None,
+        };
+    };
+    Jump {
+        hosts: jump_hosts(&option.value)
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        at: option.source_file.clone().zip(option.source_line),
+    }
+}
+
+/// Normalized listen address of a LocalForward, e.g. `localhost:8080`
+fn listen_key(spec: &str) -> String {
+    if spec.contains('/') {
+        return spec.to_string();
+    }
+    let (bind, port) = if let Some(rest) = spec.strip_prefix('[') {
+        match rest.split_once("]:") {
+            Some((addr, port)) => (addr, port),
+            None => ("", rest),
+        }
+    } else {
+        spec.rsplit_once(':').unwrap_or(("", spec))
+    };
+    let bind = match bind {
+        "" | "localhost" | "127.0.0.1" | "::1" => "localhost",
+        other => other,
+    };
+    format!("{}:{}", bind, port)
+}
+
+/// LocalForwards of different blocks listening on the same port, which
+/// can't be open at the same time
+fn local_forward_collision(ctx: &LintContext) -> Vec<Diagnostic> {
+    let mut first: HashMap<String, &Entry> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for entry in &ctx.entries {
+        if entry.directive.key() != "localforward" {
+            continue;
+        }
+        let Some(spec) = entry.directive.args.first() else {
+            continue;
+        };
+        let key = listen_key(spec);
+        let Some(prev) = first.get(&key) else {
+            first.insert(key, entry);
+            continue;
+        };
+        if prev.same_line(entry) {
+            continue;
+        }
+        diagnostics.push(entry.report(
+            Severity::Warning,
+            "local-forward-collision",
+            "local_forward_collision",
+            &[
+                ("listen", spec),
+                ("host", &prev.section.label()),
+                ("file", &prev.file.to_string_lossy()),
+                ("line", &prev.directive.line.to_string()),
+            ],
+        ));
+    }
+    diagnostics
+}
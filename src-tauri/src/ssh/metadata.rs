@@ -0,0 +1,96 @@
+use super::cst::quote_value;
+use super::group::annotation_fields;
+use super::types::HostMetadata;
+
+/// Prefix of the comments holding a host's metadata, e.g.
+/// `#@ssh-m description="Primary DB" env=prod`
+pub const METADATA_PREFIX: &str = "@ssh-m";
+
+/// Metadata from the `#@ssh-m` comments among a block's comments (the
+/// text after each `#`). A key given twice keeps its last value.
+pub fn parse_metadata(comments: &[&str]) -> HostMetadata {
+    let comments: Vec<&str> = comments
+        .iter()
+        .copied()
+        .filter(|c| is_metadata_comment(c))
+        .collect();
+    let mut metadata = HostMetadata::default();
+    for (key, value) in annotation_fields(&comments, METADATA_PREFIX) {
+        match key.as_str() {
+            "description" => metadata.description = Some(value),
+            "env" => metadata.env = Some(value),
+            "color" => metadata.color = Some(value),
+            "owner" => metadata.owner = Some(value),
+            "favorite" => {
+                metadata.favorite = matches!(value.to_lowercase().as_str(), "true" | "yes" | "1")
+            }
+            _ => {
+                metadata.extra.insert(key, value);
+            }
+        }
+    }
+    metadata
+}
+
+/// Whether a comment's text (after the `#`) is a metadata comment
+pub fn is_metadata_comment(comment: &str) -> bool {
+    comment
+        .trim_start()
+        .strip_prefix(METADATA_PREFIX)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Comment text (without the `#`) storing `metadata`, or `None` if there
+/// is nothing to store
+pub fn format_metadata(metadata: &HostMetadata) -> Option<String> {
+    let mut fields: Vec<(&str, &str)> = Vec::new();
+    let known = [
+        ("description", &metadata.description),
+        ("env", &metadata.env),
+        ("color", &metadata.color),
+        ("owner", &metadata.owner),
+    ];
+    for (key, value) in known {
+        if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+            fields.push((key, value));
+        }
+    }
+    if metadata.favorite {
+        fields.push(("favorite", "true"));
+    }
+    fields.extend(metadata.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+    if fields.is_empty() {
+        return None;
+    }
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, quote_value(value)))
+        .collect();
+    Some(format!("{} {}", METADATA_PREFIX, fields.join(" ")))
+}
+
+/// Check metadata before it is written into a comment
+pub fn validate_metadata(metadata: &HostMetadata) -> Result<(), String> {
+    let values = [
+        &metadata.description,
+        &metadata.env,
+        &metadata.color,
+        &metadata.owner,
+    ];
+    for value in values.into_iter().flatten().chain(metadata.extra.values()) {
+        if value.contains(['\n', '\r']) {
+            return Err("Host metadata cannot contain line breaks".to_string());
+        }
+    }
+    for key in metadata.extra.keys() {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        {
+            return Err(format!("Invalid host metadata key: \"{}\"", key));
+        }
+    }
+    Ok(())
+}
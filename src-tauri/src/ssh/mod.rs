@@ -1,2 +1,23 @@
+pub mod backup;
+pub mod client;
 pub mod config;
+pub mod cst;
+pub mod diff;
+pub mod edit;
+pub mod fix;
+pub mod group;
+pub mod include;
+pub mod keywords;
+pub mod launch;
+pub mod lint;
+pub mod metadata;
+pub mod parser;
+pub mod pattern;
+pub mod profile;
+pub mod resolve;
+pub mod search;
+pub mod terminal;
 pub mod types;
+pub mod validate;
+pub mod watch;
+pub mod workspace;
@@ -0,0 +1,128 @@
+use super::types::ColumnRange;
+
+/// A single `Keyword arguments...` line from an SSH config file
+#[derive(Debug, Clone)]
+pub struct Directive {
+    /// Keyword as written in the file (case preserved)
+    pub keyword: String,
+    /// Arguments after the keyword, with quotes removed
+    pub args: Vec<String>,
+    /// 1-based line number within its file
+    pub line: usize,
+    /// Columns from the start of the keyword to the end of the value
+    pub column_range: ColumnRange,
+}
+
+impl Directive {
+    /// Lowercased keyword, for case-insensitive comparisons
+    pub fn key(&self) -> String {
+        self.keyword.to_lowercase()
+    }
+
+    /// All arguments joined by a single space
+    pub fn value(&self) -> String {
+        self.args.join(" ")
+    }
+}
+
+/// Parse config content into directives, skipping blank lines and comments
+pub fn parse_directives(content: &str) -> Vec<Directive> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| parse_line(line, i + 1))
+        .collect()
+}
+
+/// Parse a single config line. Returns `None` for blank lines and comments.
+pub fn parse_line(line: &str, line_no: usize) -> Option<Directive> {
+    let (keyword, rest) = split_keyword(line)?;
+    let start = column_range(line, keyword).start;
+    let end = if rest.is_empty() {
+        column_range(line, keyword).end
+    } else {
+        column_range(line, rest).end
+    };
+    Some(Directive {
+        keyword: keyword.to_string(),
+        args: split_args(rest),
+        line: line_no,
+        column_range: ColumnRange { start, end },
+    })
+}
+
+/// Character columns covered by `sub`, which must be a slice of `line`
+pub fn column_range(line: &str, sub: &str) -> ColumnRange {
+    let offset = (sub.as_ptr() as usize).saturating_sub(line.as_ptr() as usize);
+    let start = line[..offset.min(line.len())].chars().count();
+    ColumnRange {
+        start,
+        end: start + sub.chars().count(),
+    }
+}
+
+/// Split a line into its keyword and the raw argument text.
+///
+/// Like OpenSSH, the keyword ends at whitespace or `=`, and a single `=`
+/// (optionally surrounded by whitespace) may separate it from the value.
+pub fn split_keyword(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+
+    let end = trimmed
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(trimmed.len());
+    let keyword = &trimmed[..end];
+    if keyword.is_empty() {
+        return None;
+    }
+
+    let mut rest = trimmed[end..].trim_start();
+    if let Some(stripped) = rest.strip_prefix('=') {
+        rest = stripped.trim_start();
+    }
+    Some((keyword, rest.trim_end()))
+}
+
+/// Split argument text into words, honouring single/double quotes and
+/// backslash escapes. An unquoted `#` at the start of a word ends the line.
+pub fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        match chars.peek() {
+            None | Some('#') => break,
+            _ => {}
+        }
+
+        let mut word = String::new();
+        let mut quote: Option<char> = None;
+        while let Some(&c) = chars.peek() {
+            if quote.is_none() && c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            match c {
+                '\\' => match chars.peek() {
+                    Some(&next) if next == '\\' || next == '"' || next == '\'' => {
+                        word.push(next);
+                        chars.next();
+                    }
+                    _ => word.push(c),
+                },
+                '"' | '\'' if quote.is_none() => quote = Some(c),
+                _ if quote == Some(c) => quote = None,
+                _ => word.push(c),
+            }
+        }
+        args.push(word);
+    }
+
+    args
+}
@@ -0,0 +1,79 @@
+/// Glob match supporting `*` and `?`, like OpenSSH's `match_pattern()`.
+/// Matching is case-sensitive.
+pub fn match_pattern(s: &str, pattern: &str) -> bool {
+    let s: Vec<char> = s.chars().collect();
+    let p: Vec<char> = pattern.chars().collect();
+
+    let (mut si, mut pi) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while si < s.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == s[si]) {
+            si += 1;
+            pi += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, si));
+            pi += 1;
+        } else if let Some((star_pi, star_si)) = star {
+            pi = star_pi + 1;
+            si = star_si + 1;
+            star = Some((star_pi, star_si + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Whether a pattern contains glob metacharacters
+pub fn is_wildcard(pattern: &str) -> bool {
+    pattern.contains('*') || pattern.contains('?')
+}
+
+/// Match against a comma-separated pattern list where entries may be
+/// negated with `!`, like OpenSSH's `match_pattern_list()`. A negated
+/// match always wins; otherwise at least one positive entry must match.
+pub fn match_pattern_list(s: &str, list: &str, dolower: bool) -> bool {
+    let s = if dolower {
+        s.to_lowercase()
+    } else {
+        s.to_string()
+    };
+    let mut matched = false;
+    for entry in list.split(',') {
+        let entry = if dolower {
+            entry.to_lowercase()
+        } else {
+            entry.to_string()
+        };
+        let (negated, pattern) = match entry.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, entry.as_str()),
+        };
+        if match_pattern(&s, pattern) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Match a host against the arguments of a `Host` line. Each argument is
+/// a single pattern, optionally negated; one negated hit rejects the line.
+pub fn match_host_patterns(host: &str, patterns: &[String]) -> bool {
+    let mut matched = false;
+    for arg in patterns {
+        let (negated, pattern) = match arg.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, arg.as_str()),
+        };
+        if match_pattern(host, pattern) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
@@ -0,0 +1,295 @@
+use super::config::parse_ssh_config;
+use super::launch::SshLaunch;
+use super::pattern::match_pattern_list;
+use super::types::LaunchProfile;
+use crate::settings;
+
+/// ssh's own option string: letters followed by `:` take an argument
+const SSH_OPTIONS: &str = "1246ab:c:e:fgi:kl:m:no:p:qstvxAB:CD:E:F:GI:J:KL:MNO:P:Q:R:S:TVw:W:XYy";
+
+/// Flags a launch profile can use. The others can load or run local code
+/// (`-F`, `-I`), write local files (`-E`) or change what is launched (`-W`,
+/// `-N`).
+const PROFILE_FLAGS: &str = "46ACDJLRTXYaiklpqtvx";
+
+/// Keywords a launch profile can set with `-o`, lowercase. Keywords that
+/// run commands, load libraries or name local files to write are left out.
+const PROFILE_KEYWORDS: &[&str] = &[
+    "addkeystoagent",
+    "addressfamily",
+    "batchmode",
+    "certificatefile",
+    "ciphers",
+    "clearallforwardings",
+    "compression",
+    "connectionattempts",
+    "connecttimeout",
+    "dynamicforward",
+    "escapechar",
+    "exitonforwardfailure",
+    "forwardagent",
+    "forwardx11",
+    "forwardx11timeout",
+    "forwardx11trusted",
+    "gatewayports",
+    "hostkeyalgorithms",
+    "identitiesonly",
+    "identityfile",
+    "ipqos",
+    "kbdinteractiveauthentication",
+    "kexalgorithms",
+    "localforward",
+    "loglevel",
+    "macs",
+    "numberofpasswordprompts",
+    "passwordauthentication",
+    "port",
+    "preferredauthentications",
+    "proxyjump",
+    "pubkeyacceptedalgorithms",
+    "pubkeyauthentication",
+    "rekeylimit",
+    "remoteforward",
+    "requesttty",
+    "sendenv",
+    "serveralivecountmax",
+    "serveraliveinterval",
+    "setenv",
+    "stricthostkeychecking",
+    "tcpkeepalive",
+    "user",
+    "visualhostkey",
+];
+
+/// Profiles offered for the host `alias` in `group` (`None` for a target
+/// that isn't in the config), in settings order
+pub fn profiles_for<'a>(
+    profiles: &'a [LaunchProfile],
+    alias: &str,
+    group: Option<&str>,
+) -> Vec<&'a LaunchProfile> {
+    profiles
+        .iter()
+        .filter(|p| profile_applies(p, alias, group))
+        .collect()
+}
+
+/// Whether `profile` is offered for the host `alias` in `group`
+pub fn profile_applies(profile: &LaunchProfile, alias: &str, group: Option<&str>) -> bool {
+    let alias_matches = profile
+        .alias
+        .as_deref()
+        .is_none_or(|list| match_pattern_list(alias, list, true));
+    let group_matches = profile.group.as_deref().is_none_or(|g| Some(g) == group);
+    alias_matches && group_matches
+}
+
+/// The profile called `name` among those offered for `alias`
+pub fn find_profile<'a>(
+    profiles: &'a [LaunchProfile],
+    alias: &str,
+    group: Option<&str>,
+    name: &str,
+) -> Result<&'a LaunchProfile, String> {
+    profiles_for(profiles, alias, group)
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("No launch profile \"{}\" for {}", name, alias))
+}
+
+/// The launch for `target`, with the launch profile called `profile` from
+/// the settings applied if one is given
+pub fn launch_with_profile(target: &str, profile: Option<&str>) -> Result<SshLaunch, String> {
+    let launch = SshLaunch::for_target(target)?;
+    let Some(name) = profile else {
+        return Ok(launch);
+    };
+    let profiles = settings::load_settings().launch_profiles;
+    let hosts = parse_ssh_config()?;
+    let group = hosts
+        .iter()
+        .find(|h| h.name == target)
+        .map(|h| h.group.as_str());
+    let profile = find_profile(&profiles, target, group, name)?;
+    // The settings file may have been edited by hand since it was checked
+    validate_ssh_options(&profile.ssh_options)
+        .map_err(|e| format!("Launch profile \"{}\": {}", profile.name, e))?;
+    Ok(launch.with_profile(profile))
+}
+
+/// Check launch profiles before they are saved: unique names, options ssh
+/// accepts, and no line breaks in the remote command
+pub fn validate_launch_profiles(profiles: &[LaunchProfile]) -> Result<(), String> {
+    for (i, profile) in profiles.iter().enumerate() {
+        let name = profile.name.trim();
+        if name.is_empty() {
+            return Err(format!("Launch profile {}: name is empty", i + 1));
+        }
+        if profiles[..i].iter().any(|p| p.name.trim() == name) {
+            return Err(format!("Launch profile \"{}\" is defined twice", name));
+        }
+        validate_ssh_options(&profile.ssh_options)
+            .map_err(|e| format!("Launch profile \"{}\": {}", name, e))?;
+        if profile
+            .remote_command
+            .as_deref()
+            .is_some_and(|c| c.contains(['\n', '\r']))
+        {
+            return Err(format!(
+                "Launch profile \"{}\": the remote command cannot contain line breaks",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that `options` are only ssh options a launch profile may use,
+/// with their arguments, so a profile can't add a destination or a
+/// command, load local code or write local files. Config files (`-F`)
+/// come from the settings.
+pub fn validate_ssh_options(options: &[String]) -> Result<(), String> {
+    for (flag, arg) in parse_ssh_options(options)? {
+        match (flag, arg) {
+            ('F', _) => {
+                return Err("-F can't be used; config files are set in the settings".to_string())
+            }
+            ('o', Some(option)) => {
+                let keyword = option
+                    .trim_start()
+                    .split(|c: char| c.is_whitespace() || c == '=')
+                    .next()
+                    .unwrap_or_default();
+                if !PROFILE_KEYWORDS.contains(&keyword.to_lowercase().as_str()) {
+                    return Err(format!("-o {} can't be used in a launch profile", keyword));
+                }
+            }
+            // ssh would read a jump host starting with `-` as an option
+            ('J', Some(jump)) if jump.starts_with('-') => {
+                return Err(format!("Invalid jump host \"{}\"", jump));
+            }
+            (flag, _) if !PROFILE_FLAGS.contains(flag) => {
+                return Err(format!("-{} can't be used in a launch profile", flag));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Read `options` the way ssh's getopt does: each flag letter with its
+/// argument, if it takes one. Flags can be grouped (`-At`) and an
+/// argument can follow its flag in the same word (`-p2222`) or the next.
+pub fn parse_ssh_options(options: &[String]) -> Result<Vec<(char, Option<String>)>, String> {
+    let mut parsed = Vec::new();
+    let mut args = options.iter();
+    while let Some(arg) = args.next() {
+        let flags = arg
+            .strip_prefix('-')
+            .filter(|f| !f.is_empty() && !f.starts_with('-'))
+            .ok_or_else(|| format!("\"{}\" is not an ssh option", arg))?;
+        for (i, flag) in flags.char_indices() {
+            let spec = SSH_OPTIONS
+                .find(flag)
+                .filter(|_| flag != ':')
+                .ok_or_else(|| format!("Unknown ssh option -{}", flag))?;
+            if !SSH_OPTIONS[spec + 1..].starts_with(':') {
+                parsed.push((flag, None));
+                continue;
+            }
+            // The argument is the rest of this word or the next one
+            let rest = &flags[i + flag.len_utf8()..];
+            let value = if rest.is_empty() {
+                args.next()
+                    .ok_or_else(|| format!("ssh option -{} needs an argument", flag))?
+                    .clone()
+            } else {
+                rest.to_string()
+            };
+            parsed.push((flag, Some(value)));
+            break;
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn validate_ssh_options_accepts_profile_options() {
+        let cases: &[&[&str]] = &[
+            &[],
+            &["-A"],
+            &["-At"],
+            &["-T"],
+            &["-L", "5432:localhost:5432"],
+            &["-R8080:localhost:80", "-D", "1080"],
+            &["-J", "bastion"],
+            &["-p2222", "-l", "deploy"],
+            &["-i", "~/.ssh/id_work", "-v"],
+            &["-o", "ServerAliveInterval=30"],
+            &["-o", "ForwardAgent yes"],
+            &["-oRequestTTY=force"],
+        ];
+        for args in cases {
+            assert!(validate_ssh_options(&options(args)).is_ok(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn validate_ssh_options_refuses_unsafe_options() {
+        let cases: &[&[&str]] = &[
+            // Local code
+            &["-o", "ProxyCommand=sh -c id"],
+            &["-o", "LocalCommand id"],
+            &["-o", "PermitLocalCommand=yes"],
+            &["-o", "KnownHostsCommand=/tmp/evil"],
+            &["-o", "PKCS11Provider=/tmp/evil.so"],
+            &["-o", "pkcs11provider /tmp/evil.so"],
+            &["-o", "SecurityKeyProvider=/tmp/evil.so"],
+            &["-o", "Include=/tmp/evil.conf"],
+            &["-I", "/tmp/evil.so"],
+            &["-I/tmp/evil.so"],
+            &["-F", "/tmp/evil.conf"],
+            &["-AF/tmp/evil.conf"],
+            // Local files written
+            &["-E", "/tmp/out.log"],
+            &["-tE/tmp/out.log"],
+            &["-o", "ControlPath=/tmp/sock"],
+            &["-o", "UserKnownHostsFile=/tmp/hosts"],
+            // Something other than the destination launched
+            &["-W", "host:22"],
+            &["-J", "-oProxyCommand=id"],
+            &["-N"],
+            &["-f"],
+            // Not options
+            &["host"],
+            &["--", "host"],
+            &["-p"],
+            &["-Z"],
+        ];
+        for args in cases {
+            assert!(validate_ssh_options(&options(args)).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn parse_ssh_options_reads_grouped_flags_and_arguments() {
+        let parsed = parse_ssh_options(&options(&["-At", "-p2222", "-L", "80:h:80"])).unwrap();
+        assert_eq!(
+            parsed,
+            [
+                ('A', None),
+                ('t', None),
+                ('p', Some("2222".to_string())),
+                ('L', Some("80:h:80".to_string())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::config::{
+    default_config_path, find_host_root, parse_match_criteria, ssh_config_path, system_config_path,
+    MatchCriterion,
+};
+use super::include::{load_config_file, load_config_file_in, ConfigFile, ConfigItem};
+use super::parser::Directive;
+use super::pattern::{match_host_patterns, match_pattern_list};
+use super::types::{ResolveBackend, ResolvedHost, ResolvedOption};
+
+/// Keywords that accumulate across every matching block instead of
+/// keeping only the first obtained value
+pub const MULTI_VALUE_KEYWORDS: &[&str] = &[
+    "identityfile",
+    "certificatefile",
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+    "sendenv",
+];
+
+/// Identity files ssh tries when none is configured
+const DEFAULT_IDENTITY_FILES: &[&str] = &[
+    "~/.ssh/id_rsa",
+    "~/.ssh/id_ecdsa",
+    "~/.ssh/id_ecdsa_sk",
+    "~/.ssh/id_ed25519",
+    "~/.ssh/id_ed25519_sk",
+];
+
+/// Load the main user config followed by the system-wide config, which is
+/// the order ssh reads them in. Missing files are skipped.
+pub fn load_effective_configs() -> Result<Vec<ConfigFile>, String> {
+    let user_path = ssh_config_path();
+    if user_path.exists() {
+        load_root_configs(&user_path)
+    } else {
+        Ok(load_system_config().into_iter().collect())
+    }
+}
+
+/// Load a config root and the configs ssh reads after it
+pub fn load_root_configs(root: &Path) -> Result<Vec<ConfigFile>, String> {
+    Ok(with_following_configs(load_config_file(root)?))
+}
+
+/// An already loaded config root followed by the configs ssh reads after
+/// it. The system config only follows the default user config: `ssh -F`
+/// skips it.
+pub fn with_following_configs(root: ConfigFile) -> Vec<ConfigFile> {
+    let is_default = root.path == default_config_path();
+    let mut configs = vec![root];
+    if is_default {
+        configs.extend(load_system_config());
+    }
+    configs
+}
+
+fn load_system_config() -> Option<ConfigFile> {
+    let system_path = system_config_path();
+    if !system_path.exists() {
+        return None;
+    }
+    let base_dir = system_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default();
+    load_config_file_in(&system_path, &base_dir).ok()
+}
+
+/// Compute the effective settings ssh would use for `alias`, reading the
+/// config root that declares it (or the main config if none does)
+pub fn resolve_host(alias: &str) -> Result<ResolvedHost, String> {
+    let configs = match find_host_root(alias) {
+        Some(root) => load_root_configs(&root)?,
+        None => load_effective_configs()?,
+    };
+    Ok(resolve_in(&configs, alias))
+}
+
+/// Compute the effective settings for `alias` from already loaded configs.
+///
+/// Follows OpenSSH's rules: files are read top to bottom, `Host` and
+/// `Match` lines toggle whether the following options apply, and for each
+/// keyword the first obtained value wins (except for the accumulating
+/// keywords). If any `Match final` block exists the configs are read a
+/// second time against the resolved HostName, as ssh does.
+///
+/// Options under a `Match` whose criteria can't be evaluated here (such as
+/// `exec`) are reported as conditional and don't shadow later values.
+pub fn resolve_in(configs: &[ConfigFile], alias: &str) -> ResolvedHost {
+    // ssh lowercases the destination before matching; patterns are not
+    let host = alias.to_lowercase();
+    let mut resolver = Resolver {
+        host: host.clone(),
+        original_host: host,
+        final_pass: false,
+        wants_final_pass: false,
+        options: Vec::new(),
+        seen: HashSet::new(),
+    };
+    for config in configs {
+        resolver.walk(config, &Activity::Active);
+    }
+    if resolver.wants_final_pass {
+        resolver.host = resolver.current_hostname();
+        resolver.final_pass = true;
+        for config in configs {
+            resolver.walk(config, &Activity::Active);
+        }
+    }
+    resolver.finish(alias)
+}
+
+impl ResolvedHost {
+    /// First unconditional value of `keyword` that was set by a config
+    /// file, ignoring built-in defaults
+    pub fn configured(&self, keyword: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|o| o.keyword == keyword && o.source_file.is_some() && o.conditional.is_none())
+            .map(|o| o.value.as_str())
+    }
+}
+
+/// Whether the options currently being read apply to the host
+#[derive(Debug, Clone)]
+enum Activity {
+    Active,
+    Inactive,
+    /// Applies only if the given criteria (e.g. `exec ...`) hold at connect time
+    Conditional(String),
+}
+
+impl Activity {
+    /// State of a block opened inside a file included with `self` in effect
+    fn within(&self, own: Activity) -> Activity {
+        match (self, own) {
+            (Activity::Inactive, _) | (_, Activity::Inactive) => Activity::Inactive,
+            (Activity::Conditional(outer), Activity::Conditional(inner)) => {
+                Activity::Conditional(format!("{} && {}", outer, inner))
+            }
+            (Activity::Conditional(outer), Activity::Active) => {
+                Activity::Conditional(outer.clone())
+            }
+            (Activity::Active, own) => own,
+        }
+    }
+}
+
+struct Resolver {
+    /// Name Host lines are matched against; the HostName in the final pass
+    host: String,
+    original_host: String,
+    final_pass: bool,
+    wants_final_pass: bool,
+    options: Vec<ResolvedOption>,
+    seen: HashSet<String>,
+}
+
+impl Resolver {
+    /// Apply the options of `file` that are in effect for this host.
+    /// `inherited` is the state of the block the file was included from;
+    /// nothing in a file included from an inactive block can apply,
+    /// whatever its own Host lines say.
+    fn walk(&mut self, file: &ConfigFile, inherited: &Activity) {
+        let mut state = inherited.clone();
+        for item in &file.items {
+            match item {
+                ConfigItem::Directive(d) if d.key() == "host" => {
+                    let own = if match_host_patterns(&self.host, &d.args) {
+                        Activity::Active
+                    } else {
+                        Activity::Inactive
+                    };
+                    state = inherited.within(own);
+                }
+                ConfigItem::Directive(d) if d.key() == "match" => {
+                    state = inherited.within(self.evaluate_match(d));
+                }
+                ConfigItem::Directive(d) => match &state {
+                    Activity::Active => self.obtain(file, d, None),
+                    Activity::Conditional(c) => self.obtain(file, d, Some(c.clone())),
+                    Activity::Inactive => {}
+                },
+                ConfigItem::Include { files } => {
+                    for inc in files {
+                        self.walk(inc, &state);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Evaluate a `Match` line. Criteria that depend on the environment at
+    /// connect time are collected into a `Conditional` result.
+    fn evaluate_match(&mut self, directive: &Directive) -> Activity {
+        let conditions = match parse_match_criteria(&directive.args) {
+            Ok(c) => c,
+            Err(_) => return Activity::Inactive,
+        };
+
+        let mut pending = Vec::new();
+        for condition in &conditions {
+            let result = match &condition.criterion {
+                MatchCriterion::All => Some(true),
+                MatchCriterion::Canonical => Some(self.final_pass),
+                MatchCriterion::Final => {
+                    self.wants_final_pass = true;
+                    Some(self.final_pass)
+                }
+                MatchCriterion::Host(list) => {
+                    Some(match_pattern_list(&self.current_hostname(), list, true))
+                }
+                MatchCriterion::OriginalHost(list) => {
+                    Some(match_pattern_list(&self.original_host, list, true))
+                }
+                MatchCriterion::User(list) => {
+                    let user = self.first("user").map(|u| u.to_string());
+                    Some(match_pattern_list(
+                        &user.unwrap_or_else(local_user),
+                        list,
+                        false,
+                    ))
+                }
+                MatchCriterion::LocalUser(list) => {
+                    Some(match_pattern_list(&local_user(), list, false))
+                }
+                MatchCriterion::Tagged(list) => Some(match_pattern_list(
+                    self.first("tag").unwrap_or(""),
+                    list,
+                    false,
+                )),
+                MatchCriterion::Exec(_)
+                | MatchCriterion::LocalNetwork(_)
+                | MatchCriterion::Command(_) => None,
+            };
+            match result {
+                Some(matched) if matched == condition.negated => return Activity::Inactive,
+                Some(_) => {}
+                None => pending.push(condition.to_string()),
+            }
+        }
+
+        if pending.is_empty() {
+            Activity::Active
+        } else {
+            Activity::Conditional(pending.join(" "))
+        }
+    }
+
+    fn obtain(&mut self, file: &ConfigFile, directive: &Directive, conditional: Option<String>) {
+        let keyword = directive.key();
+        let value = directive.value();
+        let source_file = file.path.to_string_lossy().to_string();
+
+        // The final pass re-reads every file; don't record a line twice
+        if self.options.iter().any(|o| {
+            o.source_line == Some(directive.line) && o.source_file.as_deref() == Some(&source_file)
+        }) {
+            return;
+        }
+        if MULTI_VALUE_KEYWORDS.contains(&keyword.as_str()) {
+            // ssh skips duplicate identities and forwards
+            if self
+                .options
+                .iter()
+                .any(|o| o.keyword == keyword && o.value == value && o.conditional == conditional)
+            {
+                return;
+            }
+        } else if self.seen.contains(&keyword) {
+            return;
+        } else if conditional.is_none() {
+            self.seen.insert(keyword.clone());
+        }
+
+        self.options.push(ResolvedOption {
+            keyword,
+            value,
+            source_file: Some(source_file),
+            source_line: Some(directive.line),
+            conditional,
+        });
+    }
+
+    /// First unconditional value obtained for `keyword`
+    fn first(&self, keyword: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|o| o.keyword == keyword && o.conditional.is_none())
+            .map(|o| o.value.as_str())
+    }
+
+    /// HostName obtained so far with tokens expanded, or the alias
+    fn current_hostname(&self) -> String {
+        match self.first("hostname") {
+            Some(h) => expand_hostname_tokens(h, &self.original_host),
+            None => self.original_host.clone(),
+        }
+    }
+
+    fn push_default(&mut self, keyword: &str, value: String) {
+        self.options.push(ResolvedOption {
+            keyword: keyword.to_string(),
+            value,
+            source_file: None,
+            source_line: None,
+            conditional: None,
+        });
+    }
+
+    fn finish(mut self, alias: &str) -> ResolvedHost {
+        let hostname = self.current_hostname();
+        match self
+            .options
+            .iter_mut()
+            .find(|o| o.keyword == "hostname" && o.conditional.is_none())
+        {
+            // ssh -G prints the expanded HostName
+            Some(opt) => opt.value = hostname.clone(),
+            None => self.push_default("hostname", hostname.clone()),
+        }
+
+        let user = match self.first("user") {
+            Some(u) => u.to_string(),
+            None => {
+                let local = local_user();
+                self.push_default("user", local.clone());
+                local
+            }
+        };
+
+        let port = match self.first("port") {
+            Some(p) => p.parse().unwrap_or(22),
+            None => {
+                self.push_default("port", "22".to_string());
+                22
+            }
+        };
+
+        if self.first("identityfile").is_none() {
+            for path in DEFAULT_IDENTITY_FILES {
+                self.push_default("identityfile", path.to_string());
+            }
+        }
+
+        ResolvedHost {
+            alias: alias.to_string(),
+            hostname,
+            user,
+            port,
+            options: self.options,
+            computed_by: ResolveBackend::Builtin,
+        }
+    }
+}
+
+/// Expand the `%h` and `%%` tokens that HostName accepts
+fn expand_hostname_tokens(value: &str, host: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => result.push_str(host),
+            Some('%') => result.push('%'),
+            Some(other) => {
+                result.push('%');
+                result.push(other);
+            }
+            None => result.push('%'),
+        }
+    }
+    result
+}
+
+/// Name of the local user, which ssh uses when no `User` is configured
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// Expand a leading `~/` in a path option value
+pub fn expand_tilde(value: &str) -> String {
+    match value.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|h| h.join(rest))
+            .unwrap_or_else(|| PathBuf::from(value))
+            .to_string_lossy()
+            .to_string(),
+        None => value.to_string(),
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::include::include_base_dir;
+use super::types::{HostMatch, SshHost};
+use crate::history::ConnectionHistory;
+
+/// How much a match in each field counts, best first
+const FIELD_WEIGHTS: &[(&str, f64)] = &[
+    ("alias", 3.0),
+    ("hostname", 2.0),
+    ("tags", 1.5),
+    ("user", 1.0),
+    ("description", 1.0),
+    ("source_file", 0.5),
+];
+
+/// Usage boost per e-fold of connections made to a host
+const FREQUENCY_WEIGHT: f64 = 20.0;
+
+/// Usage boost for a connection made just now; halves every
+/// [`RECENCY_HALF_LIFE_DAYS`]
+const RECENCY_WEIGHT: f64 = 60.0;
+const RECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Usage boost for a pinned host
+const PINNED_BONUS: f64 = 50.0;
+
+/// Rank `hosts` against `query`. Every whitespace-separated term must match
+/// one of the alias, hostname, user, tags, description or source file,
+/// exactly, as a prefix, a substring or a subsequence (in decreasing
+/// order of score). The match score is raised by a percentage given by
+/// how often and lately the host was connected to, so usage reorders
+/// similar matches without lifting weak ones above strong ones; an empty
+/// query ranks every host by usage alone.
+pub fn search_hosts(hosts: &[SshHost], history: &ConnectionHistory, query: &str) -> Vec<HostMatch> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+
+    let mut matches: Vec<HostMatch> = hosts
+        .iter()
+        .filter_map(|host| {
+            let mut score = 0.0;
+            let mut matched_fields: Vec<String> = Vec::new();
+            for term in &terms {
+                let (field, term_score) = best_field(host, term)?;
+                score += term_score;
+                if !matched_fields.iter().any(|f| f == field) {
+                    matched_fields.push(field.to_string());
+                }
+            }
+            let boost = usage_boost(history, &host.name, now);
+            Some(HostMatch {
+                score: if terms.is_empty() {
+                    boost
+                } else {
+                    score * (1.0 + boost / 100.0)
+                },
+                host: host.clone(),
+                matched_fields,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches
+}
+
+/// The field `term` matches best, with its weighted score
+fn best_field(host: &SshHost, term: &str) -> Option<(&'static str, f64)> {
+    FIELD_WEIGHTS
+        .iter()
+        .filter_map(|&(field, weight)| {
+            let score = field_values(host, field)
+                .iter()
+                .filter_map(|value| fuzzy_score(term, value))
+                .max_by(f64::total_cmp)?;
+            Some((field, score * weight))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+fn field_values<'a>(host: &'a SshHost, field: &str) -> Vec<&'a str> {
+    match field {
+        "alias" => vec![host.name.as_str()],
+        "hostname" => vec![host.hostname.as_str()],
+        "user" => vec![host.user.as_str()],
+        "tags" => host.tags.iter().map(String::as_str).collect(),
+        "description" => host.metadata.description.as_deref().into_iter().collect(),
+        // Relative to ~/.ssh, so every host doesn't match its directories
+        "source_file" => {
+            let base = include_base_dir();
+            let relative = Path::new(&host.source_file)
+                .strip_prefix(&base)
+                .ok()
+                .and_then(|p| p.to_str());
+            match relative {
+                Some(r) => vec![&host.source_file[host.source_file.len() - r.len()..]],
+                None => vec![host.source_file.as_str()],
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// How well the lowercase `term` matches `value`: 100 for equal, 80 for a
+/// prefix, 60-70 for a substring (more at a word start), up to 40 for a
+/// subsequence depending on how tightly its characters are packed
+fn fuzzy_score(term: &str, value: &str) -> Option<f64> {
+    let value = value.to_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+    if value == term {
+        return Some(100.0);
+    }
+    if value.starts_with(term) {
+        return Some(80.0);
+    }
+    if let Some(i) = value.find(term) {
+        let at_word_start = value[..i].ends_with(|c: char| !c.is_alphanumeric());
+        return Some(if at_word_start { 70.0 } else { 60.0 });
+    }
+
+    let chars: Vec<char> = value.chars().collect();
+    let mut positions = Vec::new();
+    let mut from = 0;
+    for c in term.chars() {
+        let i = (from..chars.len()).find(|&i| chars[i] == c)?;
+        positions.push(i);
+        from = i + 1;
+    }
+    let span = positions.last()? - positions.first()? + 1;
+    Some(40.0 * positions.len() as f64 / span as f64)
+}
+
+/// Boost for how often and how lately `alias` was connected to, and
+/// whether it is pinned
+fn usage_boost(history: &ConnectionHistory, alias: &str, now: u64) -> f64 {
+    let entry = history.entry(alias);
+    let frequency = FREQUENCY_WEIGHT * (entry.count as f64).ln_1p();
+    let recency = if entry.last_connected > 0 {
+        let age_days = now.saturating_sub(entry.last_connected) as f64 / 86_400_000.0;
+        RECENCY_WEIGHT * 0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+    } else {
+        0.0
+    };
+    let pinned = if entry.pinned { PINNED_BONUS } else { 0.0 };
+    frequency + recency + pinned
+}
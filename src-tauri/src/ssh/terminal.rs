@@ -0,0 +1,682 @@
+use std::process::Command;
+
+use super::launch::SshLaunch;
+use super::types::TerminalInfo;
+use crate::settings;
+
+/// Id of the terminal run from the user's argv template
+pub const CUSTOM_TERMINAL: &str = "custom";
+
+/// Placeholders a custom terminal template can use
+pub const TEMPLATE_PLACEHOLDERS: &[&str] =
+    &["alias", "host", "user", "port", "ssh_command", "title"];
+
+/// A terminal emulator that can open a window running an ssh invocation
+pub trait TerminalLauncher {
+    /// Id stored in the `default_terminal` setting
+    fn id(&self) -> &str;
+    /// Name shown in the settings
+    fn name(&self) -> &str;
+    /// Whether the terminal can be launched on this machine
+    fn is_installed(&self) -> bool;
+    /// Open a new window running `launch`
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String>;
+    /// Open `launches` as tabs of one window; terminals without tabs open
+    /// a window for each
+    fn launch_tabs(&self, launches: &[SshLaunch]) -> Result<(), String> {
+        launches.iter().try_for_each(|launch| self.launch(launch))
+    }
+}
+
+/// Terminals known on this platform, in the order one is picked when the
+/// configured terminal isn't installed
+pub fn terminal_launchers() -> Vec<Box<dyn TerminalLauncher>> {
+    let mut launchers: Vec<Box<dyn TerminalLauncher>> = Vec::new();
+
+    #[cfg(target_os = "macos")]
+    {
+        launchers.push(Box::new(AppleScriptTerminal {
+            id: "terminal",
+            name: "Terminal.app",
+            app: "Terminal",
+            script: "do script",
+            tab_script: None,
+        }));
+        launchers.push(Box::new(AppleScriptTerminal {
+            id: "iterm",
+            name: "iTerm2",
+            app: "iTerm",
+            script: "create window with default profile command",
+            tab_script: Some("create tab with default profile command"),
+        }));
+        launchers.push(Box::new(WarpTerminal));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        launchers.push(Box::new(EnvTerminal));
+        launchers.extend(
+            LINUX_TERMINALS
+                .iter()
+                .map(|t| Box::new(t.clone()) as Box<dyn TerminalLauncher>),
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    launchers.push(Box::new(CmdTerminal));
+
+    launchers.push(Box::new(CustomTerminal {
+        template: settings::load_settings().custom_terminal,
+    }));
+    launchers
+}
+
+/// Installed terminals, for the settings to choose from
+pub fn detect_installed_terminals() -> Vec<TerminalInfo> {
+    terminal_launchers()
+        .iter()
+        .filter(|t| t.is_installed())
+        .map(|t| TerminalInfo {
+            id: t.id().to_string(),
+            name: t.name().to_string(),
+        })
+        .collect()
+}
+
+/// Open `launch` in the terminal with id `terminal`, or in the first
+/// installed one if that isn't available here
+pub fn open_in_terminal(terminal: &str, launch: &SshLaunch) -> Result<(), String> {
+    with_terminal(terminal, |launcher| launcher.launch(launch))
+}
+
+/// Open `launches` as tabs of the terminal with id `terminal`, or of the
+/// first installed one if that isn't available here
+pub fn open_tabs_in_terminal(terminal: &str, launches: &[SshLaunch]) -> Result<(), String> {
+    with_terminal(terminal, |launcher| launcher.launch_tabs(launches))
+}
+
+fn with_terminal(
+    terminal: &str,
+    run: impl FnOnce(&dyn TerminalLauncher) -> Result<(), String>,
+) -> Result<(), String> {
+    let launchers = terminal_launchers();
+    let launcher = launchers
+        .iter()
+        .find(|t| t.id() == terminal && t.is_installed())
+        .or_else(|| launchers.iter().find(|t| t.is_installed()))
+        .ok_or_else(|| "No terminal emulator found".to_string())?;
+    run(launcher.as_ref())
+}
+
+fn spawn(command: &mut Command, name: &str) -> Result<(), String> {
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", name, e))
+}
+
+/// Whether `program` is found on `PATH`
+fn on_path(program: &str) -> bool {
+    which::which(program).is_ok()
+}
+
+/// A macOS terminal driven by an AppleScript command taking a shell line
+#[cfg(target_os = "macos")]
+struct AppleScriptTerminal {
+    id: &'static str,
+    name: &'static str,
+    /// Application name for `tell application`
+    app: &'static str,
+    /// Command followed by the shell line
+    script: &'static str,
+    /// Command opening a tab in the window `script` returns, if any
+    tab_script: Option<&'static str>,
+}
+
+#[cfg(target_os = "macos")]
+impl TerminalLauncher for AppleScriptTerminal {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn is_installed(&self) -> bool {
+        mac_app_installed(self.app)
+    }
+
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String> {
+        let script = format!(
+            "tell application \"{}\"\nactivate\n{} {}\nend tell",
+            self.app,
+            self.script,
+            launch.applescript_literal()
+        );
+        spawn(Command::new("osascript").arg("-e").arg(script), self.name)
+    }
+
+    fn launch_tabs(&self, launches: &[SshLaunch]) -> Result<(), String> {
+        let (Some(tab_script), Some((first, rest))) = (self.tab_script, launches.split_first())
+        else {
+            return launches.iter().try_for_each(|launch| self.launch(launch));
+        };
+        let mut script = format!(
+            "tell application \"{}\"\nactivate\nset w to ({} {})\ntell w\n",
+            self.app,
+            self.script,
+            first.applescript_literal()
+        );
+        for launch in rest {
+            script.push_str(&format!(
+                "{} {}\n",
+                tab_script,
+                launch.applescript_literal()
+            ));
+        }
+        script.push_str("end tell\nend tell");
+        spawn(Command::new("osascript").arg("-e").arg(script), self.name)
+    }
+}
+
+/// Warp doesn't support AppleScript `do script`, and using System Events
+/// keystroke requires Accessibility permissions, so it opens a
+/// self-deleting .command file instead
+#[cfg(target_os = "macos")]
+struct WarpTerminal;
+
+#[cfg(target_os = "macos")]
+impl TerminalLauncher for WarpTerminal {
+    fn id(&self) -> &str {
+        "warp"
+    }
+
+    fn name(&self) -> &str {
+        "Warp"
+    }
+
+    fn is_installed(&self) -> bool {
+        mac_app_installed("Warp")
+    }
+
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String> {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+
+        // A random name, so concurrent launches (workspace tabs) don't
+        // overwrite each other's script; it deletes itself when run
+        let mut script = tempfile::Builder::new()
+            .prefix("ssh-m-connect-")
+            .suffix(".command")
+            .permissions(std::fs::Permissions::from_mode(0o700))
+            .tempfile()
+            .map_err(|e| format!("Failed to create temp script: {}", e))?;
+        script
+            .write_all(launch.command_script().as_bytes())
+            .map_err(|e| format!("Failed to write temp script: {}", e))?;
+        let (_, tmp_path) = script
+            .keep()
+            .map_err(|e| format!("Failed to keep temp script: {}", e))?;
+        spawn(
+            Command::new("open").arg("-a").arg("Warp").arg(&tmp_path),
+            "Warp",
+        )
+    }
+}
+
+/// Whether `<app>.app` is in the system or the user's Applications folder
+#[cfg(target_os = "macos")]
+fn mac_app_installed(app: &str) -> bool {
+    // Terminal.app ships with the system
+    if app == "Terminal" {
+        return true;
+    }
+    let bundle = format!("{}.app", app);
+    let user_apps = dirs::home_dir().map(|home| home.join("Applications"));
+    [Some(std::path::PathBuf::from("/Applications")), user_apps]
+        .into_iter()
+        .flatten()
+        .any(|dir| dir.join(&bundle).exists())
+}
+
+/// How a Linux terminal takes the command to run
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+enum CommandForm {
+    /// The ssh argv follows the terminal's own arguments
+    Argv,
+    /// The ssh invocation is passed as one shell-quoted argument
+    Line,
+}
+
+/// A Linux terminal emulator started directly with its own flags
+#[cfg(target_os = "linux")]
+#[derive(Clone)]
+struct LinuxTerminal {
+    id: &'static str,
+    name: &'static str,
+    program: &'static str,
+    /// Arguments before the command
+    args: &'static [&'static str],
+    form: CommandForm,
+    /// Flag that opens the command in a new tab of the open window
+    tab_flag: Option<&'static str>,
+}
+
+#[cfg(target_os = "linux")]
+const LINUX_TERMINALS: &[LinuxTerminal] = &[
+    LinuxTerminal {
+        id: "gnome-terminal",
+        name: "GNOME Terminal",
+        program: "gnome-terminal",
+        args: &["--"],
+        form: CommandForm::Argv,
+        tab_flag: Some("--tab"),
+    },
+    LinuxTerminal {
+        id: "konsole",
+        name: "Konsole",
+        program: "konsole",
+        args: &["-e"],
+        form: CommandForm::Argv,
+        tab_flag: Some("--new-tab"),
+    },
+    LinuxTerminal {
+        id: "xfce4-terminal",
+        name: "Xfce Terminal",
+        program: "xfce4-terminal",
+        args: &["-x"],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "tilix",
+        name: "Tilix",
+        program: "tilix",
+        args: &["-e"],
+        form: CommandForm::Line,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "terminator",
+        name: "Terminator",
+        program: "terminator",
+        args: &["-x"],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "alacritty",
+        name: "Alacritty",
+        program: "alacritty",
+        args: &["-e"],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "kitty",
+        name: "kitty",
+        program: "kitty",
+        args: &[],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "wezterm",
+        name: "WezTerm",
+        program: "wezterm",
+        args: &["start", "--"],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "foot",
+        name: "foot",
+        program: "foot",
+        args: &[],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+    LinuxTerminal {
+        id: "xterm",
+        name: "XTerm",
+        program: "xterm",
+        args: &["-e"],
+        form: CommandForm::Argv,
+        tab_flag: None,
+    },
+];
+
+#[cfg(target_os = "linux")]
+impl TerminalLauncher for LinuxTerminal {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn is_installed(&self) -> bool {
+        on_path(self.program)
+    }
+
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String> {
+        spawn(
+            Command::new(self.program).args(self.command_args(launch, false)),
+            self.name,
+        )
+    }
+
+    fn launch_tabs(&self, launches: &[SshLaunch]) -> Result<(), String> {
+        let (Some(_), Some((first, rest))) = (self.tab_flag, launches.split_first()) else {
+            return launches.iter().try_for_each(|launch| self.launch(launch));
+        };
+        self.launch(first)?;
+        for launch in rest {
+            spawn(
+                Command::new(self.program).args(self.command_args(launch, true)),
+                self.name,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl LinuxTerminal {
+    /// Arguments after the program that run `launch`, in a new tab of the
+    /// open window if `tab` and the terminal has tabs
+    fn command_args(&self, launch: &SshLaunch, tab: bool) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        if tab {
+            args.extend(self.tab_flag.map(str::to_string));
+        }
+        args.extend(self.args.iter().map(|a| a.to_string()));
+        match self.form {
+            CommandForm::Argv => args.extend(launch.argv().iter().cloned()),
+            CommandForm::Line => args.push(launch.posix_command()),
+        }
+        args
+    }
+}
+
+/// The terminal named by `$TERMINAL`, started with the common `-e` flag
+#[cfg(target_os = "linux")]
+struct EnvTerminal;
+
+#[cfg(target_os = "linux")]
+impl EnvTerminal {
+    fn program() -> Option<String> {
+        std::env::var("TERMINAL")
+            .ok()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl TerminalLauncher for EnvTerminal {
+    fn id(&self) -> &str {
+        "env"
+    }
+
+    fn name(&self) -> &str {
+        "$TERMINAL"
+    }
+
+    fn is_installed(&self) -> bool {
+        Self::program().is_some_and(|p| on_path(&p))
+    }
+
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String> {
+        let program = Self::program().ok_or_else(|| "$TERMINAL is not set".to_string())?;
+        spawn(
+            Command::new(program).arg("-e").args(launch.argv()),
+            "$TERMINAL",
+        )
+    }
+}
+
+/// A console window opened by cmd.exe's `start`
+#[cfg(target_os = "windows")]
+struct CmdTerminal;
+
+#[cfg(target_os = "windows")]
+impl TerminalLauncher for CmdTerminal {
+    fn id(&self) -> &str {
+        "cmd"
+    }
+
+    fn name(&self) -> &str {
+        "命令提示符"
+    }
+
+    fn is_installed(&self) -> bool {
+        true
+    }
+
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String> {
+        use std::os::windows::process::CommandExt;
+
+        // cmd.exe parses its command line itself, so pass it pre-quoted;
+        // the empty title keeps `start` from taking a quoted argument as one
+        spawn(
+            Command::new("cmd").raw_arg(format!("/c start \"\" {}", launch.cmd_command()?)),
+            "terminal",
+        )
+    }
+}
+
+/// A terminal started from the user's argv template, with placeholders
+/// filled in per argument. No shell is involved, so values can't split or
+/// inject arguments; `{ssh_command}` is the invocation as one POSIX
+/// shell line, for terminals that run a command string.
+struct CustomTerminal {
+    template: Vec<String>,
+}
+
+impl TerminalLauncher for CustomTerminal {
+    fn id(&self) -> &str {
+        CUSTOM_TERMINAL
+    }
+
+    fn name(&self) -> &str {
+        "自定义"
+    }
+
+    fn is_installed(&self) -> bool {
+        validate_terminal_template(&self.template).is_ok() && on_path(&self.template[0])
+    }
+
+    fn launch(&self, launch: &SshLaunch) -> Result<(), String> {
+        validate_terminal_template(&self.template)?;
+        let endpoint = launch.endpoint();
+        let value = |name: &str| match name {
+            "alias" | "title" => launch.target().to_string(),
+            "host" => endpoint.host.clone(),
+            "user" => endpoint.user.clone(),
+            "port" => endpoint.port.to_string(),
+            _ => launch.posix_command(),
+        };
+        let argv = self
+            .template
+            .iter()
+            .map(|arg| expand_template_arg(arg, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        spawn(Command::new(&argv[0]).args(&argv[1..]), &self.template[0])
+    }
+}
+
+/// Check a custom terminal template: a program without placeholders,
+/// then arguments using only known placeholders. `{ssh_command}` is
+/// required: it is the only placeholder carrying the config files,
+/// profile options and remote command of a launch.
+pub fn validate_terminal_template(template: &[String]) -> Result<(), String> {
+    let program = template
+        .first()
+        .filter(|p| !p.trim().is_empty())
+        .ok_or_else(|| "Custom terminal command is empty".to_string())?;
+    if program.contains('{') {
+        return Err("The custom terminal program can't contain placeholders".to_string());
+    }
+
+    let mut used = Vec::new();
+    for arg in template {
+        expand_template_arg(arg, |name: &str| {
+            used.push(name.to_string());
+            String::new()
+        })?;
+    }
+    if !used.iter().any(|p| p == "ssh_command") {
+        return Err("Custom terminal command needs {ssh_command}".to_string());
+    }
+    Ok(())
+}
+
+/// Replace the `{name}` placeholders in `arg` with `value(name)`; `{{` and
+/// `}}` stand for literal braces
+fn expand_template_arg(arg: &str, mut value: impl FnMut(&str) -> String) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = arg;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        if tail.starts_with('}') {
+            return Err(format!(
+                "Unmatched \"}}\" in terminal template argument \"{}\"",
+                arg
+            ));
+        }
+        let end = tail
+            .find('}')
+            .ok_or_else(|| format!("Unclosed \"{{\" in terminal template argument \"{}\"", arg))?;
+        let name = &tail[1..end];
+        if !TEMPLATE_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown terminal template placeholder {{{}}}",
+                name
+            ));
+        }
+        out.push_str(&value(name));
+        rest = &tail[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_terminals_build_their_argv() {
+        let launch = SshLaunch::tmux_attach("work").unwrap();
+        let command = ["tmux", "attach-session", "-t", "=work"];
+        let line = launch.posix_command();
+        // Terminal, new tab, arguments before the command
+        let cases: &[(&str, bool, &[&str])] = &[
+            ("gnome-terminal", false, &["--"]),
+            ("gnome-terminal", true, &["--tab", "--"]),
+            ("konsole", false, &["-e"]),
+            ("konsole", true, &["--new-tab", "-e"]),
+            ("xfce4-terminal", false, &["-x"]),
+            ("terminator", false, &["-x"]),
+            ("alacritty", false, &["-e"]),
+            ("alacritty", true, &["-e"]),
+            ("kitty", false, &[]),
+            ("wezterm", false, &["start", "--"]),
+            ("foot", false, &[]),
+            // xterm takes the command after -e and has no `--`
+            ("xterm", false, &["-e"]),
+        ];
+        for (id, tab, prefix) in cases {
+            let terminal = LINUX_TERMINALS.iter().find(|t| t.id == *id).unwrap();
+            let mut expected: Vec<&str> = prefix.to_vec();
+            expected.extend(command);
+            assert_eq!(
+                terminal.command_args(&launch, *tab),
+                expected,
+                "{} {}",
+                id,
+                tab
+            );
+        }
+
+        // Tilix runs one command line
+        let tilix = LINUX_TERMINALS.iter().find(|t| t.id == "tilix").unwrap();
+        assert_eq!(tilix.command_args(&launch, false), ["-e", line.as_str()]);
+    }
+
+    #[test]
+    fn expand_template_arg_fills_placeholders() {
+        let value = |name: &str| format!("<{}>", name);
+        let cases = [
+            ("{alias}", "<alias>"),
+            ("--title={title}", "--title=<title>"),
+            ("{user}@{host}:{port}", "<user>@<host>:<port>"),
+            ("{{alias}}", "{alias}"),
+            ("{{{alias}}}", "{<alias>}"),
+            ("a}}b{{c", "a}b{c"),
+            ("plain", "plain"),
+            ("", ""),
+        ];
+        for (arg, expanded) in cases {
+            assert_eq!(
+                expand_template_arg(arg, value).unwrap(),
+                expanded,
+                "{:?}",
+                arg
+            );
+        }
+    }
+
+    #[test]
+    fn expand_template_arg_refuses_bad_placeholders() {
+        let cases = [
+            ("{command}", "Unknown"),
+            ("{}", "Unknown"),
+            ("{ALIAS}", "Unknown"),
+            ("{alias", "Unclosed"),
+            ("--title={", "Unclosed"),
+            ("alias}", "Unmatched"),
+            ("{alias}}", "Unmatched"),
+        ];
+        for (arg, error) in cases {
+            let result = expand_template_arg(arg, |name: &str| name.to_string());
+            assert!(
+                result.as_ref().is_err_and(|e| e.starts_with(error)),
+                "{:?}: {:?}",
+                arg,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn validate_terminal_template_needs_ssh_command() {
+        let template = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert!(validate_terminal_template(&template(&[
+            "wezterm",
+            "-e",
+            "sh",
+            "-c",
+            "{ssh_command}"
+        ]))
+        .is_ok());
+        assert!(validate_terminal_template(&template(&[
+            "xterm", "-T", "{title}", "-e", "ssh", "{alias}"
+        ]))
+        .is_err());
+        assert!(validate_terminal_template(&template(&["{ssh_command}"])).is_err());
+        assert!(validate_terminal_template(&template(&[" ", "{ssh_command}"])).is_err());
+        assert!(validate_terminal_template(&[]).is_err());
+    }
+}
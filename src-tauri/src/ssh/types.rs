@@ -1,4 +1,6 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 
 /// Represents a parsed SSH host entry from ~/.ssh/config
 #[derive(Debug, Clone, Serialize)]
@@ -10,14 +12,367 @@ pub struct SshHost {
     pub identity_file: Option<String>,
     pub proxy_jump: Option<String>,
     pub is_1password_agent: bool,
-    pub group: SshHostGroup,
+    /// Group from the first matching group rule or a `# ssh-m:` comment
+    pub group: String,
+    /// Tags from every matching group rule and `# ssh-m:` comments
+    pub tags: Vec<String>,
+    /// Config file the `Host` line was found in (may be an included file)
+    pub source_file: String,
+    /// 1-based line number of the `Host` line within `source_file`
+    pub source_line: usize,
+    /// Every pattern on that `Host` line, including other aliases
+    pub host_patterns: Vec<String>,
+    /// From the `#@ssh-m` comments of the block
+    pub metadata: HostMetadata,
+}
+
+/// Descriptive fields kept in `#@ssh-m key=value ...` comments above or
+/// inside a `Host` block
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostMetadata {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Environment label, e.g. `prod`
+    #[serde(default)]
+    pub env: Option<String>,
+    /// Colour name or `#rrggbb`
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Any other fields, by lowercase key
+    #[serde(default)]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// A host returned by a search with its score, best matches first
+#[derive(Debug, Clone, Serialize)]
+pub struct HostMatch {
+    pub host: SshHost,
+    pub score: f64,
+    /// Fields the query matched, e.g. `alias` or `tags`
+    pub matched_fields: Vec<String>,
+}
+
+/// A `Host` block with wildcard or negated patterns, e.g. `Host *.corp !bastion`
+#[derive(Debug, Clone, Serialize)]
+pub struct SshHostPattern {
+    pub patterns: Vec<String>,
+    pub source_file: String,
+    pub source_line: usize,
+}
+
+/// A rule from the settings assigning a group and tags to hosts. Every
+/// condition that is set must hold; a rule without any matches all hosts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupRule {
+    /// Group of matching hosts; the first matching rule with one wins
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Tags added to matching hosts
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Pattern list the alias must match, e.g. `prod-*,!prod-test`
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Pattern list the effective HostName must match
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// Comma-separated networks, e.g. `10.0.0.0/8,fc00::/7`, the HostName
+    /// must be an address in
+    #[serde(default)]
+    pub cidr: Option<String>,
+    /// Whether the host must (or must not) have a ProxyJump
+    #[serde(default)]
+    pub proxy_jump: Option<bool>,
+    /// Pattern the path of the file declaring the host must match
+    #[serde(default)]
+    pub source_file: Option<String>,
+}
+
+/// A named way of connecting from the settings: extra ssh options and a
+/// command to run on the host. Offered for hosts matching every condition
+/// that is set; a profile without any is offered for all hosts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    pub name: String,
+    /// Pattern list the alias must match, e.g. `prod-*,!prod-test`
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Group the host must be in
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Options passed before the destination, e.g. `-A` or
+    /// `-L`, `5432:localhost:5432`
+    #[serde(default)]
+    pub ssh_options: Vec<String>,
+    /// Command run on the host instead of a login shell, e.g.
+    /// `tmux new -A -s main`
+    #[serde(default)]
+    pub remote_command: Option<String>,
+}
+
+/// A saved set of hosts opened together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    /// Aliases or `user@host[:port]` targets, in pane or tab order
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub layout: WorkspaceLayout,
+    /// Send keyboard input to every tmux pane at once
+    #[serde(default)]
+    pub synchronize_panes: bool,
+}
+
+/// How a workspace's hosts are opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceLayout {
+    /// Tiled panes of one local tmux session, in one terminal window
+    #[default]
+    Tmux,
+    /// A tab per host where the terminal supports it, otherwise a window
+    Tabs,
+}
+
+/// Effective configuration for a host alias, computed the way `ssh -G` does
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedHost {
+    /// Alias as requested
+    pub alias: String,
+    pub hostname: String,
+    pub user: String,
+    pub port: u16,
+    /// Every effective option in the order it was obtained, followed by
+    /// built-in defaults for anything the config didn't set
+    pub options: Vec<ResolvedOption>,
+    pub computed_by: ResolveBackend,
+}
+
+/// What computed a [`ResolvedHost`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolveBackend {
+    /// Our own reading of the config files
+    Builtin,
+    /// The output of the local `ssh -G`, which is authoritative
+    SshClient,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedOption {
+    /// Lowercase keyword, as printed by `ssh -G`
+    pub keyword: String,
+    pub value: String,
+    /// File the value was obtained from; `None` for built-in defaults
+    pub source_file: Option<String>,
+    pub source_line: Option<usize>,
+    /// Set when the value only applies if a `Match` criterion that can't be
+    /// evaluated ahead of time (e.g. `exec`) holds; contains the criteria
+    pub conditional: Option<String>,
+}
+
+/// Typed definition of a single host, used by the structured editing commands
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SshHostDefinition {
+    pub alias: String,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub identity_files: Vec<String>,
+    #[serde(default)]
+    pub proxy_jump: Option<String>,
+    /// `LocalForward` arguments, e.g. `"8080 localhost:80"`
+    #[serde(default)]
+    pub local_forwards: Vec<String>,
+    #[serde(default)]
+    pub remote_forwards: Vec<String>,
+    #[serde(default)]
+    pub dynamic_forwards: Vec<String>,
+    /// Any other options, in file order
+    #[serde(default)]
+    pub extra_options: Vec<HostOption>,
+    #[serde(default)]
+    pub metadata: HostMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostOption {
+    pub keyword: String,
+    /// Raw argument text as it appears after the keyword
+    pub value: String,
+}
+
+/// A saved copy of a config file taken before it was overwritten
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBackup {
+    pub id: String,
+    /// Config file the copy was taken from
+    pub source_path: String,
+    /// Unix time in milliseconds
+    pub created_at: u64,
+    pub size: u64,
 }
 
+/// A config file as read by the editor, with the version it was read at
 #[derive(Debug, Clone, Serialize)]
+pub struct ConfigSnapshot {
+    pub path: String,
+    pub content: String,
+    /// Content hash; pass it back when saving to detect changes on disk
+    pub version: String,
+    /// Modification time in Unix milliseconds
+    pub modified: Option<u64>,
+}
+
+/// One region both the editor and another program changed differently
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictHunk {
+    /// 1-based line of the `<<<<<<<` marker in the merged text
+    pub line: usize,
+    pub base: Vec<String>,
+    pub ours: Vec<String>,
+    pub theirs: Vec<String>,
+}
+
+/// The file changed on disk since it was read and the edits can't be
+/// merged automatically
+#[derive(Debug, Clone, Serialize)]
+pub struct SaveConflict {
+    pub path: String,
+    pub disk_content: String,
+    pub disk_version: String,
+    pub disk_modified: Option<u64>,
+    /// Merge of both versions with conflict markers, for manual resolution
+    pub merged: String,
+    pub hunks: Vec<ConflictHunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SaveOutcome {
+    Saved {
+        warnings: Vec<Diagnostic>,
+        version: String,
+    },
+    /// The file had changed on disk and the edits were merged into it
+    Merged {
+        warnings: Vec<Diagnostic>,
+        version: String,
+        content: String,
+    },
+    Conflict(SaveConflict),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
-pub enum SshHostGroup {
-    Direct,
-    Proxy,
-    Local,
-    Github,
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Half-open range of character columns within a line, 0-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A point in a file: 1-based line, 0-based character column. A line past
+/// the last one is the end of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TextPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Replace the text from `start` up to `end` with `new_text`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start: TextPosition,
+    pub end: TextPosition,
+    pub new_text: String,
+}
+
+/// Permission change a fix applies to a file outside the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChmodFix {
+    pub path: String,
+    pub mode: u32,
+}
+
+/// A machine-applicable fix for a diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedFix {
+    /// Describes the fix; formatted with the same `args` scheme as messages
+    pub message_key: String,
+    pub args: BTreeMap<String, String>,
+    /// `message_key` rendered with `args`
+    pub message: String,
+    pub edits: Vec<TextEdit>,
+    #[serde(default)]
+    pub chmod: Option<ChmodFix>,
+}
+
+/// A problem found in a config file. `message` is `message_key` rendered
+/// with `args`; the key and arguments are kept for callers that need the
+/// parts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// File the problem is in, when known
+    pub file: Option<String>,
+    /// 1-based line, or 0 for problems with the file as a whole
+    pub line: usize,
+    pub column_range: ColumnRange,
+    pub severity: Severity,
+    /// Stable identifier of the check, e.g. `unknown-keyword`
+    pub code: String,
+    pub message_key: String,
+    pub args: BTreeMap<String, String>,
+    /// `message_key` rendered with `args`, in Chinese
+    pub message: String,
+    pub suggested_fix: Option<SuggestedFix>,
+}
+
+/// OpenSSH releases the settings offer as validation targets
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenSshVersions {
+    /// Version of the local ssh client, if it could be determined
+    pub installed: Option<String>,
+    /// Releases that added or deprecated a keyword, newest first
+    pub known: Vec<String>,
+}
+
+/// A terminal emulator installed on this machine
+#[derive(Debug, Clone, Serialize)]
+pub struct TerminalInfo {
+    /// Value for the `default_terminal` setting
+    pub id: String,
+    pub name: String,
+}
+
+/// A lint rule and whether it is currently enabled
+#[derive(Debug, Clone, Serialize)]
+pub struct LintRuleInfo {
+    pub id: String,
+    pub enabled: bool,
+}
+
+/// Result of applying the suggested fixes to config content
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFixResult {
+    /// Patched content; not written to disk
+    pub content: String,
+    /// Unified diff from the original content
+    pub diff: String,
+    /// Diagnostics whose fixes were applied
+    pub applied: Vec<Diagnostic>,
 }
@@ -0,0 +1,537 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufReader;
+
+use ssh2_config::{ParseRule, SshConfig};
+
+use super::keywords::{find_keyword, Grammar, Version, KEYWORDS};
+use super::parser::{column_range, split_args, split_keyword};
+use super::pattern::match_pattern_list;
+use super::resolve::MULTI_VALUE_KEYWORDS;
+use super::types::{ColumnRange, Diagnostic, Severity, SuggestedFix, TextEdit, TextPosition};
+
+/// Message templates (Chinese) by message key. `{name}` is replaced by
+/// the diagnostic argument of that name.
+const MESSAGES: &[(&str, &str)] = &[
+    ("unknown_keyword", "未知指令 \"{keyword}\""),
+    ("missing_value", "\"{keyword}\" 缺少值"),
+    ("port_zero", "Port 不能为 0"),
+    (
+        "invalid_port",
+        "Port 值 \"{value}\" 不是有效端口号 (1-65535)",
+    ),
+    ("expected_integer", "{keyword} 值 \"{value}\" 应为正整数"),
+    ("expected_yes_no", "{keyword} 值 \"{value}\" 应为 yes 或 no"),
+    ("expected_one_of", "{keyword} \"{value}\" 应为 {allowed}"),
+    (
+        "expected_one_of_or_time",
+        "{keyword} \"{value}\" 应为 {allowed} 或时间间隔（如 30、10m）",
+    ),
+    (
+        "invalid_time_interval",
+        "{keyword} 值 \"{value}\" 不是有效的时间间隔（如 30、10m、1h30m）",
+    ),
+    (
+        "invalid_algorithm_list",
+        "{keyword} 值 \"{value}\" 应为逗号分隔的算法列表，可带 +、- 或 ^ 前缀",
+    ),
+    (
+        "invalid_forward",
+        "{keyword} \"{value}\" 应为 [地址:]端口 主机:端口",
+    ),
+    (
+        "invalid_dynamic_forward",
+        "DynamicForward \"{value}\" 应为 [地址:]端口",
+    ),
+    (
+        "keyword_requires_version",
+        "{keyword} 需要 OpenSSH {since} 及以上版本（目标版本 {target}）",
+    ),
+    (
+        "keyword_deprecated",
+        "{keyword} 自 OpenSSH {since} 起已弃用",
+    ),
+    (
+        "keyword_replaced",
+        "{keyword} 自 OpenSSH {since} 起已弃用，请改用 {replacement}",
+    ),
+    ("structure_error", "SSH 配置结构错误: {message}"),
+    ("ssh_client_error", "ssh: {message}"),
+    (
+        "ssh_client_unavailable",
+        "无法运行本机 ssh 客户端，已跳过 ssh -G 校验",
+    ),
+    (
+        "ssh_client_skipped_temp",
+        "无法写入临时配置文件，已跳过 ssh -G 校验",
+    ),
+    (
+        "ssh_client_skipped_exec",
+        "配置中有 Match exec，为避免执行其中的命令已跳过 ssh -G 校验",
+    ),
+    (
+        "duplicate_directive",
+        "{keyword} 已在本块第 {line} 行指定，此处不会生效",
+    ),
+    (
+        "shadowed_by_global",
+        "{keyword} 已在 {file} 第 {line} 行的全局设置中指定，此处不会生效",
+    ),
+    (
+        "duplicate_host_alias",
+        "主机 {alias} 已在 {file} 第 {line} 行定义",
+    ),
+    (
+        "wildcard_host_first",
+        "Host * 位于具体主机之前，其 {keywords} 会覆盖 {hosts} 的设置",
+    ),
+    (
+        "lint_root_skipped",
+        "无法加载 {root}，已跳过对它的检查: {message}",
+    ),
+    ("identity_file_missing", "密钥文件 {path} 不存在"),
+    (
+        "identity_file_permissions",
+        "密钥文件 {path} 的权限为 {mode}，其他用户可读，ssh 将拒绝使用（应为 600）",
+    ),
+    (
+        "proxyjump_undefined",
+        "ProxyJump 引用了未定义的主机 {alias}",
+    ),
+    ("proxyjump_cycle", "ProxyJump 形成循环: {cycle}"),
+    (
+        "local_forward_collision",
+        "LocalForward {listen} 与 {host}（{file} 第 {line} 行）监听同一端口",
+    ),
+    ("fix_replace_keyword", "改为 {keyword}"),
+    ("fix_replace_value", "改为 {value}"),
+    ("fix_remove_line", "删除此行"),
+    ("fix_move_to_end", "将 Host * 移到文件末尾"),
+    ("fix_chmod", "将 {path} 的权限改为 {mode}"),
+];
+
+/// Check config content line by line, then structurally. `file` is
+/// recorded on every diagnostic. Keywords newer than `target` are
+/// reported; without a target only deprecations are. Unknown keywords
+/// listed by an earlier `IgnoreUnknown` are not reported, as ssh doesn't
+/// reject them.
+pub fn validate_content(
+    content: &str,
+    file: Option<&str>,
+    target: Option<Version>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // Options of the current block, by key, with the line they are on
+    let mut block_options: HashMap<String, usize> = HashMap::new();
+    // Pattern lists of the `IgnoreUnknown` lines so far
+    let mut ignored_unknown: Vec<String> = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let Some((keyword, value)) = split_keyword(line) else {
+            continue;
+        };
+        let line_no = i + 1;
+        let at = |sub: &str| column_range(line, sub);
+        let mut report = |severity, code: &str, key: &str, range, args: &[(&str, &str)], fix| {
+            let args = to_args(args);
+            diagnostics.push(Diagnostic {
+                file: file.map(str::to_string),
+                line: line_no,
+                column_range: range,
+                severity,
+                code: code.to_string(),
+                message_key: key.to_string(),
+                message: format_message(key, &args),
+                args,
+                suggested_fix: fix,
+            })
+        };
+        let keyword_lower = keyword.to_lowercase();
+        let info = find_keyword(keyword);
+
+        match info {
+            None if ignored_unknown
+                .iter()
+                .any(|list| match_pattern_list(keyword, list, true)) => {}
+            None => {
+                let fix = closest_keyword(keyword).map(|suggestion| {
+                    replace_fix(
+                        line_no,
+                        at(keyword),
+                        suggestion,
+                        "fix_replace_keyword",
+                        &[("keyword", suggestion)],
+                    )
+                });
+                report(
+                    Severity::Warning,
+                    "unknown-keyword",
+                    "unknown_keyword",
+                    at(keyword),
+                    &[("keyword", keyword)],
+                    fix,
+                );
+            }
+            Some(info) => {
+                if let (Some(since), Some(target)) = (info.since, target) {
+                    if target < since {
+                        report(
+                            Severity::Warning,
+                            "unsupported-keyword",
+                            "keyword_requires_version",
+                            at(keyword),
+                            &[
+                                ("keyword", info.name),
+                                ("since", &since.to_string()),
+                                ("target", &target.to_string()),
+                            ],
+                            None,
+                        );
+                    }
+                }
+                if let Some(deprecation) = info.deprecated {
+                    if target.is_none_or(|t| t >= deprecation.since) {
+                        let since = deprecation.since.to_string();
+                        match deprecation.replacement {
+                            Some(replacement) => report(
+                                Severity::Warning,
+                                "deprecated-keyword",
+                                "keyword_replaced",
+                                at(keyword),
+                                &[
+                                    ("keyword", info.name),
+                                    ("since", &since),
+                                    ("replacement", replacement),
+                                ],
+                                Some(replace_fix(
+                                    line_no,
+                                    at(keyword),
+                                    replacement,
+                                    "fix_replace_keyword",
+                                    &[("keyword", replacement)],
+                                )),
+                            ),
+                            None => report(
+                                Severity::Warning,
+                                "deprecated-keyword",
+                                "keyword_deprecated",
+                                at(keyword),
+                                &[("keyword", info.name), ("since", &since)],
+                                None,
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+
+        if keyword_lower == "ignoreunknown" {
+            ignored_unknown.extend(split_args(value).into_iter().next());
+        }
+
+        if keyword_lower == "host" || keyword_lower == "match" {
+            block_options.clear();
+        } else if keyword_lower != "include" {
+            let key = if MULTI_VALUE_KEYWORDS.contains(&keyword_lower.as_str()) {
+                format!("{} {}", keyword_lower, value)
+            } else {
+                keyword_lower.clone()
+            };
+            if let Some(first) = block_options.get(&key) {
+                report(
+                    Severity::Warning,
+                    "duplicate-directive",
+                    "duplicate_directive",
+                    column_range(line, line.trim()),
+                    &[("keyword", keyword), ("line", &first.to_string())],
+                    Some(remove_line_fix(line_no)),
+                );
+            } else {
+                block_options.insert(key, line_no);
+            }
+        }
+
+        if value.is_empty() {
+            if keyword_lower != "match" {
+                report(
+                    Severity::Warning,
+                    "missing-value",
+                    "missing_value",
+                    at(keyword),
+                    &[("keyword", keyword)],
+                    None,
+                );
+            }
+            continue;
+        }
+
+        let Some(info) = info.filter(|k| !k.grammar.accepts(value)) else {
+            continue;
+        };
+        let value_range = at(value);
+        let args = [("keyword", keyword), ("value", value)];
+        let unquoted = split_args(value).join(" ");
+        // true/on/1 and the like become yes/no where those are accepted
+        let boolean_fix = normalized_boolean(&unquoted.to_lowercase())
+            .filter(|normalized| info.grammar.accepts(normalized))
+            .map(|normalized| {
+                replace_fix(
+                    line_no,
+                    value_range,
+                    normalized,
+                    "fix_replace_value",
+                    &[("value", normalized)],
+                )
+            });
+
+        match info.grammar {
+            Grammar::Text => {}
+            Grammar::YesNo => report(
+                Severity::Warning,
+                "invalid-boolean",
+                "expected_yes_no",
+                value_range,
+                &args,
+                boolean_fix,
+            ),
+            Grammar::Port => {
+                let key = if unquoted.parse::<u16>() == Ok(0) {
+                    "port_zero"
+                } else {
+                    "invalid_port"
+                };
+                report(
+                    Severity::Warning,
+                    "invalid-port",
+                    key,
+                    value_range,
+                    &args,
+                    None,
+                )
+            }
+            Grammar::Integer => report(
+                Severity::Warning,
+                "invalid-integer",
+                "expected_integer",
+                value_range,
+                &args,
+                None,
+            ),
+            Grammar::TimeInterval => report(
+                Severity::Warning,
+                "invalid-time",
+                "invalid_time_interval",
+                value_range,
+                &args,
+                None,
+            ),
+            Grammar::OneOf(allowed) | Grammar::OneOfOrTime(allowed) => {
+                let key = match info.grammar {
+                    Grammar::OneOf(_) => "expected_one_of",
+                    _ => "expected_one_of_or_time",
+                };
+                report(
+                    Severity::Warning,
+                    "invalid-value",
+                    key,
+                    value_range,
+                    &[
+                        ("keyword", keyword),
+                        ("value", value),
+                        ("allowed", &allowed.join("/")),
+                    ],
+                    boolean_fix,
+                )
+            }
+            Grammar::KeystrokeTiming => report(
+                Severity::Warning,
+                "invalid-value",
+                "expected_one_of",
+                value_range,
+                &[
+                    ("keyword", keyword),
+                    ("value", value),
+                    ("allowed", "yes/no/interval:毫秒数"),
+                ],
+                boolean_fix,
+            ),
+            Grammar::AlgorithmList => report(
+                Severity::Warning,
+                "invalid-algorithm-list",
+                "invalid_algorithm_list",
+                value_range,
+                &args,
+                None,
+            ),
+            Grammar::LocalForward | Grammar::RemoteForward => report(
+                Severity::Warning,
+                "invalid-forward",
+                "invalid_forward",
+                value_range,
+                &args,
+                None,
+            ),
+            Grammar::DynamicForward => report(
+                Severity::Warning,
+                "invalid-forward",
+                "invalid_dynamic_forward",
+                value_range,
+                &args,
+                None,
+            ),
+        }
+    }
+
+    // Also parse the Host/Match structure with ssh2-config. Values are
+    // left out: they were checked above, and ssh2-config rejects some that
+    // OpenSSH accepts (such as `10m` intervals).
+    let structure: String = content
+        .lines()
+        .filter(|line| {
+            split_keyword(line).is_some_and(|(k, _)| {
+                k.eq_ignore_ascii_case("host") || k.eq_ignore_ascii_case("match")
+            })
+        })
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let mut reader = BufReader::new(structure.as_bytes());
+    if let Err(e) = SshConfig::default().parse(&mut reader, ParseRule::ALLOW_UNKNOWN_FIELDS) {
+        let args = to_args(&[("message", &e.to_string())]);
+        diagnostics.push(Diagnostic {
+            file: file.map(str::to_string),
+            line: 0,
+            column_range: ColumnRange { start: 0, end: 0 },
+            severity: Severity::Error,
+            code: "parse-error".to_string(),
+            message_key: "structure_error".to_string(),
+            message: format_message("structure_error", &args),
+            args,
+            suggested_fix: None,
+        });
+    }
+
+    diagnostics
+}
+
+/// The message template for `key` with `{name}` replaced by `args[name]`
+pub fn format_message(key: &str, args: &BTreeMap<String, String>) -> String {
+    let template = MESSAGES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, t)| *t)
+        .unwrap_or(key);
+    let mut message = template.to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{{}}}", name), value);
+    }
+    message
+}
+
+/// Render a diagnostic as a single sentence, prefixed with its location
+pub fn format_diagnostic(diagnostic: &Diagnostic) -> String {
+    let message = &diagnostic.message;
+    let location = match (&diagnostic.file, diagnostic.line) {
+        (Some(file), 0) => format!("{}: ", file),
+        (Some(file), line) => format!("{} 第 {} 行: ", file, line),
+        (None, 0) => String::new(),
+        (None, line) => format!("第 {} 行: ", line),
+    };
+    format!("{}{}", location, message)
+}
+
+/// Whether any diagnostic would stop the config from loading
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+/// Current keyword closest to a misspelled one, if it is within two edits
+fn closest_keyword(keyword: &str) -> Option<&'static str> {
+    let lower = keyword.to_lowercase();
+    KEYWORDS
+        .iter()
+        .filter(|k| k.deprecated.is_none())
+        .map(|k| (edit_distance(&lower, &k.name.to_lowercase()), k.name))
+        .filter(|(d, _)| *d <= 2 && *d < lower.chars().count())
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between two strings, by characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// yes/no for the boolean spellings other tools accept
+fn normalized_boolean(value: &str) -> Option<&'static str> {
+    match value {
+        "true" | "on" | "1" => Some("yes"),
+        "false" | "off" | "0" => Some("no"),
+        _ => None,
+    }
+}
+
+/// Fix replacing `range` of line `line` with `new_text`
+fn replace_fix(
+    line: usize,
+    range: ColumnRange,
+    new_text: &str,
+    message_key: &str,
+    args: &[(&str, &str)],
+) -> SuggestedFix {
+    let args = to_args(args);
+    SuggestedFix {
+        message_key: message_key.to_string(),
+        message: format_message(message_key, &args),
+        args,
+        edits: vec![TextEdit {
+            start: TextPosition {
+                line,
+                column: range.start,
+            },
+            end: TextPosition {
+                line,
+                column: range.end,
+            },
+            new_text: new_text.to_string(),
+        }],
+        chmod: None,
+    }
+}
+
+/// Fix deleting line `line` together with its line ending
+fn remove_line_fix(line: usize) -> SuggestedFix {
+    SuggestedFix {
+        message_key: "fix_remove_line".to_string(),
+        args: BTreeMap::new(),
+        message: format_message("fix_remove_line", &BTreeMap::new()),
+        edits: vec![TextEdit {
+            start: TextPosition { line, column: 0 },
+            end: TextPosition {
+                line: line + 1,
+                column: 0,
+            },
+            new_text: String::new(),
+        }],
+        chmod: None,
+    }
+}
+
+fn to_args(args: &[(&str, &str)]) -> BTreeMap<String, String> {
+    args.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
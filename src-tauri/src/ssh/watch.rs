@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use super::config::{config_roots, load_user_configs, system_config_path};
+use crate::settings::settings_path;
+
+/// Quiet period after the last change before the configs are re-read
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Every file the host list is built from: the config roots, the system
+/// config and everything they include, plus the settings file that lists
+/// the roots. Symlinked files are listed under both names so edits to the
+/// link target are seen too.
+pub fn watched_files() -> HashSet<PathBuf> {
+    let roots = load_user_configs().unwrap_or_default();
+    let mut files: Vec<PathBuf> = roots
+        .iter()
+        .flat_map(|(_, configs)| configs)
+        .flat_map(|c| std::iter::once(c.path.clone()).chain(c.included_files()))
+        .collect();
+    files.extend(config_roots());
+    files.push(system_config_path());
+    files.push(settings_path());
+
+    let targets: Vec<PathBuf> = files.iter().filter_map(|f| f.canonicalize().ok()).collect();
+    files.into_iter().chain(targets).collect()
+}
+
+/// Directories to watch for `files`. Parents are watched rather than the
+/// files themselves because editors (and our own saves) replace files by
+/// renaming, which drops a watch placed on the old file.
+fn watched_dirs(files: &HashSet<PathBuf>) -> HashSet<PathBuf> {
+    files
+        .iter()
+        .filter_map(|f| f.parent())
+        .filter(|d| d.is_dir())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Watch the config files in the background and call `on_change` once a
+/// burst of changes to any of them has settled. The set of files is
+/// recomputed after each change, so adding or removing an `Include`
+/// starts or stops watching the files it pulls in.
+pub fn spawn_config_watcher<F>(on_change: F) -> Result<(), String>
+where
+    F: Fn() + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let mut files = watched_files();
+    let mut dirs = watched_dirs(&files);
+    for dir in &dirs {
+        let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+    }
+
+    thread::spawn(move || {
+        // The watcher lives as long as this thread
+        while let Ok(first) = rx.recv() {
+            let mut changed: Vec<PathBuf> = event_paths(first);
+            while let Ok(next) = rx.recv_timeout(DEBOUNCE) {
+                changed.extend(event_paths(next));
+            }
+
+            let new_files = watched_files();
+            let relevant = changed
+                .iter()
+                .any(|p| files.contains(p) || new_files.contains(p));
+            if !relevant {
+                continue;
+            }
+
+            let new_dirs = watched_dirs(&new_files);
+            for dir in dirs.difference(&new_dirs) {
+                let _ = watcher.unwatch(dir);
+            }
+            for dir in new_dirs.difference(&dirs) {
+                let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+            }
+            files = new_files;
+            dirs = new_dirs;
+
+            on_change();
+        }
+    });
+
+    Ok(())
+}
+
+/// Paths an event changed. Access events are dropped: re-reading the
+/// configs after a change would otherwise trigger another round.
+fn event_paths(event: notify::Result<Event>) -> Vec<PathBuf> {
+    match event {
+        Ok(e) if !matches!(e.kind, EventKind::Access(_)) => e.paths,
+        _ => Vec::new(),
+    }
+}
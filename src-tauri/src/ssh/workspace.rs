@@ -0,0 +1,78 @@
+use std::process::{Command, Stdio};
+
+use super::launch::SshLaunch;
+use super::terminal::{open_in_terminal, open_tabs_in_terminal};
+use super::types::{Workspace, WorkspaceLayout};
+
+/// Check workspaces before they are saved: unique names and hosts that
+/// can be launched
+pub fn validate_workspaces(workspaces: &[Workspace]) -> Result<(), String> {
+    for (i, workspace) in workspaces.iter().enumerate() {
+        let name = workspace.name.trim();
+        if name.is_empty() {
+            return Err(format!("Workspace {}: name is empty", i + 1));
+        }
+        if workspaces[..i].iter().any(|w| w.name.trim() == name) {
+            return Err(format!("Workspace \"{}\" is defined twice", name));
+        }
+        if workspace.hosts.is_empty() {
+            return Err(format!("Workspace \"{}\" has no hosts", name));
+        }
+        for host in &workspace.hosts {
+            SshLaunch::for_target(host).map_err(|e| format!("Workspace \"{}\": {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Open every host of `workspace` in `terminal`. A tmux workspace that is
+/// still running is attached to rather than started again.
+pub fn open_workspace(workspace: &Workspace, terminal: &str) -> Result<(), String> {
+    let launches = workspace
+        .hosts
+        .iter()
+        .map(|host| SshLaunch::for_target(host))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match workspace.layout {
+        WorkspaceLayout::Tabs => open_tabs_in_terminal(terminal, &launches),
+        WorkspaceLayout::Tmux => {
+            if which::which("tmux").is_err() {
+                return Err("tmux is not installed".to_string());
+            }
+            let session = tmux_session_name(&workspace.name);
+            let launch = if tmux_session_exists(&session) {
+                SshLaunch::tmux_attach(&session)?
+            } else {
+                SshLaunch::tmux_session(&session, &launches, workspace.synchronize_panes)?
+            };
+            open_in_terminal(terminal, &launch)
+        }
+    }
+}
+
+/// tmux session of the workspace called `name`: `ssh-m-` followed by the
+/// name with anything but letters, digits, `_` and `-` replaced
+fn tmux_session_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("ssh-m-{}", name)
+}
+
+fn tmux_session_exists(session: &str) -> bool {
+    Command::new("tmux")
+        .args(["has-session", "-t", &format!("={}", session)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
@@ -5,9 +5,19 @@ use tauri::{
     AppHandle, Manager, Wry,
 };
 
+use crate::commands::ssh::{open_ssh_terminal, open_workspace};
+use crate::history;
 use crate::settings;
 use crate::ssh::config::parse_ssh_config;
-use crate::ssh::types::SshHostGroup;
+use crate::ssh::profile::profile_applies;
+use crate::ssh::types::{LaunchProfile, SshHost};
+
+/// Recent hosts listed at the top of the tray menu
+const TRAY_RECENT_HOSTS: usize = 5;
+
+/// Menu sections a host can be listed in. A host may appear in several,
+/// and menu ids must be unique, so its item ids carry the section.
+const HOST_SECTIONS: [&str; 4] = ["favorites", "recent", "group", "all"];
 
 /// Build and attach the system tray icon with SSH host menu.
 /// Called once during app setup.
@@ -37,14 +47,20 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                     let _ = w.set_focus();
                 }
             } else if id == "refresh" {
-                if let Ok(menu) = build_tray_menu(app) {
-                    if let Some(tray) = app.tray_by_id("ssh-m-tray") {
-                        let _ = tray.set_menu(Some(menu));
-                    }
+                refresh_tray_menu(app);
+            } else if let Some(index) = id.strip_prefix("workspace:") {
+                let name = index.parse::<usize>().ok().and_then(|i| {
+                    settings::load_settings()
+                        .workspaces
+                        .get(i)
+                        .map(|w| w.name.clone())
+                });
+                if let Some(name) = name {
+                    // The tray has nowhere to show an error
+                    let _ = open_workspace(app.clone(), name, None);
                 }
-            } else if id.starts_with("ssh:") {
-                let host_name = &id[4..];
-                let _ = open_ssh_from_tray(host_name);
+            } else if let Some((host_name, profile)) = parse_host_item_id(id) {
+                let _ = open_ssh_terminal(app.clone(), host_name.to_string(), None, profile);
             }
         })
         .build(app)?;
@@ -52,55 +68,99 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Rebuild the tray menu from the current SSH config
+pub fn refresh_tray_menu(app: &AppHandle) {
+    if let Ok(menu) = build_tray_menu(app) {
+        if let Some(tray) = app.tray_by_id("ssh-m-tray") {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+}
+
 /// Build the tray context menu with SSH hosts grouped by category.
 fn build_tray_menu(
     app: &AppHandle,
 ) -> Result<tauri::menu::Menu<tauri::Wry>, Box<dyn std::error::Error>> {
     let hosts = parse_ssh_config().unwrap_or_default();
 
-    // Group hosts
-    let mut direct: Vec<_> = Vec::new();
-    let mut proxy: Vec<_> = Vec::new();
-    let mut local: Vec<_> = Vec::new();
-    let mut github: Vec<_> = Vec::new();
-
+    // Group hosts, in the order the groups first appear
+    let mut groups: Vec<(&str, Vec<&SshHost>)> = Vec::new();
     for h in &hosts {
-        match h.group {
-            SshHostGroup::Direct => direct.push(h),
-            SshHostGroup::Proxy => proxy.push(h),
-            SshHostGroup::Local => local.push(h),
-            SshHostGroup::Github => github.push(h),
+        match groups.iter_mut().find(|(name, _)| *name == h.group) {
+            Some((_, group_hosts)) => group_hosts.push(h),
+            None => groups.push((&h.group, vec![h])),
         }
     }
 
+    let settings = settings::load_settings();
+    let profiles = settings.launch_profiles;
     let mut menu_builder = MenuBuilder::new(app);
 
     // Title
     menu_builder = menu_builder.text("title", format!("SSH-M ({} 台主机)", hosts.len()));
     menu_builder = menu_builder.separator();
 
-    // Helper to add a group submenu
-    let groups: Vec<(&str, &str, &Vec<&crate::ssh::types::SshHost>)> = vec![
-        ("direct", "直连", &direct),
-        ("proxy", "代理跳板", &proxy),
-        ("local", "本地/隧道", &local),
-        ("github", "GitHub", &github),
+    // Favorites and recent connections first
+    let history = history::load_history();
+    let favorites = history.favorites(&hosts);
+    let mut recent = history.recent(&hosts);
+    recent.truncate(TRAY_RECENT_HOSTS);
+    let sections = [
+        ("favorites", "★ 收藏", favorites),
+        ("recent", "最近连接", recent),
     ];
-
-    for (_, label, group_hosts) in &groups {
-        if group_hosts.is_empty() {
+    for (id, label, entries) in sections {
+        if entries.is_empty() {
             continue;
         }
+        menu_builder = menu_builder.text(format!("section:{}", id), label);
+        for entry in &entries {
+            if let Some(host) = hosts.iter().find(|h| h.name == entry.alias) {
+                menu_builder = menu_builder.text(host_item_id(id, &host.name), host_detail(host));
+            }
+        }
+        menu_builder = menu_builder.separator();
+    }
 
-        let mut submenu = SubmenuBuilder::new(app, format!("{} ({})", label, group_hosts.len()));
+    let workspaces = settings.workspaces;
+    if !workspaces.is_empty() {
+        let mut submenu = SubmenuBuilder::new(app, format!("工作区 ({})", workspaces.len()));
+        for (index, workspace) in workspaces.iter().enumerate() {
+            submenu = submenu.text(
+                format!("workspace:{}", index),
+                format!("{}  ({} 台主机)", workspace.name, workspace.hosts.len()),
+            );
+        }
+        menu_builder = menu_builder.item(&submenu.build()?);
+        menu_builder = menu_builder.separator();
+    }
 
-        for host in *group_hosts {
-            let detail = if host.user.is_empty() || host.user == "root" {
-                format!("{}  →  {}", host.name, host.hostname)
-            } else {
-                format!("{}  →  {}@{}", host.name, host.user, host.hostname)
-            };
-            submenu = submenu.text(format!("ssh:{}", host.name), detail);
+    for (name, group_hosts) in &groups {
+        let mut submenu = SubmenuBuilder::new(
+            app,
+            format!("{} ({})", group_label(name), group_hosts.len()),
+        );
+
+        for host in group_hosts {
+            let host_profiles: Vec<(usize, &LaunchProfile)> = profiles
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| profile_applies(p, &host.name, Some(&host.group)))
+                .collect();
+            if host_profiles.is_empty() {
+                submenu = submenu.text(host_item_id("group", &host.name), host_detail(host));
+                continue;
+            }
+            // Hosts with launch profiles get a submenu of their own
+            let mut host_menu = SubmenuBuilder::new(app, host_detail(host))
+                .text(host_item_id("group", &host.name), "连接");
+            for (index, profile) in host_profiles {
+                host_menu = host_menu.text(
+                    format!("group:ssh-profile:{}:{}", index, host.name),
+                    &profile.name,
+                );
+            }
+            submenu = submenu.item(&host_menu.build()?);
         }
 
         menu_builder = menu_builder.item(&submenu.build()?);
@@ -115,7 +175,7 @@ fn build_tray_menu(
             } else {
                 format!("{} ({}@{})", host.name, host.user, host.hostname)
             };
-            menu_builder = menu_builder.text(format!("ssh:{}", host.name), detail);
+            menu_builder = menu_builder.text(host_item_id("all", &host.name), detail);
         }
     }
 
@@ -129,90 +189,49 @@ fn build_tray_menu(
     Ok(menu_builder.build()?)
 }
 
-/// Open an SSH connection using the user-configured terminal.
-fn open_ssh_from_tray(host: &str) -> Result<(), String> {
-    let terminal = settings::load_settings().default_terminal;
-
-    #[cfg(target_os = "macos")]
-    {
-        if terminal == "warp" {
-            // Warp doesn't support AppleScript `do script`, and using
-            // System Events keystroke requires Accessibility permissions.
-            // Use a self-deleting .command file instead.
-            let tmp_path = format!("/tmp/ssh-m-connect-{}.command", std::process::id());
-            let script_content = format!("#!/bin/bash\nexec ssh {}\n", host);
-            std::fs::write(&tmp_path, &script_content)
-                .map_err(|e| format!("Failed to create temp script: {}", e))?;
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o755))
-                    .map_err(|e| format!("Failed to set script permissions: {}", e))?;
-            }
-            std::process::Command::new("open")
-                .args(["-a", "Warp", &tmp_path])
-                .spawn()
-                .map_err(|e| format!("Failed to open Warp: {}", e))?;
-        } else {
-            let script = match terminal.as_str() {
-                "iterm" => format!(
-                    r#"
-                    tell application "iTerm"
-                        activate
-                        create window with default profile command "ssh {}"
-                    end tell
-                    "#,
-                    host
-                ),
-                _ => format!(
-                    r#"
-                    tell application "Terminal"
-                        activate
-                        do script "ssh {}"
-                    end tell
-                    "#,
-                    host
-                ),
-            };
-
-            std::process::Command::new("osascript")
-                .arg("-e")
-                .arg(&script)
-                .spawn()
-                .map_err(|e| format!("Failed to open terminal: {}", e))?;
-        }
+/// Menu label of a host: its alias and where it connects to
+fn host_detail(host: &SshHost) -> String {
+    if host.user.is_empty() || host.user == "root" {
+        format!("{}  →  {}", host.name, host.hostname)
+    } else {
+        format!("{}  →  {}@{}", host.name, host.user, host.hostname)
     }
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        let _ = &terminal; // suppress unused warning
-        let terminals = ["gnome-terminal", "konsole", "xterm"];
-        let mut launched = false;
-        for term in &terminals {
-            if std::process::Command::new(term)
-                .args(["--", "ssh", host])
-                .spawn()
-                .is_ok()
-            {
-                launched = true;
-                break;
-            }
-        }
-        if !launched {
-            return Err("No terminal emulator found".to_string());
-        }
+/// Menu label of a group; groups from custom rules are shown by name
+fn group_label(group: &str) -> &str {
+    match group {
+        "direct" => "直连",
+        "proxy" => "代理跳板",
+        "local" => "本地/隧道",
+        "git" => "代码托管",
+        other => other,
     }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        let _ = &terminal; // suppress unused warning
-        std::process::Command::new("cmd")
-            .args(["/c", "start", "ssh", host])
-            .spawn()
-            .map_err(|e| format!("Failed to open terminal: {}", e))?;
-    }
+/// Menu item id connecting plainly to `alias`, listed in `section`
+fn host_item_id(section: &str, alias: &str) -> String {
+    format!("{}:ssh:{}", section, alias)
+}
 
-    Ok(())
+/// Host and launch profile of a menu item id: `<section>:ssh:<alias>`
+/// connects plainly, `<section>:ssh-profile:<index>:<alias>` with the
+/// profile at `index` in the settings
+fn parse_host_item_id(id: &str) -> Option<(&str, Option<String>)> {
+    let (section, id) = id.split_once(':')?;
+    if !HOST_SECTIONS.contains(&section) {
+        return None;
+    }
+    if let Some(alias) = id.strip_prefix("ssh:") {
+        return Some((alias, None));
+    }
+    let (index, alias) = id.strip_prefix("ssh-profile:")?.split_once(':')?;
+    let index: usize = index.parse().ok()?;
+    let profile = settings::load_settings()
+        .launch_profiles
+        .get(index)
+        .map(|p| p.name.clone())?;
+    Some((alias, Some(profile)))
 }
 
 /// Load a small PNG icon for the tray.
@@ -9,15 +9,24 @@ import {
   FolderOpen,
   File,
   ChevronRight,
+  History,
+  XCircle,
+  Info,
+  Wand2,
 } from "lucide-react";
 import { clsx } from "clsx";
 import {
   readSshConfig,
   saveSshConfig,
   validateSshConfig,
+  applyConfigFixes,
+  fixKeyFilePermissions,
   listSshConfigFiles,
   type SshConfigFile,
 } from "../../services/tauri";
+import type { ConfigFixResult, Diagnostic, SaveConflict, Severity } from "../../types/ssh";
+import { SshConfigHistory } from "./SshConfigHistory";
+import { formatDiagnostic, sortDiagnostics } from "./diagnostics";
 
 interface SshConfigEditorProps {
   open: boolean;
@@ -25,10 +34,88 @@ interface SshConfigEditorProps {
   onSaved: () => void;
 }
 
+const SEVERITY_TEXT: Record<Severity, string> = {
+  error: "text-danger",
+  warning: "text-warning",
+  info: "text-text-dim",
+};
+
+const SEVERITY_UNDERLINE: Record<Severity, string> = {
+  error: "decoration-danger",
+  warning: "decoration-warning",
+  info: "decoration-text-dim",
+};
+
+const SEVERITY_ICON: Record<Severity, typeof AlertTriangle> = {
+  error: XCircle,
+  warning: AlertTriangle,
+  info: Info,
+};
+
+// Renders `content` invisibly with wavy underlines under diagnostic ranges;
+// laid over the textarea, which must use the same font metrics and padding
+function DiagnosticOverlay({
+  content,
+  diagnostics,
+  overlayRef,
+}: {
+  content: string;
+  diagnostics: Diagnostic[];
+  overlayRef: React.RefObject<HTMLDivElement | null>;
+}) {
+  const byLine = new Map<number, Diagnostic[]>();
+  for (const d of diagnostics) {
+    if (d.line > 0) byLine.set(d.line, [...(byLine.get(d.line) ?? []), d]);
+  }
+  return (
+    <div
+      ref={overlayRef}
+      aria-hidden
+      className="absolute inset-0 pointer-events-none overflow-hidden text-sm font-mono p-3 leading-[1.625rem] whitespace-pre-wrap break-words text-transparent"
+      style={{ tabSize: 4, scrollbarGutter: "stable" }}
+    >
+      {content.split("\n").map((line, i) => {
+        const marks = sortDiagnostics(byLine.get(i + 1) ?? []);
+        if (marks.length === 0) return <div key={i}>{line || " "}</div>;
+        // Mark each column with the most severe diagnostic covering it
+        const chars = [...line];
+        const severityAt = chars.map(
+          (_, col) =>
+            marks.find((d) => d.column_range.start <= col && col < d.column_range.end)?.severity,
+        );
+        const parts: { text: string; severity?: Severity }[] = [];
+        chars.forEach((ch, col) => {
+          const last = parts[parts.length - 1];
+          if (last && last.severity === severityAt[col]) last.text += ch;
+          else parts.push({ text: ch, severity: severityAt[col] });
+        });
+        return (
+          <div key={i}>
+            {parts.map((p, j) => (
+              <span
+                key={j}
+                className={clsx(
+                  p.severity && "underline decoration-wavy underline-offset-4",
+                  p.severity && SEVERITY_UNDERLINE[p.severity],
+                )}
+              >
+                {p.text}
+              </span>
+            ))}
+            {line === "" && " "}
+          </div>
+        );
+      })}
+    </div>
+  );
+}
+
 interface FileTab {
   file: SshConfigFile;
   content: string;
   original: string;
+  // Version of `original` on disk, for conflict detection on save
+  version: string;
   dirty: boolean;
 }
 
@@ -40,12 +127,22 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
   const [loading, setLoading] = useState(false);
   const [saving, setSaving] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const [warnings, setWarnings] = useState<string[]>([]);
+  const [warnings, setWarnings] = useState<Diagnostic[]>([]);
+  // Content the current diagnostics were computed for
+  const [checkedContent, setCheckedContent] = useState<string | null>(null);
   const [saveSuccess, setSaveSuccess] = useState(false);
   const [validateSuccess, setValidateSuccess] = useState(false);
+  const [historyOpen, setHistoryOpen] = useState(false);
+  const [conflict, setConflict] = useState<SaveConflict | null>(null);
+  const [mergedNotice, setMergedNotice] = useState(false);
+  const [fixResult, setFixResult] = useState<ConfigFixResult | null>(null);
+  // Key files whose permissions were just restricted
+  const [chmodded, setChmodded] = useState<string[] | null>(null);
+  const [fixing, setFixing] = useState(false);
 
   const textareaRef = useRef<HTMLTextAreaElement>(null);
   const lineNumbersRef = useRef<HTMLDivElement>(null);
+  const overlayRef = useRef<HTMLDivElement>(null);
 
   const activeTab = tabs.get(activeFilePath);
   const content = activeTab?.content ?? "";
@@ -83,6 +180,10 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
     if (!textarea || !lineNumbers) return;
     const onScroll = () => {
       lineNumbers.scrollTop = textarea.scrollTop;
+      if (overlayRef.current) {
+        overlayRef.current.scrollTop = textarea.scrollTop;
+        overlayRef.current.scrollLeft = textarea.scrollLeft;
+      }
     };
     textarea.addEventListener("scroll", onScroll);
     return () => textarea.removeEventListener("scroll", onScroll);
@@ -90,11 +191,14 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
 
   const loadFile = useCallback(async (file: SshConfigFile) => {
     setError(null);
+    setConflict(null);
     setWarnings([]);
+    setFixResult(null);
+    setChmodded(null);
     setSaveSuccess(false);
     setValidateSuccess(false);
     try {
-      const text = await readSshConfig(file.path);
+      const snapshot = await readSshConfig(file.path);
       setTabs((prev) => {
         const next = new Map(prev);
         // Don't reload if already loaded and dirty
@@ -103,8 +207,9 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
         }
         next.set(file.path, {
           file,
-          content: text,
-          original: text,
+          content: snapshot.content,
+          original: snapshot.content,
+          version: snapshot.version,
           dirty: false,
         });
         return next;
@@ -119,7 +224,10 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
     if (tabs.has(file.path)) {
       setActiveFilePath(file.path);
       setError(null);
+      setConflict(null);
       setWarnings([]);
+      setFixResult(null);
+      setChmodded(null);
       setSaveSuccess(false);
       setValidateSuccess(false);
     } else {
@@ -158,10 +266,13 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
   const handleValidate = useCallback(async () => {
     setError(null);
     setWarnings([]);
+    setFixResult(null);
+    setChmodded(null);
     setValidateSuccess(false);
     try {
-      const w = await validateSshConfig(content);
+      const w = await validateSshConfig(content, activeFilePath || undefined);
       setWarnings(w);
+      setCheckedContent(content);
       if (w.length === 0) {
         setValidateSuccess(true);
         setTimeout(() => setValidateSuccess(false), 5000);
@@ -171,26 +282,112 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
       setError(String(e));
       return false;
     }
-  }, [content]);
+  }, [content, activeFilePath]);
+
+  // Apply suggested fixes (only for `codes` when given) to the editor
+  // content, then re-check the result
+  const handleFix = useCallback(async (codes?: string[]) => {
+    if (fixing) return;
+    setFixing(true);
+    setError(null);
+    setFixResult(null);
+    setChmodded(null);
+    setValidateSuccess(false);
+    try {
+      const path = activeFilePath || undefined;
+      const result = await applyConfigFixes(content, path, codes);
+      setFixResult(result);
+      if (result.content !== content) {
+        updateContent(result.content);
+      }
+      setWarnings(await validateSshConfig(result.content, path));
+      setCheckedContent(result.content);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setFixing(false);
+    }
+  }, [content, activeFilePath, fixing, updateContent]);
 
-  const handleSave = useCallback(async () => {
-    if (saving || !isDirty) return;
+  // Restrict the permissions of one key file the checks offered to fix
+  const handleChmod = useCallback(async (file: string) => {
+    if (fixing) return;
+    setFixing(true);
+    setError(null);
+    setFixResult(null);
+    setChmodded(null);
+    setValidateSuccess(false);
+    try {
+      const path = activeFilePath || undefined;
+      setChmodded(await fixKeyFilePermissions(content, path, [file]));
+      setWarnings(await validateSshConfig(content, path));
+      setCheckedContent(content);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setFixing(false);
+    }
+  }, [content, activeFilePath, fixing]);
+
+  // Put the cursor on a diagnostic's range
+  const revealDiagnostic = useCallback((d: Diagnostic) => {
+    const textarea = textareaRef.current;
+    if (!textarea || d.line === 0 || (d.file && d.file !== activeFilePath)) return;
+    const lines = content.split("\n");
+    const lineStart = lines.slice(0, d.line - 1).reduce((n, l) => n + l.length + 1, 0);
+    // Columns count characters; convert to UTF-16 offsets for the textarea
+    const line = [...(lines[d.line - 1] ?? "")];
+    const offset = (col: number) => line.slice(0, col).join("").length;
+    textarea.focus();
+    textarea.setSelectionRange(
+      lineStart + offset(d.column_range.start),
+      lineStart + offset(d.column_range.end),
+    );
+  }, [content, activeFilePath]);
+
+  const markSaved = useCallback((saved: string, version: string) => {
+    setTabs((prev) => {
+      const next = new Map(prev);
+      const tab = next.get(activeFilePath);
+      if (tab) {
+        next.set(activeFilePath, { ...tab, content: saved, original: saved, version, dirty: false });
+      }
+      return next;
+    });
+  }, [activeFilePath]);
+
+  const handleSave = useCallback(async (force = false) => {
+    if (saving || !isDirty || !activeTab) return;
     setSaving(true);
     setError(null);
     setWarnings([]);
     setSaveSuccess(false);
+    setMergedNotice(false);
+    setFixResult(null);
+    setChmodded(null);
     try {
-      const w = await saveSshConfig(content, activeFilePath || undefined);
-      setWarnings(w);
-      // Mark saved
-      setTabs((prev) => {
-        const next = new Map(prev);
-        const tab = next.get(activeFilePath);
-        if (tab) {
-          next.set(activeFilePath, { ...tab, original: content, dirty: false });
-        }
-        return next;
-      });
+      const outcome = force
+        ? await saveSshConfig(content, activeFilePath || undefined)
+        : await saveSshConfig(
+            content,
+            activeFilePath || undefined,
+            activeTab.version,
+            activeTab.original,
+          );
+      if (outcome.status === "conflict") {
+        setConflict(outcome);
+        return;
+      }
+      setConflict(null);
+      setWarnings(outcome.warnings);
+      if (outcome.status === "merged") {
+        markSaved(outcome.content, outcome.version);
+        setCheckedContent(outcome.content);
+        setMergedNotice(true);
+      } else {
+        markSaved(content, outcome.version);
+        setCheckedContent(content);
+      }
       setSaveSuccess(true);
       onSaved();
       setTimeout(() => setSaveSuccess(false), 3000);
@@ -199,7 +396,46 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
     } finally {
       setSaving(false);
     }
-  }, [content, activeFilePath, saving, isDirty, onSaved]);
+  }, [content, activeFilePath, activeTab, saving, isDirty, onSaved, markSaved]);
+
+  // Conflict resolution: keep the disk version and drop local edits
+  const takeDiskVersion = useCallback(() => {
+    if (!conflict) return;
+    markSaved(conflict.disk_content, conflict.disk_version);
+    setConflict(null);
+  }, [conflict, markSaved]);
+
+  // Conflict resolution: edit the marked-up merge, based on the disk version
+  const resolveManually = useCallback(() => {
+    if (!conflict) return;
+    setTabs((prev) => {
+      const next = new Map(prev);
+      const tab = next.get(activeFilePath);
+      if (tab) {
+        next.set(activeFilePath, {
+          ...tab,
+          content: conflict.merged,
+          original: conflict.disk_content,
+          version: conflict.disk_version,
+          dirty: true,
+        });
+      }
+      return next;
+    });
+    setConflict(null);
+  }, [conflict, activeFilePath]);
+
+  const handleRestored = useCallback(async () => {
+    if (!activeTab) return;
+    // Drop the stale tab so the restored content is read back
+    setTabs((prev) => {
+      const next = new Map(prev);
+      next.delete(activeTab.file.path);
+      return next;
+    });
+    await loadFile(activeTab.file);
+    onSaved();
+  }, [activeTab, loadFile, onSaved]);
 
   const handleClose = () => {
     if (anyDirty) {
@@ -211,6 +447,11 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
   if (!open) return null;
 
   const activeFileName = activeTab?.file.name ?? "";
+  // Ranges are only meaningful for the content they were computed on
+  const shownDiagnostics =
+    checkedContent === content
+      ? warnings.filter((d) => !d.file || d.file === activeFilePath)
+      : [];
 
   return (
     <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/60 backdrop-blur-sm">
@@ -233,6 +474,15 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
             )}
           </div>
           <div className="flex items-center gap-2">
+            <button
+              onClick={() => setHistoryOpen(true)}
+              disabled={!activeTab || isDirty}
+              title={isDirty ? "请先保存或撤销修改" : undefined}
+              className="flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium bg-surface-light border border-border hover:bg-surface-lighter transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+            >
+              <History className="w-3.5 h-3.5" />
+              历史
+            </button>
             <button
               onClick={handleValidate}
               className="flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium bg-surface-light border border-border hover:bg-surface-lighter transition-colors"
@@ -241,7 +491,20 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
               校验
             </button>
             <button
-              onClick={handleSave}
+              onClick={() => handleFix()}
+              disabled={!activeTab || fixing}
+              title="自动修复可修复的问题（修改编辑器内容，不会保存）"
+              className="flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium bg-surface-light border border-border hover:bg-surface-lighter transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+            >
+              {fixing ? (
+                <Loader2 className="w-3.5 h-3.5 animate-spin" />
+              ) : (
+                <Wand2 className="w-3.5 h-3.5" />
+              )}
+              自动修复
+            </button>
+            <button
+              onClick={() => handleSave()}
               disabled={saving || !isDirty}
               className={clsx(
                 "flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium transition-all",
@@ -267,7 +530,43 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
         </div>
 
         {/* Status bar */}
-        {(error || warnings.length > 0 || saveSuccess || validateSuccess) && (
+        {conflict && (
+          <div className="px-5 py-2 border-b border-border shrink-0 bg-warning/10 space-y-2">
+            <div className="flex items-start gap-2 text-warning text-xs">
+              <AlertTriangle className="w-3.5 h-3.5 shrink-0 mt-0.5" />
+              <span>
+                文件在打开后已被其他程序修改，有 {conflict.hunks.length} 处修改无法自动合并
+                {conflict.hunks.length > 0 &&
+                  `（第 ${conflict.hunks.map((h) => h.line).join("、")} 行）`}
+              </span>
+            </div>
+            <div className="flex gap-2">
+              <button
+                onClick={resolveManually}
+                className="px-2.5 py-1 rounded-md text-xs bg-primary text-white hover:bg-primary-dark transition-colors"
+              >
+                手动合并
+              </button>
+              <button
+                onClick={takeDiskVersion}
+                className="px-2.5 py-1 rounded-md text-xs bg-surface-light border border-border hover:bg-surface-lighter transition-colors"
+              >
+                使用磁盘版本
+              </button>
+              <button
+                onClick={() => {
+                  setConflict(null);
+                  handleSave(true);
+                }}
+                className="px-2.5 py-1 rounded-md text-xs text-danger bg-surface-light border border-border hover:bg-surface-lighter transition-colors"
+              >
+                覆盖磁盘版本
+              </button>
+            </div>
+          </div>
+        )}
+
+        {(error || warnings.length > 0 || saveSuccess || validateSuccess || fixResult || chmodded) && (
           <div className="px-5 py-2 border-b border-border shrink-0 space-y-1">
             {error && (
               <div className="flex items-start gap-2 text-danger text-xs">
@@ -275,12 +574,61 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
                 <span>{error}</span>
               </div>
             )}
-            {warnings.map((w, i) => (
-              <div key={i} className="flex items-start gap-2 text-warning text-xs">
-                <AlertTriangle className="w-3.5 h-3.5 shrink-0 mt-0.5" />
-                <span>{w}</span>
+            {fixResult && (
+              <div className="text-xs text-success">
+                <div className="flex items-center gap-2">
+                  <Wand2 className="w-3.5 h-3.5" />
+                  <span>
+                    {fixResult.applied.length > 0
+                      ? `已应用 ${fixResult.applied.length} 项修复${fixResult.diff ? "，请检查后保存" : ""}`
+                      : "没有可自动修复的问题"}
+                  </span>
+                </div>
+                {fixResult.diff && (
+                  <details className="mt-1 text-text-dim">
+                    <summary className="cursor-pointer">查看改动</summary>
+                    <pre className="mt-1 max-h-40 overflow-auto rounded bg-surface-light p-2 font-mono text-[11px] leading-4">
+                      {fixResult.diff}
+                    </pre>
+                  </details>
+                )}
               </div>
-            ))}
+            )}
+            {chmodded && chmodded.length > 0 && (
+              <div className="flex items-center gap-2 text-success text-xs">
+                <Wand2 className="w-3.5 h-3.5" />
+                <span>已修改 {chmodded.length} 个密钥文件的权限</span>
+              </div>
+            )}
+            {sortDiagnostics(warnings).map((d, i) => {
+              const Icon = SEVERITY_ICON[d.severity];
+              const fix = d.suggested_fix;
+              const fixable = fix && (fix.chmod !== null || !d.file || d.file === activeFilePath);
+              return (
+                <div key={i} className="flex items-start gap-2">
+                  <button
+                    onClick={() => revealDiagnostic(d)}
+                    className={clsx(
+                      "flex-1 flex items-start gap-2 text-xs text-left hover:underline",
+                      SEVERITY_TEXT[d.severity],
+                    )}
+                  >
+                    <Icon className="w-3.5 h-3.5 shrink-0 mt-0.5" />
+                    <span>{formatDiagnostic(d, activeFilePath)}</span>
+                  </button>
+                  {fix && fixable && (
+                    <button
+                      onClick={() => (fix.chmod ? handleChmod(fix.chmod.path) : handleFix([d.code]))}
+                      disabled={fixing}
+                      title={fix.chmod ? undefined : "同类问题会一并修复"}
+                      className="shrink-0 px-1.5 py-0.5 rounded text-[11px] text-primary bg-primary/10 hover:bg-primary/20 transition-colors disabled:opacity-50"
+                    >
+                      {fix.message}
+                    </button>
+                  )}
+                </div>
+              );
+            })}
             {validateSuccess && !error && warnings.length === 0 && (
               <div className="flex items-center gap-2 text-success text-xs">
                 <CheckCircle className="w-3.5 h-3.5" />
@@ -290,7 +638,10 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
             {saveSuccess && !error && (
               <div className="flex items-center gap-2 text-success text-xs">
                 <CheckCircle className="w-3.5 h-3.5" />
-                <span>已保存成功{warnings.length > 0 ? "（有警告）" : ""}</span>
+                <span>
+                  已保存成功{mergedNotice ? "，已合并磁盘上的修改" : ""}
+                  {warnings.length > 0 ? "（有警告）" : ""}
+                </span>
               </div>
             )}
           </div>
@@ -303,7 +654,7 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
             <div className="px-3 py-2 border-b border-border">
               <div className="flex items-center gap-1.5 text-[10px] font-semibold text-text-dim uppercase tracking-wider">
                 <FolderOpen className="w-3 h-3" />
-                ~/.ssh
+                配置文件
               </div>
             </div>
             <div className="py-1">
@@ -358,19 +709,37 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
                   ref={lineNumbersRef}
                   className="py-3 px-2 text-right text-text-dim text-xs font-mono select-none overflow-hidden border-r border-border bg-surface-light shrink-0 leading-[1.625rem]"
                 >
-                  {content.split("\n").map((_, i) => (
-                    <div key={i}>{i + 1}</div>
-                  ))}
+                  {content.split("\n").map((_, i) => {
+                    const marks = sortDiagnostics(
+                      shownDiagnostics.filter((d) => d.line === i + 1),
+                    );
+                    return (
+                      <div
+                        key={i}
+                        title={marks.map((d) => formatDiagnostic(d)).join("\n") || undefined}
+                        className={clsx(marks.length > 0 && SEVERITY_TEXT[marks[0].severity])}
+                      >
+                        {i + 1}
+                      </div>
+                    );
+                  })}
+                </div>
+                {/* Textarea with diagnostic underlines behind it */}
+                <div className="relative flex-1 h-full">
+                  <DiagnosticOverlay
+                    content={content}
+                    diagnostics={shownDiagnostics}
+                    overlayRef={overlayRef}
+                  />
+                  <textarea
+                    ref={textareaRef}
+                    value={content}
+                    onChange={(e) => updateContent(e.target.value)}
+                    spellCheck={false}
+                    className="relative w-full h-full resize-none bg-transparent text-sm font-mono p-3 focus:outline-none leading-[1.625rem] overflow-auto"
+                    style={{ tabSize: 4, scrollbarGutter: "stable" }}
+                  />
                 </div>
-                {/* Textarea */}
-                <textarea
-                  ref={textareaRef}
-                  value={content}
-                  onChange={(e) => updateContent(e.target.value)}
-                  spellCheck={false}
-                  className="flex-1 h-full resize-none bg-transparent text-sm font-mono p-3 focus:outline-none leading-[1.625rem] overflow-auto"
-                  style={{ tabSize: 4 }}
-                />
               </div>
             )}
           </div>
@@ -385,6 +754,13 @@ export function SshConfigEditor({ open, onClose, onSaved }: SshConfigEditorProps
           <span>⌘S 保存 · 保存时自动校验 · 自动备份</span>
         </div>
       </div>
+
+      <SshConfigHistory
+        open={historyOpen}
+        path={activeFilePath}
+        onClose={() => setHistoryOpen(false)}
+        onRestored={handleRestored}
+      />
     </div>
   );
 }
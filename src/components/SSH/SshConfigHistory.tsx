@@ -0,0 +1,170 @@
+import { useState, useEffect } from "react";
+import { X, History, RotateCcw, Loader2, AlertTriangle } from "lucide-react";
+import { clsx } from "clsx";
+import {
+  listSshConfigBackups,
+  diffSshConfigBackup,
+  restoreSshConfigBackup,
+} from "../../services/tauri";
+import type { ConfigBackup } from "../../types/ssh";
+
+interface SshConfigHistoryProps {
+  open: boolean;
+  // Config file whose history is shown
+  path: string;
+  onClose: () => void;
+  onRestored: () => void;
+}
+
+function formatTime(ms: number) {
+  return new Date(ms).toLocaleString();
+}
+
+export function SshConfigHistory({ open, path, onClose, onRestored }: SshConfigHistoryProps) {
+  const [backups, setBackups] = useState<ConfigBackup[]>([]);
+  const [selected, setSelected] = useState<string | null>(null);
+  const [diff, setDiff] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [restoring, setRestoring] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!open) return;
+    setLoading(true);
+    setError(null);
+    setSelected(null);
+    setDiff("");
+    listSshConfigBackups(path)
+      .then(setBackups)
+      .catch((e) => setError(String(e)))
+      .finally(() => setLoading(false));
+  }, [open, path]);
+
+  const selectBackup = async (id: string) => {
+    setSelected(id);
+    setError(null);
+    try {
+      setDiff(await diffSshConfigBackup(id));
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleRestore = async () => {
+    if (!selected) return;
+    if (!window.confirm("确认恢复到该版本？当前内容会先被备份。")) return;
+    setRestoring(true);
+    try {
+      await restoreSshConfigBackup(selected);
+      onRestored();
+      onClose();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setRestoring(false);
+    }
+  };
+
+  if (!open) return null;
+
+  return (
+    <div className="fixed inset-0 z-[60] flex items-center justify-center bg-black/60 backdrop-blur-sm">
+      <div className="bg-surface border border-border rounded-2xl shadow-2xl flex flex-col w-[88vw] max-w-4xl h-[80vh]">
+        <div className="flex items-center justify-between px-5 py-3 border-b border-border shrink-0">
+          <div className="flex items-center gap-2">
+            <History className="w-4.5 h-4.5 text-primary" />
+            <h2 className="text-sm font-semibold">历史版本</h2>
+            <span className="text-xs text-text-dim font-mono truncate">{path}</span>
+          </div>
+          <div className="flex items-center gap-2">
+            <button
+              onClick={handleRestore}
+              disabled={!selected || restoring}
+              className={clsx(
+                "flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium transition-all",
+                selected
+                  ? "bg-primary text-white hover:bg-primary-dark"
+                  : "bg-surface-light text-text-dim border border-border cursor-not-allowed",
+              )}
+            >
+              {restoring ? (
+                <Loader2 className="w-3.5 h-3.5 animate-spin" />
+              ) : (
+                <RotateCcw className="w-3.5 h-3.5" />
+              )}
+              恢复此版本
+            </button>
+            <button
+              onClick={onClose}
+              className="p-1.5 rounded-lg hover:bg-surface-lighter text-text-dim hover:text-text transition"
+            >
+              <X className="w-4 h-4" />
+            </button>
+          </div>
+        </div>
+
+        {error && (
+          <div className="px-5 py-2 border-b border-border flex items-start gap-2 text-danger text-xs">
+            <AlertTriangle className="w-3.5 h-3.5 shrink-0 mt-0.5" />
+            <span>{error}</span>
+          </div>
+        )}
+
+        <div className="flex-1 flex overflow-hidden">
+          <div className="w-56 shrink-0 border-r border-border bg-surface-light overflow-y-auto py-1">
+            {loading ? (
+              <div className="flex justify-center py-6">
+                <Loader2 className="w-5 h-5 text-primary animate-spin" />
+              </div>
+            ) : backups.length === 0 ? (
+              <div className="px-3 py-3 text-[10px] text-text-dim">暂无备份</div>
+            ) : (
+              backups.map((b) => (
+                <button
+                  key={b.id}
+                  onClick={() => selectBackup(b.id)}
+                  className={clsx(
+                    "w-full flex items-center justify-between gap-2 px-3 py-1.5 text-left text-xs transition-colors",
+                    b.id === selected
+                      ? "bg-primary/15 text-primary font-medium"
+                      : "text-text-dim hover:bg-surface-lighter hover:text-text",
+                  )}
+                >
+                  <span className="truncate">{formatTime(b.created_at)}</span>
+                  <span className="text-[10px] shrink-0">{b.size} B</span>
+                </button>
+              ))
+            )}
+          </div>
+
+          <div className="flex-1 overflow-auto">
+            {!selected ? (
+              <div className="flex items-center justify-center h-full text-text-dim text-sm">
+                选择一个版本查看与当前内容的差异
+              </div>
+            ) : diff === "" ? (
+              <div className="flex items-center justify-center h-full text-text-dim text-sm">
+                与当前内容相同
+              </div>
+            ) : (
+              <pre className="text-xs font-mono p-3 leading-5">
+                {diff.split("\n").map((line, i) => (
+                  <div
+                    key={i}
+                    className={clsx(
+                      line.startsWith("+") && !line.startsWith("+++") && "text-success bg-success/10",
+                      line.startsWith("-") && !line.startsWith("---") && "text-danger bg-danger/10",
+                      line.startsWith("@@") && "text-primary",
+                    )}
+                  >
+                    {line || " "}
+                  </div>
+                ))}
+              </pre>
+            )}
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
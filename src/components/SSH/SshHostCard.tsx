@@ -1,52 +1,71 @@
+import { useState } from "react";
 import { clsx } from "clsx";
 import {
   Monitor,
   ArrowRight,
+  ChevronDown,
   Shield,
   Network,
   Globe,
+  Star,
+  Pin,
 } from "lucide-react";
 import type { SshHost } from "../../types/ssh";
+import { groupLabel } from "./groups";
+import { useLaunchProfiles } from "../../hooks/useSshHosts";
 
 interface SshHostCardProps {
   host: SshHost;
-  onConnect: (name: string) => void;
+  // `profile` names the launch profile to connect with, if any
+  onConnect: (name: string, profile?: string) => void;
   isConnecting: boolean;
+  pinned: boolean;
+  onTogglePin: (name: string) => void;
 }
 
 const groupIcons: Record<string, React.FC<{ className?: string }>> = {
   direct: Monitor,
   proxy: Network,
   local: Monitor,
-  github: Globe,
+  git: Globe,
 };
 
-const groupLabels: Record<string, string> = {
-  direct: "直连",
-  proxy: "跳板",
-  local: "本地",
-  github: "代码托管",
-};
-
-export function SshHostCard({ host, onConnect, isConnecting }: SshHostCardProps) {
+export function SshHostCard({
+  host,
+  onConnect,
+  isConnecting,
+  pinned,
+  onTogglePin,
+}: SshHostCardProps) {
   const Icon = groupIcons[host.group] || Monitor;
+  // Launch profiles are only fetched once the menu is opened
+  const [profilesOpen, setProfilesOpen] = useState(false);
+  const { data: profiles, isLoading: profilesLoading } = useLaunchProfiles(host.name, profilesOpen);
 
   return (
-    <div className="bg-surface-light border border-border rounded-xl p-4 hover:border-primary/40 transition-colors group">
+    <div
+      className="bg-surface-light border border-border rounded-xl p-4 hover:border-primary/40 transition-colors group"
+      style={host.metadata.color ? { borderLeftColor: host.metadata.color, borderLeftWidth: 3 } : undefined}
+    >
       <div className="flex items-start justify-between">
         <div className="flex items-center gap-3">
           <div
             className={clsx(
               "w-10 h-10 rounded-lg flex items-center justify-center",
               host.group === "proxy" ? "bg-warning/15 text-warning" :
-              host.group === "github" ? "bg-success/15 text-success" :
+              host.group === "git" ? "bg-success/15 text-success" :
               "bg-primary/15 text-primary",
             )}
           >
             <Icon className="w-5 h-5" />
           </div>
           <div>
-            <h3 className="font-medium text-sm">{host.name}</h3>
+            <h3 className="font-medium text-sm flex items-center gap-1">
+              {host.name}
+              {host.metadata.favorite && (
+                <Star className="w-3 h-3 text-warning fill-warning" />
+              )}
+            </h3>
             <p className="text-xs text-text-dim mt-0.5">
               {host.user ? `${host.user}@` : ""}
               {host.hostname}
@@ -55,25 +74,99 @@ export function SshHostCard({ host, onConnect, isConnecting }: SshHostCardProps)
           </div>
         </div>
 
-        <button
-          onClick={() => onConnect(host.name)}
-          disabled={isConnecting}
-          className={clsx(
-            "flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium transition-all",
-            "bg-primary/10 text-primary hover:bg-primary hover:text-white",
-            "disabled:opacity-50 disabled:cursor-not-allowed",
-          )}
-        >
-          <ArrowRight className="w-3.5 h-3.5" />
-          连接
-        </button>
+        <div className="flex items-center gap-1">
+          <button
+            onClick={() => onTogglePin(host.name)}
+            className={clsx(
+              "p-1.5 rounded-lg transition-colors",
+              pinned ? "text-primary" : "text-text-dim hover:text-text",
+            )}
+            title={pinned ? "取消收藏" : "收藏"}
+          >
+            <Pin className={clsx("w-3.5 h-3.5", pinned && "fill-primary")} />
+          </button>
+          <button
+            onClick={() => onConnect(host.name)}
+            disabled={isConnecting}
+            className={clsx(
+              "flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-xs font-medium transition-all",
+              "bg-primary/10 text-primary hover:bg-primary hover:text-white",
+              "disabled:opacity-50 disabled:cursor-not-allowed",
+            )}
+          >
+            <ArrowRight className="w-3.5 h-3.5" />
+            连接
+          </button>
+          <div className="relative">
+            <button
+              onClick={() => setProfilesOpen((open) => !open)}
+              disabled={isConnecting}
+              className="p-1.5 rounded-lg text-text-dim hover:text-text transition-colors disabled:opacity-50"
+              title="连接方式"
+            >
+              <ChevronDown className="w-3.5 h-3.5" />
+            </button>
+            {profilesOpen && (
+              <div className="absolute right-0 top-full mt-1 z-10 min-w-40 bg-surface border border-border rounded-lg shadow-lg py-1">
+                {profilesLoading && <p className="px-3 py-1.5 text-xs text-text-dim">加载中…</p>}
+                {profiles?.length === 0 && (
+                  <p className="px-3 py-1.5 text-xs text-text-dim">没有可用的连接方式</p>
+                )}
+                {profiles?.map((profile) => (
+                  <button
+                    key={profile.name}
+                    onClick={() => {
+                      setProfilesOpen(false);
+                      onConnect(host.name, profile.name);
+                    }}
+                    className="block w-full text-left px-3 py-1.5 text-xs hover:bg-surface-lighter"
+                    title={[...profile.ssh_options, profile.remote_command ?? ""].join(" ").trim()}
+                  >
+                    {profile.name}
+                  </button>
+                ))}
+              </div>
+            )}
+          </div>
+        </div>
       </div>
 
+      {host.metadata.description && (
+        <p className="text-xs text-text-dim mt-2 line-clamp-2" title={host.metadata.description}>
+          {host.metadata.description}
+        </p>
+      )}
+
       {/* Tags */}
-      <div className="flex items-center gap-2 mt-3">
+      <div className="flex flex-wrap items-center gap-2 mt-3">
         <span className="text-[10px] px-2 py-0.5 rounded-full bg-surface-lighter text-text-dim">
-          {groupLabels[host.group]}
+          {groupLabel(host.group)}
         </span>
+        {host.metadata.env && (
+          <span
+            className={clsx(
+              "text-[10px] px-2 py-0.5 rounded-full",
+              host.metadata.env === "prod" || host.metadata.env === "production"
+                ? "bg-danger/10 text-danger"
+                : "bg-surface-lighter text-text-dim",
+            )}
+          >
+            {host.metadata.env}
+          </span>
+        )}
+        {host.metadata.owner && (
+          <span className="text-[10px] px-2 py-0.5 rounded-full bg-surface-lighter text-text-dim">
+            @{host.metadata.owner}
+          </span>
+        )}
+        {host.tags.map((tag) => (
+          <span
+            key={tag}
+            className="text-[10px] px-2 py-0.5 rounded-full bg-primary/10 text-primary"
+          >
+            #{tag}
+          </span>
+        ))}
         {host.is_1password_agent && (
           <span className="text-[10px] px-2 py-0.5 rounded-full bg-primary/10 text-primary flex items-center gap-1">
             <Shield className="w-2.5 h-2.5" />
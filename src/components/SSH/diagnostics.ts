@@ -0,0 +1,28 @@
+import type { Diagnostic, Severity } from "../../types/ssh";
+
+// Settings labels for the lint rules, by rule id
+export const LINT_RULE_LABELS: Record<string, string> = {
+  "global-option-shadows": "全局选项覆盖了主机中的同名设置",
+  "duplicate-host-alias": "主机别名重复定义",
+  "wildcard-host-first": "Host * 位于具体主机之前",
+  "identity-file-missing": "IdentityFile 指向不存在的文件",
+  "identity-file-permissions": "私钥文件权限过宽",
+  "proxyjump-undefined": "ProxyJump 引用未定义的主机",
+  "proxyjump-cycle": "ProxyJump 循环跳转",
+  "local-forward-collision": "LocalForward 端口冲突",
+};
+
+const SEVERITY_ORDER: Record<Severity, number> = { error: 0, warning: 1, info: 2 };
+
+// `file` is the file being shown; diagnostics for other files name theirs
+export function formatDiagnostic(d: Diagnostic, file?: string): string {
+  const where = d.file && file && d.file !== file ? `${d.file.split("/").pop()} ` : "";
+  return d.line > 0 ? `${where}第 ${d.line} 行: ${d.message}` : `${where}${d.message}`;
+}
+
+// Most severe first, then in file order
+export function sortDiagnostics(diagnostics: Diagnostic[]): Diagnostic[] {
+  return [...diagnostics].sort(
+    (a, b) => SEVERITY_ORDER[a.severity] - SEVERITY_ORDER[b.severity] || a.line - b.line,
+  );
+}
@@ -0,0 +1,12 @@
+// Labels of the groups the default group rules assign; groups from custom
+// rules and `# ssh-m:` comments are shown by name
+export const GROUP_LABELS: Record<string, string> = {
+  direct: "直连",
+  proxy: "跳板",
+  local: "本地",
+  git: "代码托管",
+};
+
+export function groupLabel(group: string): string {
+  return GROUP_LABELS[group] ?? group;
+}
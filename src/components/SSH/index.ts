@@ -1,2 +1,3 @@
 export { SshHostCard } from "./SshHostCard";
 export { SshConfigEditor } from "./SshConfigEditor";
+export { SshConfigHistory } from "./SshConfigHistory";
@@ -1,7 +1,33 @@
+import { useEffect } from "react";
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
-import { listSshHosts, openSshTerminal, refreshSshConfig } from "../services/tauri";
+import {
+  listSshHosts,
+  openSshTerminal,
+  refreshSshConfig,
+  onSshHostsChanged,
+  listRecentHosts,
+  listFavoriteHosts,
+  setSshHostPinned,
+  searchSshHosts,
+  listLaunchProfiles,
+  openWorkspace,
+  getAppSettings,
+} from "../services/tauri";
 
 export function useSshHosts() {
+  const queryClient = useQueryClient();
+
+  // Keep the list in sync with config edits made outside the app
+  useEffect(() => {
+    const unlisten = onSshHostsChanged((hosts) => {
+      queryClient.setQueryData(["ssh-hosts"], hosts);
+      queryClient.invalidateQueries({ queryKey: ["ssh-search"] });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [queryClient]);
+
   return useQuery({
     queryKey: ["ssh-hosts"],
     queryFn: listSshHosts,
@@ -9,9 +35,80 @@ export function useSshHosts() {
 }
 
 export function useOpenSshTerminal() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({
+      host,
+      terminal,
+      profile,
+    }: {
+      host: string;
+      terminal?: string;
+      profile?: string;
+    }) => openSshTerminal(host, terminal, profile),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["ssh-recent-hosts"] });
+    },
+  });
+}
+
+export function useWorkspaces() {
+  return useQuery({
+    queryKey: ["ssh-workspaces"],
+    queryFn: () => getAppSettings().then((s) => s.workspaces),
+  });
+}
+
+export function useOpenWorkspace() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ name, terminal }: { name: string; terminal?: string }) =>
+      openWorkspace(name, terminal),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["ssh-recent-hosts"] });
+    },
+  });
+}
+
+// Launch profiles of a host, fetched once `enabled`
+export function useLaunchProfiles(alias: string, enabled: boolean) {
+  return useQuery({
+    queryKey: ["ssh-launch-profiles", alias],
+    queryFn: () => listLaunchProfiles(alias),
+    enabled,
+  });
+}
+
+export function useSearchSshHosts(query: string) {
+  return useQuery({
+    queryKey: ["ssh-search", query],
+    queryFn: () => searchSshHosts(query),
+    enabled: query.trim() !== "",
+  });
+}
+
+export function useRecentHosts() {
+  return useQuery({
+    queryKey: ["ssh-recent-hosts"],
+    queryFn: () => listRecentHosts(),
+  });
+}
+
+export function useFavoriteHosts() {
+  return useQuery({
+    queryKey: ["ssh-favorite-hosts"],
+    queryFn: listFavoriteHosts,
+  });
+}
+
+export function useSetHostPinned() {
+  const queryClient = useQueryClient();
   return useMutation({
-    mutationFn: ({ host, terminal }: { host: string; terminal?: string }) =>
-      openSshTerminal(host, terminal),
+    mutationFn: ({ alias, pinned }: { alias: string; pinned: boolean }) =>
+      setSshHostPinned(alias, pinned),
+    onSuccess: (data) => {
+      queryClient.setQueryData(["ssh-favorite-hosts"], data);
+    },
   });
 }
 
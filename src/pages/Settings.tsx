@@ -1,13 +1,72 @@
 import { useState, useEffect } from "react";
-import { Settings as SettingsIcon, Save, RotateCcw, Sun, Moon, Monitor } from "lucide-react";
+import {
+  Settings as SettingsIcon,
+  Save,
+  RotateCcw,
+  Sun,
+  Moon,
+  Monitor,
+  Plus,
+  Trash2,
+  ArrowUp,
+} from "lucide-react";
 import { clsx } from "clsx";
 import { useTheme, type ThemeMode } from "../hooks/useTheme";
-import { saveAppSettings, getAppSettings } from "../services/tauri";
+import {
+  saveAppSettings,
+  getAppSettings,
+  listLintRules,
+  getOpensshVersions,
+  getDefaultGroupRules,
+  detectTerminals,
+} from "../services/tauri";
+import { LINT_RULE_LABELS } from "../components/SSH/diagnostics";
+import type {
+  GroupRule,
+  LaunchProfile,
+  LintRuleInfo,
+  OpenSshVersions,
+  TerminalInfo,
+  Workspace,
+} from "../types/ssh";
 
 // --- 区块链 RPC 配置（暂时注释）---
 // interface ChainRpcSetting { name: string; chain_type: string; rpc_url: string; default_url: string; }
 // const defaultRpcSettings: ChainRpcSetting[] = [ ... ];
 
+const emptyRule: GroupRule = {
+  group: null,
+  tags: [],
+  alias: null,
+  hostname: null,
+  cidr: null,
+  proxy_jump: null,
+  source_file: null,
+};
+
+const emptyProfile: LaunchProfile = {
+  name: "",
+  alias: null,
+  group: null,
+  ssh_options: [],
+  remote_command: null,
+};
+
+const emptyWorkspace: Workspace = {
+  name: "",
+  hosts: [],
+  layout: "tmux",
+  synchronize_panes: false,
+};
+
+// Conditions a group rule can set, edited as text
+const ruleTextFields = [
+  { key: "alias", label: "别名", placeholder: "prod-*,!prod-test" },
+  { key: "hostname", label: "地址", placeholder: "*.example.com" },
+  { key: "cidr", label: "网段", placeholder: "10.0.0.0/8,fc00::/7" },
+  { key: "source_file", label: "来源文件", placeholder: "~/.ssh/conf.d/work*" },
+] as const;
+
 const themeOptions: { value: ThemeMode; label: string; icon: typeof Sun }[] = [
   { value: "light", label: "浅色", icon: Sun },
   { value: "dark", label: "深色", icon: Moon },
@@ -17,29 +76,139 @@ const themeOptions: { value: ThemeMode; label: string; icon: typeof Sun }[] = [
 export function SettingsPage() {
   const { mode: themeMode, setTheme } = useTheme();
   const [defaultTerminal, setDefaultTerminal] = useState("terminal");
+  // Custom terminal argv template, one argument per line
+  const [customTerminal, setCustomTerminal] = useState("");
+  // Installed terminals; null until detected
+  const [terminals, setTerminals] = useState<TerminalInfo[] | null>(null);
   const [sshConfigPath, setSshConfigPath] = useState("~/.ssh/config");
+  // Additional config files, one path per line
+  const [additionalPaths, setAdditionalPaths] = useState("");
+  const [backupRetention, setBackupRetention] = useState(20);
+  const [lintRules, setLintRules] = useState<LintRuleInfo[]>([]);
+  // OpenSSH release configs are checked against; "" for the latest
+  const [opensshVersion, setOpensshVersion] = useState("");
+  const [opensshVersions, setOpensshVersions] = useState<OpenSshVersions | null>(null);
+  const [sshClientCheck, setSshClientCheck] = useState(false);
+  const [groupRules, setGroupRules] = useState<GroupRule[]>([]);
+  // ssh options of each profile are edited as one space-separated line
+  const [launchProfiles, setLaunchProfiles] = useState<(LaunchProfile & { options: string })[]>([]);
+  // Hosts of each workspace are edited as one comma-separated line
+  const [workspaces, setWorkspaces] = useState<(Workspace & { hostList: string })[]>([]);
   const [saved, setSaved] = useState(false);
+  const [saveError, setSaveError] = useState<string | null>(null);
 
   // Load settings from backend on mount
   useEffect(() => {
     getAppSettings().then((s) => {
       setDefaultTerminal(s.default_terminal);
+      setCustomTerminal(s.custom_terminal.join("\n"));
       setSshConfigPath(s.ssh_config_path);
+      setAdditionalPaths(s.additional_config_paths.join("\n"));
+      setBackupRetention(s.backup_retention);
+      setOpensshVersion(s.openssh_version ?? "");
+      setSshClientCheck(s.ssh_client_check);
+      setGroupRules(s.group_rules);
+      setLaunchProfiles(s.launch_profiles.map((p) => ({ ...p, options: p.ssh_options.join(" ") })));
+      setWorkspaces(s.workspaces.map((w) => ({ ...w, hostList: w.hosts.join(", ") })));
     }).catch(() => {
       // Fallback to localStorage for first run
       setDefaultTerminal(localStorage.getItem("ssh-m:defaultTerminal") || "terminal");
       setSshConfigPath(localStorage.getItem("ssh-m:sshConfigPath") || "~/.ssh/config");
     });
+    listLintRules().then(setLintRules).catch(() => {});
+    getOpensshVersions().then(setOpensshVersions).catch(() => {});
+    detectTerminals().then(setTerminals).catch(() => setTerminals([]));
   }, []);
 
+  // Offer only installed terminals and the custom one: a saved one that
+  // isn't installed here is replaced by the first that is
+  useEffect(() => {
+    if (
+      terminals?.length &&
+      defaultTerminal !== "custom" &&
+      !terminals.some((t) => t.id === defaultTerminal)
+    ) {
+      setDefaultTerminal(terminals[0].id);
+    }
+  }, [terminals, defaultTerminal]);
+
+  const toggleLintRule = (id: string) => {
+    setLintRules((rules) => rules.map((r) => (r.id === id ? { ...r, enabled: !r.enabled } : r)));
+  };
+
+  const updateRule = (index: number, patch: Partial<GroupRule>) => {
+    setGroupRules((rules) => rules.map((r, i) => (i === index ? { ...r, ...patch } : r)));
+  };
+
+  const updateProfile = (index: number, patch: Partial<LaunchProfile & { options: string }>) => {
+    setLaunchProfiles((profiles) => profiles.map((p, i) => (i === index ? { ...p, ...patch } : p)));
+  };
+
+  const updateWorkspace = (index: number, patch: Partial<Workspace & { hostList: string }>) => {
+    setWorkspaces((list) => list.map((w, i) => (i === index ? { ...w, ...patch } : w)));
+  };
+
+  const moveRuleUp = (index: number) => {
+    if (index === 0) return;
+    setGroupRules((rules) => {
+      const next = [...rules];
+      [next[index - 1], next[index]] = [next[index], next[index - 1]];
+      return next;
+    });
+  };
+
   const handleSave = async () => {
     // Save to both localStorage (for frontend) and backend (for tray)
     localStorage.setItem("ssh-m:defaultTerminal", defaultTerminal);
     localStorage.setItem("ssh-m:sshConfigPath", sshConfigPath);
     try {
-      await saveAppSettings(defaultTerminal, sshConfigPath);
+      await saveAppSettings({
+        default_terminal: defaultTerminal,
+        custom_terminal: customTerminal
+          .split("\n")
+          .map((a) => a.trim())
+          .filter(Boolean),
+        ssh_config_path: sshConfigPath,
+        additional_config_paths: additionalPaths
+          .split("\n")
+          .map((p) => p.trim())
+          .filter(Boolean),
+        backup_retention: backupRetention,
+        lint_rules: Object.fromEntries(lintRules.map((r) => [r.id, r.enabled])),
+        openssh_version: opensshVersion || null,
+        ssh_client_check: sshClientCheck,
+        // Empty fields are conditions that aren't set
+        group_rules: groupRules.map((r) => ({
+          ...r,
+          group: r.group?.trim() || null,
+          tags: r.tags.map((t) => t.trim()).filter(Boolean),
+          alias: r.alias?.trim() || null,
+          hostname: r.hostname?.trim() || null,
+          cidr: r.cidr?.trim() || null,
+          source_file: r.source_file?.trim() || null,
+        })),
+        launch_profiles: launchProfiles.map(({ options, ...p }) => ({
+          ...p,
+          name: p.name.trim(),
+          alias: p.alias?.trim() || null,
+          group: p.group?.trim() || null,
+          ssh_options: options.split(/\s+/).filter(Boolean),
+          remote_command: p.remote_command?.trim() || null,
+        })),
+        workspaces: workspaces.map(({ hostList, ...w }) => ({
+          ...w,
+          name: w.name.trim(),
+          hosts: hostList
+            .split(/[,\s]+/)
+            .map((h) => h.trim())
+            .filter(Boolean),
+        })),
+      });
+      setSaveError(null);
     } catch (e) {
       console.error("Failed to save settings to backend:", e);
+      setSaveError(String(e));
+      return;
     }
     setSaved(true);
     setTimeout(() => setSaved(false), 2000);
@@ -102,6 +271,23 @@ export function SettingsPage() {
               <RotateCcw className="w-3.5 h-3.5" />
             </button>
           </div>
+          <div className="flex items-start gap-3">
+            <label className="w-32 text-sm text-text-dim shrink-0 pt-2">
+              附加配置文件
+            </label>
+            <div className="flex-1">
+              <textarea
+                value={additionalPaths}
+                onChange={(e) => setAdditionalPaths(e.target.value)}
+                rows={3}
+                placeholder="~/work/ssh_config"
+                className="w-full px-3 py-2 bg-surface border border-border rounded-lg text-sm font-mono focus:outline-none focus:border-primary transition-colors resize-y"
+              />
+              <p className="text-xs text-text-dim mt-1">
+                每行一个路径，其中的主机会合并到主机列表；同名主机以靠前的文件为准
+              </p>
+            </div>
+          </div>
           <div className="flex items-center gap-3">
             <label className="w-32 text-sm text-text-dim shrink-0">
               默认终端
@@ -111,14 +297,375 @@ export function SettingsPage() {
               onChange={(e) => setDefaultTerminal(e.target.value)}
               className="flex-1 px-3 py-2 bg-surface border border-border rounded-lg text-sm focus:outline-none focus:border-primary transition-colors"
             >
-              <option value="terminal">Terminal.app</option>
-              <option value="iterm">iTerm2</option>
-              <option value="warp">Warp</option>
+              {terminals
+                ?.filter((t) => t.id !== "custom")
+                .map((t) => (
+                  <option key={t.id} value={t.id}>
+                    {t.name}
+                  </option>
+                ))}
+              <option value="custom">自定义命令…</option>
+            </select>
+          </div>
+          {terminals?.length === 0 && defaultTerminal !== "custom" && (
+            <p className="text-xs text-text-dim pl-[8.75rem]">
+              未检测到已安装的终端，可使用自定义命令
+            </p>
+          )}
+          {defaultTerminal === "custom" && (
+            <div className="flex items-start gap-3">
+              <label className="w-32 text-sm text-text-dim shrink-0 pt-2">
+                自定义终端命令
+              </label>
+              <div className="flex-1">
+                <textarea
+                  value={customTerminal}
+                  onChange={(e) => setCustomTerminal(e.target.value)}
+                  rows={4}
+                  placeholder={"myterm\n--title={title}\n-e\n{ssh_command}"}
+                  className="w-full px-3 py-2 bg-surface border border-border rounded-lg text-sm font-mono focus:outline-none focus:border-primary transition-colors resize-y"
+                />
+                <p className="text-xs text-text-dim mt-1">
+                  每行一个参数，第一行为程序。可用占位符：{"{alias} {host} {user} {port} {ssh_command} {title}"}；
+                  {"{ssh_command}"} 是完整的 ssh 命令行（已做 shell 转义，必须包含）；{"{{"} {"}}"} 表示字面花括号
+                </p>
+              </div>
+            </div>
+          )}
+          <div className="flex items-center gap-3">
+            <label className="w-32 text-sm text-text-dim shrink-0">
+              备份保留数量
+            </label>
+            <input
+              type="number"
+              min={1}
+              max={500}
+              value={backupRetention}
+              onChange={(e) => setBackupRetention(Math.max(1, Number(e.target.value) || 1))}
+              className="w-24 px-3 py-2 bg-surface border border-border rounded-lg text-sm focus:outline-none focus:border-primary transition-colors"
+            />
+            <span className="text-xs text-text-dim">每个配置文件保留的历史版本数</span>
+          </div>
+          <div className="flex items-center gap-3">
+            <label className="w-32 text-sm text-text-dim shrink-0">
+              目标 OpenSSH 版本
+            </label>
+            <select
+              value={opensshVersion}
+              onChange={(e) => setOpensshVersion(e.target.value)}
+              className="w-40 px-3 py-2 bg-surface border border-border rounded-lg text-sm focus:outline-none focus:border-primary transition-colors"
+            >
+              <option value="">最新版本</option>
+              {opensshVersion && !opensshVersions?.known.includes(opensshVersion) && (
+                <option value={opensshVersion}>{opensshVersion}</option>
+              )}
+              {opensshVersions?.known.map((v) => (
+                <option key={v} value={v}>
+                  {v}
+                </option>
+              ))}
             </select>
+            {opensshVersions?.installed && (
+              <button
+                onClick={() => setOpensshVersion(opensshVersions.installed ?? "")}
+                className="text-xs text-primary hover:underline"
+              >
+                使用本机版本 {opensshVersions.installed}
+              </button>
+            )}
+          </div>
+          <div className="flex items-start gap-3">
+            <label className="w-32 text-sm text-text-dim shrink-0">
+              ssh 客户端校验
+            </label>
+            <div className="flex-1">
+              <label className="flex items-center gap-2 text-sm cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={sshClientCheck}
+                  onChange={(e) => setSshClientCheck(e.target.checked)}
+                  className="accent-primary"
+                />
+                <span>校验时同时运行本机 ssh -G</span>
+              </label>
+              <p className="text-xs text-text-dim mt-1">
+                ssh 报告的错误会阻止保存；主机的生效配置也以 ssh -G 的输出为准。含 Match exec 的配置不会被检查
+              </p>
+            </div>
           </div>
         </div>
       </section>
 
+      {/* Group rules */}
+      <section className="bg-surface-light border border-border rounded-xl p-5 mb-6">
+        <div className="flex items-center justify-between mb-1">
+          <h2 className="text-sm font-semibold">分组规则</h2>
+          <div className="flex items-center gap-3">
+            <button
+              onClick={() => getDefaultGroupRules().then(setGroupRules).catch(() => {})}
+              className="text-xs text-text-dim hover:text-text"
+            >
+              恢复默认
+            </button>
+            <button
+              onClick={() => setGroupRules((rules) => [...rules, { ...emptyRule }])}
+              className="flex items-center gap-1 text-xs text-primary hover:underline"
+            >
+              <Plus className="w-3.5 h-3.5" />
+              添加规则
+            </button>
+          </div>
+        </div>
+        <p className="text-xs text-text-dim mb-4">
+          主机归入第一条匹配且设置了分组的规则，并获得所有匹配规则的标签；规则中填写的条件须全部满足。
+          也可在 Host 块中写 <code className="font-mono"># ssh-m: group=prod tags=db,eu</code> 覆盖
+        </p>
+        <div className="space-y-3">
+          {groupRules.map((rule, i) => (
+            <div key={i} className="border border-border rounded-lg p-3 space-y-2">
+              <div className="flex items-center gap-2">
+                <input
+                  type="text"
+                  value={rule.group ?? ""}
+                  onChange={(e) => updateRule(i, { group: e.target.value })}
+                  placeholder="分组"
+                  className="w-32 px-2 py-1 bg-surface border border-border rounded text-sm focus:outline-none focus:border-primary"
+                />
+                <input
+                  type="text"
+                  value={rule.tags.join(",")}
+                  onChange={(e) => updateRule(i, { tags: e.target.value.split(",") })}
+                  placeholder="标签，逗号分隔"
+                  className="flex-1 px-2 py-1 bg-surface border border-border rounded text-sm focus:outline-none focus:border-primary"
+                />
+                <button
+                  onClick={() => moveRuleUp(i)}
+                  disabled={i === 0}
+                  className="p-1 rounded text-text-dim hover:text-text disabled:opacity-30"
+                  title="上移"
+                >
+                  <ArrowUp className="w-3.5 h-3.5" />
+                </button>
+                <button
+                  onClick={() => setGroupRules((rules) => rules.filter((_, j) => j !== i))}
+                  className="p-1 rounded text-text-dim hover:text-danger"
+                  title="删除"
+                >
+                  <Trash2 className="w-3.5 h-3.5" />
+                </button>
+              </div>
+              <div className="grid grid-cols-2 gap-2">
+                {ruleTextFields.map(({ key, label, placeholder }) => (
+                  <label key={key} className="flex items-center gap-2 text-xs text-text-dim">
+                    <span className="w-14 shrink-0">{label}</span>
+                    <input
+                      type="text"
+                      value={rule[key] ?? ""}
+                      onChange={(e) => updateRule(i, { [key]: e.target.value })}
+                      placeholder={placeholder}
+                      className="flex-1 min-w-0 px-2 py-1 bg-surface border border-border rounded text-xs font-mono text-text focus:outline-none focus:border-primary"
+                    />
+                  </label>
+                ))}
+                <label className="flex items-center gap-2 text-xs text-text-dim">
+                  <span className="w-14 shrink-0">ProxyJump</span>
+                  <select
+                    value={rule.proxy_jump === null ? "" : String(rule.proxy_jump)}
+                    onChange={(e) =>
+                      updateRule(i, {
+                        proxy_jump: e.target.value === "" ? null : e.target.value === "true",
+                      })
+                    }
+                    className="flex-1 px-2 py-1 bg-surface border border-border rounded text-xs text-text focus:outline-none focus:border-primary"
+                  >
+                    <option value="">不限</option>
+                    <option value="true">使用跳板</option>
+                    <option value="false">不使用跳板</option>
+                  </select>
+                </label>
+              </div>
+            </div>
+          ))}
+          {groupRules.length === 0 && (
+            <p className="text-xs text-text-dim">没有规则，所有主机归入“直连”</p>
+          )}
+        </div>
+      </section>
+
+      {/* Launch profiles */}
+      <section className="bg-surface-light border border-border rounded-xl p-5 mb-6">
+        <div className="flex items-center justify-between mb-1">
+          <h2 className="text-sm font-semibold">连接方式</h2>
+          <button
+            onClick={() => setLaunchProfiles((profiles) => [...profiles, { ...emptyProfile, options: "" }])}
+            className="flex items-center gap-1 text-xs text-primary hover:underline"
+          >
+            <Plus className="w-3.5 h-3.5" />
+            添加连接方式
+          </button>
+        </div>
+        <p className="text-xs text-text-dim mb-4">
+          附加 ssh 参数并在远端执行命令，例如 tmux 会话或端口转发；在主机卡片和托盘的主机子菜单中选择。
+          别名和分组留空则对所有主机可用
+        </p>
+        <div className="space-y-3">
+          {launchProfiles.map((profile, i) => (
+            <div key={i} className="border border-border rounded-lg p-3 space-y-2">
+              <div className="flex items-center gap-2">
+                <input
+                  type="text"
+                  value={profile.name}
+                  onChange={(e) => updateProfile(i, { name: e.target.value })}
+                  placeholder="名称，如 attach tmux"
+                  className="flex-1 px-2 py-1 bg-surface border border-border rounded text-sm focus:outline-none focus:border-primary"
+                />
+                <button
+                  onClick={() => setLaunchProfiles((profiles) => profiles.filter((_, j) => j !== i))}
+                  className="p-1 rounded text-text-dim hover:text-danger"
+                  title="删除"
+                >
+                  <Trash2 className="w-3.5 h-3.5" />
+                </button>
+              </div>
+              <div className="grid grid-cols-2 gap-2">
+                <label className="flex items-center gap-2 text-xs text-text-dim">
+                  <span className="w-14 shrink-0">别名</span>
+                  <input
+                    type="text"
+                    value={profile.alias ?? ""}
+                    onChange={(e) => updateProfile(i, { alias: e.target.value })}
+                    placeholder="prod-*,!prod-test"
+                    className="flex-1 min-w-0 px-2 py-1 bg-surface border border-border rounded text-xs font-mono text-text focus:outline-none focus:border-primary"
+                  />
+                </label>
+                <label className="flex items-center gap-2 text-xs text-text-dim">
+                  <span className="w-14 shrink-0">分组</span>
+                  <input
+                    type="text"
+                    value={profile.group ?? ""}
+                    onChange={(e) => updateProfile(i, { group: e.target.value })}
+                    placeholder="prod"
+                    className="flex-1 min-w-0 px-2 py-1 bg-surface border border-border rounded text-xs font-mono text-text focus:outline-none focus:border-primary"
+                  />
+                </label>
+                <label className="flex items-center gap-2 text-xs text-text-dim">
+                  <span className="w-14 shrink-0">ssh 参数</span>
+                  <input
+                    type="text"
+                    value={profile.options}
+                    onChange={(e) => updateProfile(i, { options: e.target.value })}
+                    placeholder="-A -L 5432:localhost:5432"
+                    className="flex-1 min-w-0 px-2 py-1 bg-surface border border-border rounded text-xs font-mono text-text focus:outline-none focus:border-primary"
+                  />
+                </label>
+                <label className="flex items-center gap-2 text-xs text-text-dim">
+                  <span className="w-14 shrink-0">远端命令</span>
+                  <input
+                    type="text"
+                    value={profile.remote_command ?? ""}
+                    onChange={(e) => updateProfile(i, { remote_command: e.target.value })}
+                    placeholder="tmux new -A -s main"
+                    className="flex-1 min-w-0 px-2 py-1 bg-surface border border-border rounded text-xs font-mono text-text focus:outline-none focus:border-primary"
+                  />
+                </label>
+              </div>
+            </div>
+          ))}
+          {launchProfiles.length === 0 && (
+            <p className="text-xs text-text-dim">没有连接方式，只能直接连接</p>
+          )}
+        </div>
+      </section>
+
+      {/* Workspaces */}
+      <section className="bg-surface-light border border-border rounded-xl p-5 mb-6">
+        <div className="flex items-center justify-between mb-1">
+          <h2 className="text-sm font-semibold">工作区</h2>
+          <button
+            onClick={() => setWorkspaces((list) => [...list, { ...emptyWorkspace, hostList: "" }])}
+            className="flex items-center gap-1 text-xs text-primary hover:underline"
+          >
+            <Plus className="w-3.5 h-3.5" />
+            添加工作区
+          </button>
+        </div>
+        <p className="text-xs text-text-dim mb-4">
+          一次打开多台主机：在本地 tmux 会话中平铺（已在运行则直接连回），或在终端的多个标签页中打开（不支持标签页的终端会打开多个窗口）
+        </p>
+        <div className="space-y-3">
+          {workspaces.map((workspace, i) => (
+            <div key={i} className="border border-border rounded-lg p-3 space-y-2">
+              <div className="flex items-center gap-2">
+                <input
+                  type="text"
+                  value={workspace.name}
+                  onChange={(e) => updateWorkspace(i, { name: e.target.value })}
+                  placeholder="名称，如 prod"
+                  className="w-40 px-2 py-1 bg-surface border border-border rounded text-sm focus:outline-none focus:border-primary"
+                />
+                <select
+                  value={workspace.layout}
+                  onChange={(e) => updateWorkspace(i, { layout: e.target.value as Workspace["layout"] })}
+                  className="px-2 py-1 bg-surface border border-border rounded text-sm focus:outline-none focus:border-primary"
+                >
+                  <option value="tmux">tmux 平铺</option>
+                  <option value="tabs">终端标签页</option>
+                </select>
+                {workspace.layout === "tmux" && (
+                  <label className="flex items-center gap-1.5 text-xs text-text-dim cursor-pointer">
+                    <input
+                      type="checkbox"
+                      checked={workspace.synchronize_panes}
+                      onChange={(e) => updateWorkspace(i, { synchronize_panes: e.target.checked })}
+                      className="accent-primary"
+                    />
+                    同步输入
+                  </label>
+                )}
+                <div className="flex-1" />
+                <button
+                  onClick={() => setWorkspaces((list) => list.filter((_, j) => j !== i))}
+                  className="p-1 rounded text-text-dim hover:text-danger"
+                  title="删除"
+                >
+                  <Trash2 className="w-3.5 h-3.5" />
+                </button>
+              </div>
+              <input
+                type="text"
+                value={workspace.hostList}
+                onChange={(e) => updateWorkspace(i, { hostList: e.target.value })}
+                placeholder="主机别名或 user@host:port，逗号分隔"
+                className="w-full px-2 py-1 bg-surface border border-border rounded text-xs font-mono focus:outline-none focus:border-primary"
+              />
+            </div>
+          ))}
+          {workspaces.length === 0 && <p className="text-xs text-text-dim">没有工作区</p>}
+        </div>
+      </section>
+
+            {/* Lint rules */}
+      {lintRules.length > 0 && (
+        <section className="bg-surface-light border border-border rounded-xl p-5 mb-6">
+          <h2 className="text-sm font-semibold mb-1">配置检查规则</h2>
+          <p className="text-xs text-text-dim mb-4">校验和保存 SSH 配置时运行的检查</p>
+          <div className="space-y-2">
+            {lintRules.map((rule) => (
+              <label key={rule.id} className="flex items-center gap-3 text-sm cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={rule.enabled}
+                  onChange={() => toggleLintRule(rule.id)}
+                  className="accent-primary"
+                />
+                <span>{LINT_RULE_LABELS[rule.id] ?? rule.id}</span>
+                <span className="text-xs text-text-dim font-mono">{rule.id}</span>
+              </label>
+            ))}
+          </div>
+        </section>
+      )}
+
       {/* Save */}
       <button
         onClick={handleSave}
@@ -130,6 +677,7 @@ export function SettingsPage() {
         <Save className="w-4 h-4" />
         {saved ? "已保存 ✓" : "保存设置"}
       </button>
+      {saveError && <p className="text-xs text-danger mt-2">{saveError}</p>}
     </div>
   );
 }
@@ -1,35 +1,71 @@
 import { useState } from "react";
-import { RefreshCw, Search, Terminal, FileEdit } from "lucide-react";
+import { RefreshCw, Search, Terminal, FileEdit, LayoutGrid } from "lucide-react";
 import { clsx } from "clsx";
-import { useSshHosts, useOpenSshTerminal, useRefreshSshConfig } from "../hooks/useSshHosts";
+import {
+  useSshHosts,
+  useOpenSshTerminal,
+  useRefreshSshConfig,
+  useRecentHosts,
+  useFavoriteHosts,
+  useSetHostPinned,
+  useSearchSshHosts,
+  useWorkspaces,
+  useOpenWorkspace,
+} from "../hooks/useSshHosts";
 import { SshHostCard, SshConfigEditor } from "../components/SSH";
+import { groupLabel } from "../components/SSH/groups";
 import type { SshHost } from "../types/ssh";
 
 export function SshManager() {
   const { data: hosts, isLoading, error } = useSshHosts();
   const connectMutation = useOpenSshTerminal();
   const refreshMutation = useRefreshSshConfig();
+  const { data: recent } = useRecentHosts();
+  const { data: favorites } = useFavoriteHosts();
+  const pinMutation = useSetHostPinned();
+  const { data: workspaces } = useWorkspaces();
+  const workspaceMutation = useOpenWorkspace();
   const [search, setSearch] = useState("");
+  const { data: searchResults } = useSearchSshHosts(search);
   const [activeGroup, setActiveGroup] = useState<string | "all">("all");
   const [editorOpen, setEditorOpen] = useState(false);
 
-  const groups = ["all", "direct", "proxy", "local", "github"] as const;
-  const groupLabels: Record<string, string> = {
-    all: "全部",
-    direct: "直连",
-    proxy: "跳板",
-    local: "本地",
-    github: "代码托管",
-  };
+  // Groups in the order they first appear, as in the tray menu, after the
+  // favorites and recent connections
+  const groups = [
+    "all",
+    ...(favorites?.length ? ["favorites"] : []),
+    ...(recent?.length ? ["recent"] : []),
+    ...new Set((hosts || []).map((h: SshHost) => h.group)),
+  ];
+  const specialLabels: Record<string, string> = { all: "全部", favorites: "收藏", recent: "最近" };
+  const favoriteOrder = (favorites || []).map((f) => f.alias);
+  const recentOrder = (recent || []).map((r) => r.alias);
+  const pinned = new Set((favorites || []).filter((f) => f.pinned).map((f) => f.alias));
 
-  const filteredHosts = (hosts || []).filter((h: SshHost) => {
-    const matchSearch =
-      !search ||
-      h.name.toLowerCase().includes(search.toLowerCase()) ||
-      h.hostname.toLowerCase().includes(search.toLowerCase());
-    const matchGroup = activeGroup === "all" || h.group === activeGroup;
-    return matchSearch && matchGroup;
+  // Search results come ranked from the backend
+  const searching = search.trim() !== "";
+  const baseHosts = searching ? (searchResults || []).map((m) => m.host) : hosts || [];
+  const filteredHosts = baseHosts.filter((h: SshHost) => {
+    const matchGroup =
+      activeGroup === "all" ||
+      (activeGroup === "favorites"
+        ? favoriteOrder.includes(h.name)
+        : activeGroup === "recent"
+          ? recentOrder.includes(h.name)
+          : h.group === activeGroup);
+    return matchGroup;
   });
+  const order = searching
+    ? null
+    : activeGroup === "favorites"
+      ? favoriteOrder
+      : activeGroup === "recent"
+        ? recentOrder
+        : null;
+  if (order) {
+    filteredHosts.sort((a, b) => order.indexOf(a.name) - order.indexOf(b.name));
+  }
 
   return (
     <div className="p-6">
@@ -71,13 +107,43 @@ export function SshManager() {
         </div>
       </div>
 
+      {/* Workspaces */}
+      {workspaces && workspaces.length > 0 && (
+        <div className="flex flex-wrap items-center gap-2 mb-4">
+          <span className="text-xs text-text-dim">工作区</span>
+          {workspaces.map((w) => (
+            <button
+              key={w.name}
+              onClick={() => {
+                const terminal = localStorage.getItem("ssh-m:defaultTerminal") || "terminal";
+                workspaceMutation.mutate({ name: w.name, terminal });
+              }}
+              disabled={workspaceMutation.isPending}
+              title={w.hosts.join(", ")}
+              className={clsx(
+                "flex items-center gap-1.5 px-3 py-1 rounded-lg text-xs font-medium transition-all",
+                "bg-surface-light border border-border text-text-dim hover:text-text hover:bg-surface-lighter",
+                "disabled:opacity-50 disabled:cursor-not-allowed",
+              )}
+            >
+              <LayoutGrid className="w-3.5 h-3.5" />
+              {w.name}
+              <span className="text-text-dim">({w.hosts.length})</span>
+            </button>
+          ))}
+          {workspaceMutation.error && (
+            <span className="text-xs text-danger">{String(workspaceMutation.error)}</span>
+          )}
+        </div>
+      )}
+
       {/* Search & Filter */}
       <div className="flex items-center gap-4 mb-6">
         <div className="relative flex-1 max-w-sm">
           <Search className="absolute left-3 top-1/2 -translate-y-1/2 w-4 h-4 text-text-dim" />
           <input
             type="text"
-            placeholder="搜索主机名或地址..."
+            placeholder="搜索别名、地址、用户、标签、描述..."
             value={search}
             onChange={(e) => setSearch(e.target.value)}
             className="w-full pl-9 pr-4 py-2 bg-surface-light border border-border rounded-lg text-sm focus:outline-none focus:border-primary transition-colors"
@@ -95,7 +161,7 @@ export function SshManager() {
                   : "text-text-dim hover:text-text hover:bg-surface-lighter",
               )}
             >
-              {groupLabels[g]}
+              {specialLabels[g] ?? groupLabel(g)}
             </button>
           ))}
         </div>
@@ -124,7 +190,7 @@ export function SshManager() {
             {search ? "没有找到匹配的主机" : "未找到 SSH 配置"}
           </p>
           <p className="text-xs text-text-dim mt-1">
-            请确保 ~/.ssh/config 文件存在
+            请确保设置中的 SSH 配置文件存在
           </p>
         </div>
       ) : (
@@ -133,11 +199,15 @@ export function SshManager() {
             <SshHostCard
               key={host.name}
               host={host}
-              onConnect={(name) => {
+              onConnect={(name, profile) => {
                 const terminal = localStorage.getItem("ssh-m:defaultTerminal") || "terminal";
-                connectMutation.mutate({ host: name, terminal });
+                connectMutation.mutate({ host: name, terminal, profile });
               }}
               isConnecting={connectMutation.isPending}
+              pinned={pinned.has(host.name)}
+              onTogglePin={(name) =>
+                pinMutation.mutate({ alias: name, pinned: !pinned.has(name) })
+              }
             />
           ))}
         </div>
@@ -1,5 +1,24 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { SshHost } from "../types/ssh";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  SshHost,
+  SshHostPattern,
+  ResolvedHost,
+  SshHostDefinition,
+  ConfigBackup,
+  ConfigSnapshot,
+  SaveOutcome,
+  Diagnostic,
+  LintRuleInfo,
+  ConfigFixResult,
+  OpenSshVersions,
+  GroupRule,
+  HostUsageEntry,
+  HostMatch,
+  TerminalInfo,
+  LaunchProfile,
+  Workspace,
+} from "../types/ssh";
 import type { Vault, VaultItem, OpStatus } from "../types/onepassword";
 import type { MultiChainAccounts, BalanceReport, ChainConfig } from "../types/blockchain";
 
@@ -9,14 +28,90 @@ export async function listSshHosts(): Promise<SshHost[]> {
   return invoke("list_ssh_hosts");
 }
 
-export async function openSshTerminal(host: string, terminal?: string): Promise<void> {
-  return invoke("open_ssh_terminal", { host, terminal });
+export async function listSshHostPatterns(): Promise<SshHostPattern[]> {
+  return invoke("list_ssh_host_patterns");
+}
+
+// Fires with the new host list whenever a config file changes on disk
+export function onSshHostsChanged(callback: (hosts: SshHost[]) => void): Promise<UnlistenFn> {
+  return listen<SshHost[]>("ssh-hosts-changed", (event) => callback(event.payload));
+}
+
+// `host` is a configured alias or an explicit `user@host[:port]`; anything
+// else is refused. `profile` names a launch profile offered for the host.
+export async function openSshTerminal(
+  host: string,
+  terminal?: string,
+  profile?: string,
+): Promise<void> {
+  return invoke("open_ssh_terminal", { host, terminal, profile });
+}
+
+// Open every host of the saved workspace `name`
+export async function openWorkspace(name: string, terminal?: string): Promise<void> {
+  return invoke("open_workspace", { name, terminal });
+}
+
+export async function listLaunchProfiles(alias: string): Promise<LaunchProfile[]> {
+  return invoke("list_launch_profiles", { alias });
+}
+
+// Fuzzy search over alias, hostname, user, tags, description and source
+// file, boosted by connection history
+export async function searchSshHosts(query: string, limit?: number): Promise<HostMatch[]> {
+  return invoke("search_ssh_hosts", { query, limit });
+}
+
+export async function listRecentHosts(limit?: number): Promise<HostUsageEntry[]> {
+  return invoke("list_recent_hosts", { limit });
+}
+
+export async function listMostUsedHosts(limit?: number): Promise<HostUsageEntry[]> {
+  return invoke("list_most_used_hosts", { limit });
+}
+
+// Pinned hosts, followed by those marked favorite in their metadata
+export async function listFavoriteHosts(): Promise<HostUsageEntry[]> {
+  return invoke("list_favorite_hosts");
+}
+
+export async function setSshHostPinned(alias: string, pinned: boolean): Promise<HostUsageEntry[]> {
+  return invoke("set_ssh_host_pinned", { alias, pinned });
 }
 
 export async function refreshSshConfig(): Promise<SshHost[]> {
   return invoke("refresh_ssh_config");
 }
 
+export async function resolveSshHost(alias: string): Promise<ResolvedHost> {
+  return invoke("resolve_ssh_host", { alias });
+}
+
+export async function getSshHostDefinition(alias: string): Promise<SshHostDefinition> {
+  return invoke("get_ssh_host_definition", { alias });
+}
+
+export async function createSshHost(host: SshHostDefinition, path?: string): Promise<SshHost[]> {
+  return invoke("create_ssh_host", { host, path });
+}
+
+export async function updateSshHost(alias: string, host: SshHostDefinition): Promise<SshHost[]> {
+  return invoke("update_ssh_host", { alias, host });
+}
+
+export async function deleteSshHost(alias: string): Promise<SshHost[]> {
+  return invoke("delete_ssh_host", { alias });
+}
+
+export async function duplicateSshHost(alias: string, newAlias: string): Promise<SshHost[]> {
+  return invoke("duplicate_ssh_host", { alias, newAlias });
+}
+
+// Moves a host to another config file (a config root or a file it includes)
+export async function moveSshHost(alias: string, path: string): Promise<SshHost[]> {
+  return invoke("move_ssh_host", { alias, path });
+}
+
 export interface SshConfigFile {
   name: string;
   path: string;
@@ -28,16 +123,61 @@ export async function listSshConfigFiles(): Promise<SshConfigFile[]> {
   return invoke("list_ssh_config_files");
 }
 
-export async function readSshConfig(path?: string): Promise<string> {
+export async function readSshConfig(path?: string): Promise<ConfigSnapshot> {
   return invoke("read_ssh_config", { path });
 }
 
-export async function validateSshConfig(content: string): Promise<string[]> {
-  return invoke("validate_ssh_config", { content });
+export async function validateSshConfig(content: string, path?: string): Promise<Diagnostic[]> {
+  return invoke("validate_ssh_config", { content, path });
+}
+
+// Fixes only the given diagnostic codes when `codes` is passed
+export async function applyConfigFixes(
+  content: string,
+  path?: string,
+  codes?: string[],
+): Promise<ConfigFixResult> {
+  return invoke("apply_config_fixes", { content, path, codes });
+}
+
+// Restricts the permissions of key files picked from the offered fixes
+export async function fixKeyFilePermissions(
+  content: string,
+  path: string | undefined,
+  files: string[],
+): Promise<string[]> {
+  return invoke("fix_key_file_permissions", { content, path, files });
+}
+
+export async function getOpensshVersions(): Promise<OpenSshVersions> {
+  return invoke("get_openssh_versions");
 }
 
-export async function saveSshConfig(content: string, path?: string): Promise<string[]> {
-  return invoke("save_ssh_config", { content, path });
+export async function listLintRules(): Promise<LintRuleInfo[]> {
+  return invoke("list_lint_rules");
+}
+
+// Pass the version and content the editor read so changes made on disk
+// in the meantime are merged or reported instead of overwritten
+export async function saveSshConfig(
+  content: string,
+  path?: string,
+  baseVersion?: string,
+  base?: string,
+): Promise<SaveOutcome> {
+  return invoke("save_ssh_config", { content, path, baseVersion, base });
+}
+
+export async function listSshConfigBackups(path?: string): Promise<ConfigBackup[]> {
+  return invoke("list_ssh_config_backups", { path });
+}
+
+export async function diffSshConfigBackup(id: string): Promise<string> {
+  return invoke("diff_ssh_config_backup", { id });
+}
+
+export async function restoreSshConfigBackup(id: string): Promise<SshHost[]> {
+  return invoke("restore_ssh_config_backup", { id });
 }
 
 // ============ 1Password Commands ============
@@ -81,13 +221,39 @@ export async function getDefaultChainConfigs(): Promise<ChainConfig[]> {
 
 export interface AppSettings {
   default_terminal: string;
+  // Argv template of the "custom" terminal, one argument per item, with
+  // {alias} {host} {user} {port} {ssh_command} {title} placeholders
+  custom_terminal: string[];
   ssh_config_path: string;
+  // Further config files whose hosts are merged into the list
+  additional_config_paths: string[];
+  // Backups kept per config file
+  backup_retention: number;
+  // Lint rules switched on or off by id; rules not listed are on
+  lint_rules: Record<string, boolean>;
+  // OpenSSH release configs are checked against, e.g. "8.9"; null for the latest
+  openssh_version: string | null;
+  // Also check configs with the local `ssh -G`
+  ssh_client_check: boolean;
+  // Rules assigning hosts to groups and tags, in priority order
+  group_rules: GroupRule[];
+  launch_profiles: LaunchProfile[];
+  workspaces: Workspace[];
 }
 
-export async function saveAppSettings(defaultTerminal: string, sshConfigPath: string): Promise<void> {
-  return invoke("save_app_settings", { defaultTerminal, sshConfigPath });
+export async function saveAppSettings(settings: AppSettings): Promise<void> {
+  return invoke("save_app_settings", { settings });
 }
 
 export async function getAppSettings(): Promise<AppSettings> {
   return invoke("get_app_settings");
 }
+
+export async function getDefaultGroupRules(): Promise<GroupRule[]> {
+  return invoke("get_default_group_rules");
+}
+
+// Terminals installed on this machine that ssh can be launched in
+export async function detectTerminals(): Promise<TerminalInfo[]> {
+  return invoke("detect_terminals");
+}
@@ -7,9 +7,250 @@ export interface SshHost {
   identity_file: string | null;
   proxy_jump: string | null;
   is_1password_agent: boolean;
-  group: "direct" | "proxy" | "local" | "github";
+  // Group from the first matching group rule or a `# ssh-m:` comment
+  group: string;
+  tags: string[];
+  source_file: string;
+  source_line: number;
+  // All patterns on the Host line this alias was declared on
+  host_patterns: string[];
+  // From the `#@ssh-m` comments of the block
+  metadata: HostMetadata;
+}
+
+// Fields kept in `#@ssh-m key=value ...` comments above or inside a Host block
+export interface HostMetadata {
+  description: string | null;
+  // Environment label, e.g. "prod"
+  env: string | null;
+  // Colour name or #rrggbb
+  color: string | null;
+  favorite: boolean;
+  owner: string | null;
+  // Any other fields, by lowercase key
+  extra: Record<string, string>;
+}
+
+// A wildcard / negated Host block, e.g. `Host *.corp !bastion`
+export interface SshHostPattern {
+  patterns: string[];
+  source_file: string;
+  source_line: number;
 }
 
 export interface SshHostDetail extends SshHost {
   raw_config: string;
 }
+
+export interface ResolvedOption {
+  keyword: string;
+  value: string;
+  source_file: string | null;
+  source_line: number | null;
+  // Match criteria (e.g. `exec ...`) this value depends on, if any
+  conditional: string | null;
+}
+
+// Effective settings for an alias, as `ssh -G` would compute them
+export interface ResolvedHost {
+  alias: string;
+  hostname: string;
+  user: string;
+  port: number;
+  options: ResolvedOption[];
+  // "ssh_client" when taken from the local `ssh -G`
+  computed_by: "builtin" | "ssh_client";
+}
+
+export interface HostOption {
+  keyword: string;
+  // Raw argument text, written as-is
+  value: string;
+}
+
+// Typed form of a single `Host <alias>` block, for create/update
+export interface SshHostDefinition {
+  alias: string;
+  hostname?: string | null;
+  user?: string | null;
+  port?: number | null;
+  identity_files?: string[];
+  proxy_jump?: string | null;
+  local_forwards?: string[];
+  remote_forwards?: string[];
+  dynamic_forwards?: string[];
+  extra_options?: HostOption[];
+  metadata?: HostMetadata;
+}
+
+// A saved copy of a config file, taken before it was overwritten
+export interface ConfigBackup {
+  id: string;
+  source_path: string;
+  // Unix time in milliseconds
+  created_at: number;
+  size: number;
+}
+
+// A config file as read by the editor, with the version it was read at
+export interface ConfigSnapshot {
+  path: string;
+  content: string;
+  version: string;
+  modified: number | null;
+}
+
+export interface ConflictHunk {
+  // 1-based line of the `<<<<<<<` marker in the merged text
+  line: number;
+  base: string[];
+  ours: string[];
+  theirs: string[];
+}
+
+// The file changed on disk since it was read and the edits couldn't be merged
+export interface SaveConflict {
+  path: string;
+  disk_content: string;
+  disk_version: string;
+  disk_modified: number | null;
+  // Both versions merged with conflict markers
+  merged: string;
+  hunks: ConflictHunk[];
+}
+
+export type SaveOutcome =
+  | { status: "saved"; warnings: Diagnostic[]; version: string }
+  | { status: "merged"; warnings: Diagnostic[]; version: string; content: string }
+  | ({ status: "conflict" } & SaveConflict);
+
+export type Severity = "error" | "warning" | "info";
+
+// Half-open range of character columns within a line, 0-based
+export interface ColumnRange {
+  start: number;
+  end: number;
+}
+
+// 1-based line, 0-based character column; a line past the last one is
+// the end of the file
+export interface TextPosition {
+  line: number;
+  column: number;
+}
+
+export interface TextEdit {
+  start: TextPosition;
+  end: TextPosition;
+  new_text: string;
+}
+
+export interface ChmodFix {
+  path: string;
+  mode: number;
+}
+
+export interface SuggestedFix {
+  message_key: string;
+  args: Record<string, string>;
+  // message_key rendered with args
+  message: string;
+  edits: TextEdit[];
+  chmod: ChmodFix | null;
+}
+
+// A problem found in a config file; render it with formatDiagnostic
+export interface Diagnostic {
+  file: string | null;
+  // 1-based, or 0 for the file as a whole
+  line: number;
+  column_range: ColumnRange;
+  severity: Severity;
+  code: string;
+  message_key: string;
+  args: Record<string, string>;
+  // message_key rendered with args
+  message: string;
+  suggested_fix: SuggestedFix | null;
+}
+
+export interface OpenSshVersions {
+  // Version of the local ssh client, if known
+  installed: string | null;
+  // Releases that added or deprecated a keyword, newest first
+  known: string[];
+}
+
+// A host found by searchSshHosts, best matches first
+export interface HostMatch {
+  host: SshHost;
+  score: number;
+  // Fields the query matched, e.g. "alias" or "tags"
+  matched_fields: string[];
+}
+
+// A host with its connection history
+export interface HostUsageEntry {
+  alias: string;
+  count: number;
+  // Unix milliseconds; 0 if never connected
+  last_connected: number;
+  pinned: boolean;
+}
+
+// Assigns a group and tags to the hosts matching every condition set
+export interface GroupRule {
+  group: string | null;
+  tags: string[];
+  // Pattern list the alias must match, e.g. "prod-*,!prod-test"
+  alias: string | null;
+  hostname: string | null;
+  // Comma-separated networks, e.g. "10.0.0.0/8,fc00::/7"
+  cidr: string | null;
+  proxy_jump: boolean | null;
+  source_file: string | null;
+}
+
+// A named way of connecting: extra ssh options and a remote command,
+// offered for hosts matching the alias patterns and group (all if unset)
+export interface LaunchProfile {
+  name: string;
+  alias: string | null;
+  group: string | null;
+  // e.g. ["-L", "5432:localhost:5432"]
+  ssh_options: string[];
+  // Run instead of a login shell, e.g. "tmux new -A -s main"
+  remote_command: string | null;
+}
+
+// "tmux": tiled panes of one local tmux session; "tabs": a terminal tab
+// (or window) per host
+export type WorkspaceLayout = "tmux" | "tabs";
+
+// A saved set of hosts opened together
+export interface Workspace {
+  name: string;
+  // Aliases or user@host[:port] targets, in pane or tab order
+  hosts: string[];
+  layout: WorkspaceLayout;
+  synchronize_panes: boolean;
+}
+
+// A terminal emulator installed on this machine
+export interface TerminalInfo {
+  // Value for the default_terminal setting
+  id: string;
+  name: string;
+}
+
+export interface LintRuleInfo {
+  id: string;
+  enabled: boolean;
+}
+
+export interface ConfigFixResult {
+  // Patched content, not yet saved
+  content: string;
+  diff: string;
+  applied: Diagnostic[];
+}